@@ -4,7 +4,7 @@ use serde::Deserialize;
 #[allow(dead_code)]
 #[derive(Debug, Deserialize)]
 struct GlyphInfo {
-    // unicode: Option<u32>,
+    unicode: Option<String>,
     // unicode_legacy: Option<String>,
     name: String,
     category: String,
@@ -32,11 +32,9 @@ fn main() {
             continue;
         };
         include.push_str(&format!("{{\"name\": \"{}\", ", name));
-        // if let Some(unicode) = node.attribute("unicode") {
-        //     let codepoint = u32::from_str_radix(unicode, 16)
-        //         .expect(format!("Invalid unicode value: {}", unicode).as_str());
-        //     include.push_str(&format!("unicode: {},", codepoint));
-        // }
+        if let Some(unicode) = node.attribute("unicode") {
+            include.push_str(&format!("\"unicode\": \"{}\",", unicode));
+        }
         if let Some(category) = node.attribute("category") {
             include.push_str(&format!("\"category\": \"{}\",", category));
         }
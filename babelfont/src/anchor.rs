@@ -21,6 +21,21 @@ pub struct Anchor {
     pub format_specific: FormatSpecific,
 }
 
+impl Anchor {
+    /// If this anchor marks a ligature caret (`caret_N` or `vcaret_N`), its index
+    ///
+    /// Returns the (one-based) caret index and whether the caret is vertical.
+    pub fn ligature_caret(&self) -> Option<(usize, bool)> {
+        let (index, vertical) = if let Some(index) = self.name.strip_prefix("vcaret_") {
+            (index, true)
+        } else {
+            (self.name.strip_prefix("caret_")?, false)
+        };
+        let index = index.parse::<usize>().ok().filter(|&i| i > 0)?;
+        Some((index, vertical))
+    }
+}
+
 #[cfg(feature = "ufo")]
 mod ufo {
     use crate::{
@@ -0,0 +1,56 @@
+use std::collections::BTreeSet;
+
+use crate::{
+    checks::{Check, Finding, Severity},
+    Font,
+};
+
+/// Reports anchors which are present in some master layers of a glyph but not others
+#[derive(Debug, Clone, Default)]
+pub struct MissingAnchors;
+
+impl Check for MissingAnchors {
+    fn name(&self) -> &'static str {
+        "missing-anchors"
+    }
+
+    fn description(&self) -> &'static str {
+        "Anchors missing from some of a glyph's masters"
+    }
+
+    fn run(&self, font: &Font) -> Vec<Finding> {
+        let mut findings = vec![];
+        for glyph in font.glyphs.iter() {
+            let layers: Vec<_> = glyph
+                .layers
+                .iter()
+                .filter(|l| l.should_interpolate())
+                .collect();
+            if layers.len() < 2 {
+                continue;
+            }
+            let all: BTreeSet<&str> = layers
+                .iter()
+                .flat_map(|l| l.anchors.iter().map(|a| a.name.as_str()))
+                .collect();
+            for layer in layers {
+                let missing: Vec<&str> = all
+                    .iter()
+                    .filter(|name| !layer.anchors.iter().any(|a| a.name == **name))
+                    .copied()
+                    .collect();
+                if !missing.is_empty() {
+                    findings.push(
+                        Finding::new(
+                            Severity::Warning,
+                            format!("Anchors missing from this layer: {}", missing.join(", ")),
+                        )
+                        .glyph(glyph.name.as_str())
+                        .layer(layer.debug_name()),
+                    );
+                }
+            }
+        }
+        findings
+    }
+}
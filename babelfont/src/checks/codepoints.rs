@@ -0,0 +1,46 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    checks::{Check, Finding, Severity},
+    Font,
+};
+
+/// Reports codepoints which are mapped to more than one glyph
+#[derive(Debug, Clone, Default)]
+pub struct DuplicateCodepoints;
+
+impl Check for DuplicateCodepoints {
+    fn name(&self) -> &'static str {
+        "duplicate-codepoints"
+    }
+
+    fn description(&self) -> &'static str {
+        "Codepoints mapped to more than one glyph"
+    }
+
+    fn run(&self, font: &Font) -> Vec<Finding> {
+        let mut glyphs_for: BTreeMap<u32, Vec<&str>> = BTreeMap::new();
+        for glyph in font.glyphs.iter() {
+            for codepoint in glyph.codepoints.iter() {
+                glyphs_for
+                    .entry(*codepoint)
+                    .or_default()
+                    .push(glyph.name.as_str());
+            }
+        }
+        glyphs_for
+            .into_iter()
+            .filter(|(_, glyphs)| glyphs.len() > 1)
+            .map(|(codepoint, glyphs)| {
+                Finding::new(
+                    Severity::Error,
+                    format!(
+                        "U+{:04X} is mapped to more than one glyph: {}",
+                        codepoint,
+                        glyphs.join(", ")
+                    ),
+                )
+            })
+            .collect()
+    }
+}
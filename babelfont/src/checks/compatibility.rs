@@ -0,0 +1,660 @@
+use std::{collections::BTreeSet, fmt::Display, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    checks::{Check, Finding, Severity},
+    filters::{
+        align_path, match_contours, reference_layer, start_point_offset, winds_opposite,
+        DropIncompatiblePaths, FixStartPoints, FontFilter, MakeCompatible,
+    },
+    BabelfontError, Font, Glyph, Layer, LayerType, NodeType, Path, Shape,
+};
+
+/// A filter which is likely to fix a compatibility problem
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompatibilityFix {
+    /// Reorder, reverse and rotate contours to match the default master
+    /// ([`FixStartPoints`])
+    FixStartPoints,
+    /// Insert nodes so that contours have the same structure
+    /// ([`MakeCompatible`])
+    MakeCompatible,
+    /// Remove the contours which cannot be matched up
+    /// ([`DropIncompatiblePaths`])
+    DropIncompatiblePaths,
+}
+
+impl CompatibilityFix {
+    /// The filter's name on the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            CompatibilityFix::FixStartPoints => "fixstartpoints",
+            CompatibilityFix::MakeCompatible => "makecompatible",
+            CompatibilityFix::DropIncompatiblePaths => "dropincompatiblepaths",
+        }
+    }
+
+    /// The filter which applies this fix
+    pub fn filter(&self) -> Box<dyn FontFilter> {
+        match self {
+            CompatibilityFix::FixStartPoints => Box::new(FixStartPoints::new()),
+            CompatibilityFix::MakeCompatible => Box::new(MakeCompatible::new()),
+            CompatibilityFix::DropIncompatiblePaths => Box::new(DropIncompatiblePaths::new()),
+        }
+    }
+}
+
+impl Display for CompatibilityFix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for CompatibilityFix {
+    type Err = BabelfontError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        [
+            CompatibilityFix::FixStartPoints,
+            CompatibilityFix::MakeCompatible,
+            CompatibilityFix::DropIncompatiblePaths,
+        ]
+        .into_iter()
+        .find(|fix| fix.name() == s)
+        .ok_or_else(|| BabelfontError::FilterError(format!("Unknown compatibility fix: {}", s)))
+    }
+}
+
+/// Identifies a layer in a [`CompatibilityReport`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LayerRef {
+    /// The layer's ID, if it has one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// The layer's name, if it has one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// The ID of the master the layer belongs to, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub master_id: Option<String>,
+    /// The name of the master the layer belongs to, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub master_name: Option<String>,
+}
+
+impl LayerRef {
+    fn new(font: &Font, layer: &Layer) -> Self {
+        let master_id = match &layer.master {
+            LayerType::DefaultForMaster(id) | LayerType::AssociatedWithMaster(id) => {
+                Some(id.clone())
+            }
+            LayerType::FreeFloating => None,
+        };
+        let master_name = master_id.as_ref().and_then(|id| {
+            font.masters
+                .iter()
+                .find(|m| &m.id == id)
+                .and_then(|m| m.name.get_default().cloned())
+        });
+        LayerRef {
+            id: layer.id.clone(),
+            name: layer.name.clone(),
+            master_id,
+            master_name,
+        }
+    }
+}
+
+impl Display for LayerRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = self
+            .name
+            .as_ref()
+            .or(self.id.as_ref())
+            .or(self.master_name.as_ref())
+            .map_or("unnamed layer", |s| s.as_str());
+        write!(f, "'{}'", label)?;
+        if let Some(id) = self.id.as_ref().filter(|&id| id != label) {
+            write!(f, " ({})", id)?;
+        }
+        if let Some(master) = self.master_name.as_ref().filter(|&m| m != label) {
+            write!(f, " in master '{}'", master)?;
+        }
+        Ok(())
+    }
+}
+
+/// What is wrong with a layer, compared to the glyph's reference layer
+///
+/// Shape indices are positions in the layer's list of shapes; contour
+/// indices count paths only.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum IncompatibilityKind {
+    /// The layers have different numbers of shapes
+    ShapeCount {
+        /// The number of shapes in the reference layer
+        expected: usize,
+        /// The number of shapes in this layer
+        found: usize,
+    },
+    /// A shape is a path in one layer and a component in the other
+    ShapeType {
+        /// The index of the shape
+        shape: usize,
+    },
+    /// A component refers to a different glyph
+    ComponentReference {
+        /// The index of the component's shape
+        shape: usize,
+        /// The glyph referred to in the reference layer
+        expected: String,
+        /// The glyph referred to in this layer
+        found: String,
+    },
+    /// The contours are the same, but in a different order
+    ContourOrder {
+        /// For each contour of the reference layer, the index of the
+        /// matching contour in this layer
+        order: Vec<usize>,
+    },
+    /// A contour winds the other way
+    Direction {
+        /// The index of the contour's shape
+        shape: usize,
+    },
+    /// A closed contour starts at a different node
+    StartPoint {
+        /// The index of the contour's shape
+        shape: usize,
+        /// How many nodes the start point needs to move forward by
+        offset: usize,
+    },
+    /// The contours' node structure diverges
+    PathStructure {
+        /// The index of the contour's shape
+        shape: usize,
+        /// The index of the first node whose type differs
+        node: usize,
+        /// The index of the segment containing that node
+        segment: usize,
+    },
+}
+
+impl Display for IncompatibilityKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IncompatibilityKind::ShapeCount { expected, found } => {
+                write!(f, "has {} shapes, expected {}", found, expected)
+            }
+            IncompatibilityKind::ShapeType { shape } => write!(
+                f,
+                "shape {} is a path in one layer and a component in the other",
+                shape
+            ),
+            IncompatibilityKind::ComponentReference {
+                shape,
+                expected,
+                found,
+            } => write!(
+                f,
+                "shape {} is a component of '{}', expected '{}'",
+                shape, found, expected
+            ),
+            IncompatibilityKind::ContourOrder { order } => write!(
+                f,
+                "contours are in a different order ({})",
+                order
+                    .iter()
+                    .map(|ix| ix.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            IncompatibilityKind::Direction { shape } => {
+                write!(f, "contour {} runs in the opposite direction", shape)
+            }
+            IncompatibilityKind::StartPoint { shape, offset } => write!(
+                f,
+                "contour {} starts {} node(s) away from the matching start point",
+                shape, offset
+            ),
+            IncompatibilityKind::PathStructure {
+                shape,
+                node,
+                segment,
+            } => write!(
+                f,
+                "contour {} diverges at node {} (segment {})",
+                shape, node, segment
+            ),
+        }
+    }
+}
+
+/// A problem which stops (or will spoil) interpolation between two layers of a glyph
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompatibilityIssue {
+    /// The glyph's name
+    pub glyph: String,
+    /// The layer with the problem
+    pub layer: LayerRef,
+    /// The layer it was compared against, usually the default master's
+    pub reference: LayerRef,
+    /// What is wrong
+    pub kind: IncompatibilityKind,
+    /// Whether the layers cannot be interpolated at all until this is fixed;
+    /// non-blocking problems interpolate, but badly
+    pub blocking: bool,
+    /// The filter which is likely to fix the problem, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fix: Option<CompatibilityFix>,
+}
+
+impl Display for CompatibilityIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: layer {} vs {}: {}",
+            self.glyph, self.layer, self.reference, self.kind
+        )?;
+        if let Some(fix) = self.fix {
+            write!(f, " (fix: {})", fix)?;
+        }
+        Ok(())
+    }
+}
+
+/// A font-wide report on interpolation compatibility
+///
+/// Every interpolating layer of each glyph is compared against the default
+/// master's layer. Unlike
+/// [`Glyph::compatibility_errors`](crate::Glyph::compatibility_errors), this
+/// also looks for contours which are in the wrong order, wind the wrong way or
+/// start at the wrong node, and suggests a filter to fix each problem.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CompatibilityReport {
+    /// The problems found, grouped by glyph
+    pub issues: Vec<CompatibilityIssue>,
+}
+
+impl CompatibilityReport {
+    /// Build a compatibility report for a font
+    pub fn new(font: &Font) -> Self {
+        let default_master = font.default_master().map(|m| m.id.as_str());
+        CompatibilityReport {
+            issues: font
+                .glyphs
+                .iter()
+                .flat_map(|glyph| glyph_issues(font, glyph, default_master))
+                .collect(),
+        }
+    }
+
+    /// Whether no problems were found
+    pub fn is_empty(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// The names of the glyphs which the given fix applies to
+    pub fn glyphs_for(&self, fix: CompatibilityFix) -> BTreeSet<String> {
+        self.issues
+            .iter()
+            .filter(|issue| issue.fix == Some(fix))
+            .map(|issue| issue.glyph.clone())
+            .collect()
+    }
+
+    /// Apply the given fixes to the glyphs whose issues suggest them
+    ///
+    /// Each fix's filter is run only over the glyphs that need it, and fixes
+    /// are applied in a fixed order (start points, then node insertion, then
+    /// dropping paths) whatever order they are given in. To fix only some
+    /// glyphs, remove the other glyphs' issues from the report first. As one
+    /// fix can change what the next needs to do, it is worth building a fresh
+    /// report afterwards.
+    pub fn apply_fixes(
+        &self,
+        font: &mut Font,
+        fixes: &[CompatibilityFix],
+    ) -> Result<(), BabelfontError> {
+        let fixes: BTreeSet<CompatibilityFix> = fixes.iter().copied().collect();
+        for fix in fixes {
+            let glyphs = self.glyphs_for(fix);
+            if glyphs.is_empty() {
+                continue;
+            }
+            log::info!("Applying {} to {} glyph(s)", fix, glyphs.len());
+            apply_to_glyphs(fix.filter().as_ref(), font, &glyphs)?;
+        }
+        Ok(())
+    }
+}
+
+/// Run a filter over some of a font's glyphs, leaving the others untouched
+///
+/// The filter sees a copy of the whole font, so anything it looks up
+/// elsewhere (component bases, other masters) is still there; only the
+/// selected glyphs are copied back.
+fn apply_to_glyphs(
+    filter: &dyn FontFilter,
+    font: &mut Font,
+    names: &BTreeSet<String>,
+) -> Result<(), BabelfontError> {
+    let mut filtered = font.clone();
+    filter.apply(&mut filtered)?;
+    for name in names {
+        if let (Some(glyph), Some(new)) = (font.glyphs.get_mut(name), filtered.glyphs.get(name)) {
+            *glyph = new.clone();
+        }
+    }
+    Ok(())
+}
+
+fn path_shape_indices(layer: &Layer) -> Vec<usize> {
+    layer
+        .shapes
+        .iter()
+        .enumerate()
+        .filter_map(|(ix, shape)| shape.is_path().then_some(ix))
+        .collect()
+}
+
+/// Where the node types of two paths first differ, as (node, segment)
+fn divergence(reference: &Path, path: &Path) -> (usize, usize) {
+    let (reference_signature, signature) = (reference.signature(), path.signature());
+    let node = reference_signature
+        .iter()
+        .zip(&signature)
+        .position(|(a, b)| a != b)
+        .unwrap_or(reference_signature.len().min(signature.len()));
+    let segment = path.nodes[..node.min(path.nodes.len())]
+        .iter()
+        .filter(|n| n.nodetype != NodeType::OffCurve)
+        .count();
+    (node, segment)
+}
+
+fn glyph_issues(
+    font: &Font,
+    glyph: &Glyph,
+    default_master: Option<&str>,
+) -> Vec<CompatibilityIssue> {
+    let mut issues = vec![];
+    let interpolating: Vec<usize> = glyph
+        .layers
+        .iter()
+        .enumerate()
+        .filter(|(_, layer)| layer.should_interpolate())
+        .map(|(ix, _)| ix)
+        .collect();
+    let Some(reference_ix) = reference_layer(glyph, default_master, &interpolating) else {
+        return issues;
+    };
+    let reference_layer = &glyph.layers[reference_ix];
+    let reference = LayerRef::new(font, reference_layer);
+    let reference_paths: Vec<&Path> = reference_layer.paths().collect();
+    for &layer_ix in interpolating.iter().filter(|&&ix| ix != reference_ix) {
+        let layer = &glyph.layers[layer_ix];
+        let mut report = |kind, blocking, fix| {
+            issues.push(CompatibilityIssue {
+                glyph: glyph.name.to_string(),
+                layer: LayerRef::new(font, layer),
+                reference: reference.clone(),
+                kind,
+                blocking,
+                fix,
+            })
+        };
+
+        if layer.shapes.len() != reference_layer.shapes.len() {
+            let path_counts_differ = layer.paths().count() != reference_paths.len();
+            let components_match =
+                layer.components().count() == reference_layer.components().count();
+            report(
+                IncompatibilityKind::ShapeCount {
+                    expected: reference_layer.shapes.len(),
+                    found: layer.shapes.len(),
+                },
+                true,
+                (path_counts_differ && components_match)
+                    .then_some(CompatibilityFix::DropIncompatiblePaths),
+            );
+            continue;
+        }
+        if let Some(shape) = reference_layer
+            .shapes
+            .iter()
+            .zip(&layer.shapes)
+            .position(|(a, b)| a.is_path() != b.is_path())
+        {
+            report(IncompatibilityKind::ShapeType { shape }, true, None);
+            continue;
+        }
+        for (shape, (a, b)) in reference_layer.shapes.iter().zip(&layer.shapes).enumerate() {
+            if let (Shape::Component(a), Shape::Component(b)) = (a, b) {
+                if a.reference != b.reference {
+                    report(
+                        IncompatibilityKind::ComponentReference {
+                            shape,
+                            expected: a.reference.to_string(),
+                            found: b.reference.to_string(),
+                        },
+                        true,
+                        None,
+                    );
+                }
+            }
+        }
+
+        let paths: Vec<&Path> = layer.paths().collect();
+        let shape_indices = path_shape_indices(layer);
+        let identity: Vec<usize> = (0..paths.len()).collect();
+        let order = match match_contours(&reference_paths, &paths) {
+            Some(order) if order != identity => {
+                let identity_aligns = reference_paths
+                    .iter()
+                    .zip(&paths)
+                    .all(|(a, b)| align_path(a, b).is_some());
+                report(
+                    IncompatibilityKind::ContourOrder {
+                        order: order.clone(),
+                    },
+                    !identity_aligns,
+                    Some(CompatibilityFix::FixStartPoints),
+                );
+                order
+            }
+            _ => identity,
+        };
+        for (reference_path, &ix) in reference_paths.iter().zip(&order) {
+            let (path, shape) = (paths[ix], shape_indices[ix]);
+            if winds_opposite(reference_path, path) && align_path(reference_path, path).is_some() {
+                report(
+                    IncompatibilityKind::Direction { shape },
+                    start_point_offset(reference_path, path).is_none(),
+                    Some(CompatibilityFix::FixStartPoints),
+                );
+                continue;
+            }
+            match start_point_offset(reference_path, path) {
+                Some(0) => {}
+                Some(offset) => report(
+                    IncompatibilityKind::StartPoint { shape, offset },
+                    reference_path.signature() != path.signature(),
+                    Some(CompatibilityFix::FixStartPoints),
+                ),
+                None => {
+                    let (node, segment) = divergence(reference_path, path);
+                    report(
+                        IncompatibilityKind::PathStructure {
+                            shape,
+                            node,
+                            segment,
+                        },
+                        true,
+                        Some(if reference_path.closed && path.closed {
+                            CompatibilityFix::MakeCompatible
+                        } else {
+                            CompatibilityFix::DropIncompatiblePaths
+                        }),
+                    );
+                }
+            }
+        }
+    }
+    issues
+}
+
+/// Reports glyphs whose master layers cannot be interpolated, or will interpolate badly
+///
+/// This uses a [`CompatibilityReport`]; problems which stop interpolation
+/// altogether are errors, and contours which are merely misaligned are
+/// warnings.
+#[derive(Debug, Clone, Default)]
+pub struct InterpolationCompatibility;
+
+impl Check for InterpolationCompatibility {
+    fn name(&self) -> &'static str {
+        "compatibility"
+    }
+
+    fn description(&self) -> &'static str {
+        "Glyphs whose layers are not interpolation compatible"
+    }
+
+    fn run(&self, font: &Font) -> Vec<Finding> {
+        CompatibilityReport::new(font)
+            .issues
+            .into_iter()
+            .map(|issue| {
+                let severity = if issue.blocking {
+                    Severity::Error
+                } else {
+                    Severity::Warning
+                };
+                let mut message = format!("{} (compared to {})", issue.kind, issue.reference);
+                if let Some(fix) = issue.fix {
+                    message.push_str(&format!("; try {}", fix));
+                }
+                Finding::new(severity, message)
+                    .glyph(issue.glyph)
+                    .layer(issue.layer.to_string())
+            })
+            .collect()
+    }
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{checks::tests::font_with_glyphs, Master, Node};
+
+    fn square(x: f64, size: f64) -> Path {
+        let mut path = Path {
+            closed: true,
+            ..Default::default()
+        };
+        for (dx, dy) in [(0.0, 0.0), (size, 0.0), (size, size), (0.0, size)] {
+            path.nodes.push(Node {
+                x: x + dx,
+                y: dy,
+                nodetype: NodeType::Line,
+                ..Default::default()
+            });
+        }
+        path
+    }
+
+    fn layer(master: &str, paths: Vec<Path>) -> Layer {
+        let mut layer = Layer::new(500.0);
+        layer.id = Some(master.into());
+        layer.master = LayerType::DefaultForMaster(master.into());
+        layer.shapes = paths.into_iter().map(Shape::Path).collect();
+        layer
+    }
+
+    #[test]
+    fn test_report_and_fix() {
+        let mut rotated = square(0.0, 200.0);
+        rotated.nodes.rotate_left(1);
+        let mut reversed = square(0.0, 200.0);
+        reversed.reverse();
+        let mut triangle = square(0.0, 200.0);
+        triangle.nodes.pop();
+        let glyph = |name: &str, bold: Vec<Path>| Glyph {
+            name: name.into(),
+            layers: vec![
+                layer("m01", vec![square(0.0, 100.0), square(300.0, 100.0)]),
+                layer("m02", bold),
+            ],
+            ..Default::default()
+        };
+        let mut font = font_with_glyphs(vec![
+            glyph("ok", vec![square(0.0, 200.0), square(300.0, 200.0)]),
+            glyph("start", vec![rotated, square(300.0, 200.0)]),
+            glyph("order", vec![square(300.0, 200.0), square(0.0, 200.0)]),
+            glyph("direction", vec![reversed, square(300.0, 200.0)]),
+            glyph("structure", vec![triangle, square(300.0, 200.0)]),
+        ]);
+        font.masters
+            .push(Master::new("Bold", "m02", Default::default()));
+
+        let report = CompatibilityReport::new(&font);
+        let kinds: Vec<(&str, &IncompatibilityKind, bool)> = report
+            .issues
+            .iter()
+            .map(|issue| (issue.glyph.as_str(), &issue.kind, issue.blocking))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (
+                    "start",
+                    &IncompatibilityKind::StartPoint {
+                        shape: 0,
+                        offset: 3
+                    },
+                    false
+                ),
+                (
+                    "order",
+                    &IncompatibilityKind::ContourOrder { order: vec![1, 0] },
+                    false
+                ),
+                (
+                    "direction",
+                    &IncompatibilityKind::Direction { shape: 0 },
+                    false
+                ),
+                (
+                    "structure",
+                    &IncompatibilityKind::PathStructure {
+                        shape: 0,
+                        node: 3,
+                        segment: 3
+                    },
+                    true
+                ),
+            ]
+        );
+        assert_eq!(
+            report.issues[0].to_string(),
+            "start: layer 'm02' in master 'Bold' vs 'm01' in master 'Regular': contour 0 starts 3 node(s) away from the matching start point (fix: fixstartpoints)"
+        );
+
+        report
+            .apply_fixes(&mut font, &[CompatibilityFix::FixStartPoints])
+            .unwrap();
+        let remaining = CompatibilityReport::new(&font);
+        assert_eq!(remaining.issues.len(), 1);
+        assert_eq!(remaining.issues[0].glyph, "structure");
+        let names: Vec<&str> = font.glyphs.iter().map(|g| g.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["ok", "start", "order", "direction", "structure"]
+        );
+    }
+}
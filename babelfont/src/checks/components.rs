@@ -0,0 +1,116 @@
+use crate::{
+    checks::{Check, Finding, Severity},
+    Font,
+};
+
+/// Reports components which refer to glyphs not in the font
+#[derive(Debug, Clone, Default)]
+pub struct MissingComponents;
+
+impl Check for MissingComponents {
+    fn name(&self) -> &'static str {
+        "missing-components"
+    }
+
+    fn description(&self) -> &'static str {
+        "Components referencing glyphs which do not exist"
+    }
+
+    fn run(&self, font: &Font) -> Vec<Finding> {
+        let mut findings = vec![];
+        for glyph in font.glyphs.iter() {
+            for layer in glyph.layers.iter() {
+                for component in layer.components() {
+                    if font.glyphs.get(&component.reference).is_none() {
+                        findings.push(
+                            Finding::new(
+                                Severity::Error,
+                                format!(
+                                    "Component refers to missing glyph {}",
+                                    component.reference
+                                ),
+                            )
+                            .glyph(glyph.name.as_str())
+                            .layer(layer.debug_name()),
+                        );
+                    }
+                }
+            }
+        }
+        findings
+    }
+}
+
+/// Reports glyphs which use themselves as components, directly or indirectly
+#[derive(Debug, Clone, Default)]
+pub struct ComponentCycles;
+
+impl Check for ComponentCycles {
+    fn name(&self) -> &'static str {
+        "component-cycles"
+    }
+
+    fn description(&self) -> &'static str {
+        "Glyphs which contain themselves as components"
+    }
+
+    fn run(&self, font: &Font) -> Vec<Finding> {
+        let cycles = font.component_graph().cycles();
+        cycles
+            .into_iter()
+            .map(|cycle| {
+                Finding::new(
+                    Severity::Error,
+                    format!("Component cycle: {}", cycle.join(" -> ")),
+                )
+                .glyph(cycle[0].as_str())
+            })
+            .collect()
+    }
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        checks::tests::{font_with_glyphs, master_layer},
+        common::decomposition::DecomposedAffine,
+        Component, Glyph, Shape,
+    };
+
+    fn glyph(name: &str, components: &[&str]) -> Glyph {
+        let mut layer = master_layer("m01");
+        for component in components {
+            layer.shapes.push(Shape::Component(Component {
+                reference: (*component).into(),
+                transform: DecomposedAffine::default(),
+                location: Default::default(),
+                format_specific: Default::default(),
+            }));
+        }
+        Glyph {
+            name: name.into(),
+            layers: vec![layer],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_component_checks() {
+        let font = font_with_glyphs(vec![
+            glyph("A", &["B"]),
+            glyph("B", &["C"]),
+            glyph("C", &["A", "D"]),
+            glyph("E", &["A"]),
+        ]);
+        let cycles = ComponentCycles.run(&font);
+        assert_eq!(cycles.len(), 1);
+        assert_eq!(cycles[0].message, "Component cycle: A -> B -> C -> A");
+
+        let missing = MissingComponents.run(&font);
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].glyph.as_deref(), Some("C"));
+        assert_eq!(missing[0].message, "Component refers to missing glyph D");
+    }
+}
@@ -0,0 +1,41 @@
+use crate::{
+    checks::{Check, Finding, Severity},
+    Font,
+};
+
+/// Reports open contours in exported glyphs
+///
+/// Open contours have no inside, so they disappear when the font is compiled.
+/// Background layers are not checked.
+#[derive(Debug, Clone, Default)]
+pub struct OpenContours;
+
+impl Check for OpenContours {
+    fn name(&self) -> &'static str {
+        "open-contours"
+    }
+
+    fn description(&self) -> &'static str {
+        "Open contours in exported glyphs"
+    }
+
+    fn run(&self, font: &Font) -> Vec<Finding> {
+        let mut findings = vec![];
+        for glyph in font.glyphs.iter().filter(|g| g.exported) {
+            for layer in glyph.layers.iter().filter(|l| !l.is_background) {
+                let open = layer.paths().filter(|p| !p.closed).count();
+                if open > 0 {
+                    findings.push(
+                        Finding::new(
+                            Severity::Warning,
+                            format!("Layer has {} open contour(s)", open),
+                        )
+                        .glyph(glyph.name.as_str())
+                        .layer(layer.debug_name()),
+                    );
+                }
+            }
+        }
+        findings
+    }
+}
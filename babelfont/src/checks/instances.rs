@@ -0,0 +1,84 @@
+use crate::{
+    checks::{Check, Finding, Severity},
+    Font,
+};
+
+/// Reports instances placed outside the range of the font's axes
+#[derive(Debug, Clone, Default)]
+pub struct InstancesOutsideAxes;
+
+impl Check for InstancesOutsideAxes {
+    fn name(&self) -> &'static str {
+        "instance-locations"
+    }
+
+    fn description(&self) -> &'static str {
+        "Instances outside the bounds of the font's axes"
+    }
+
+    fn run(&self, font: &Font) -> Vec<Finding> {
+        let mut findings = vec![];
+        for instance in font.instances.iter() {
+            let name = instance
+                .name
+                .get_default()
+                .cloned()
+                .unwrap_or_else(|| instance.id.clone());
+            for axis in font.axes.iter() {
+                let (Some(coord), Some((min, _, max))) =
+                    (instance.location.get(axis.tag), axis.bounds())
+                else {
+                    continue;
+                };
+                let Ok(user) = axis.designspace_to_userspace(coord) else {
+                    continue;
+                };
+                if user < min || user > max {
+                    findings.push(Finding::new(
+                        Severity::Error,
+                        format!(
+                            "Instance {} is at {}={}, outside the axis range {}-{}",
+                            name,
+                            axis.tag,
+                            user.to_f64(),
+                            min.to_f64(),
+                            max.to_f64()
+                        ),
+                    ));
+                }
+            }
+        }
+        findings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Axis, DesignCoord, DesignLocation, Instance, Tag, UserCoord};
+
+    #[test]
+    fn test_instances_outside_axes() {
+        let mut font = Font::new();
+        let mut axis = Axis::new("Weight", Tag::from_be_bytes(*b"wght"));
+        axis.min = Some(UserCoord::new(100.0));
+        axis.default = Some(UserCoord::new(400.0));
+        axis.max = Some(UserCoord::new(900.0));
+        font.axes.push(axis);
+        for (name, weight) in [("Regular", 400.0), ("Heavy", 1000.0)] {
+            let mut location = DesignLocation::new();
+            location.insert(Tag::from_be_bytes(*b"wght"), DesignCoord::new(weight));
+            font.instances.push(Instance {
+                name: name.into(),
+                location,
+                ..Default::default()
+            });
+        }
+        let findings = InstancesOutsideAxes.run(&font);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(
+            findings[0].message,
+            "Instance Heavy is at wght=1000, outside the axis range 100-900"
+        );
+    }
+}
@@ -0,0 +1,81 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    checks::{Check, Finding, Severity},
+    Font,
+};
+
+/// Reports kerning which refers to glyphs or groups not in the font
+///
+/// Kerning pairs may name glyphs directly, or kerning groups with a `@`
+/// prefix; first-side groups are looked up in the font's first kerning groups
+/// and second-side groups in its second kerning groups. Group members which are
+/// not in the font are also reported.
+#[derive(Debug, Clone, Default)]
+pub struct UnknownKerningReferences;
+
+impl Check for UnknownKerningReferences {
+    fn name(&self) -> &'static str {
+        "kerning-references"
+    }
+
+    fn description(&self) -> &'static str {
+        "Kerning referring to unknown glyphs or groups"
+    }
+
+    fn run(&self, font: &Font) -> Vec<Finding> {
+        let known = |name: &str, first: bool| match name.strip_prefix('@') {
+            Some(group) if first => font.first_kern_groups.contains_key(group),
+            Some(group) => font.second_kern_groups.contains_key(group),
+            None => font.glyphs.get(name).is_some(),
+        };
+        // Count the pairs using each unknown name, rather than reporting every pair
+        let mut unknown: BTreeMap<&str, usize> = BTreeMap::new();
+        for master in font.masters.iter() {
+            for (left, right) in master.kerning.keys() {
+                if !known(left, true) {
+                    *unknown.entry(left.as_str()).or_default() += 1;
+                }
+                if !known(right, false) {
+                    *unknown.entry(right.as_str()).or_default() += 1;
+                }
+            }
+        }
+        let mut findings: Vec<Finding> = unknown
+            .into_iter()
+            .map(|(name, count)| {
+                let what = if name.starts_with('@') {
+                    "group"
+                } else {
+                    "glyph"
+                };
+                Finding::new(
+                    Severity::Error,
+                    format!(
+                        "Kerning refers to unknown {} {} ({} pairs)",
+                        what, name, count
+                    ),
+                )
+            })
+            .collect();
+        for (side, groups) in [
+            ("first", &font.first_kern_groups),
+            ("second", &font.second_kern_groups),
+        ] {
+            for (group, members) in groups.iter() {
+                for member in members {
+                    if font.glyphs.get(member).is_none() {
+                        findings.push(Finding::new(
+                            Severity::Warning,
+                            format!(
+                                "Kerning group {} ({} side) contains unknown glyph {}",
+                                group, side, member
+                            ),
+                        ));
+                    }
+                }
+            }
+        }
+        findings
+    }
+}
@@ -0,0 +1,210 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Font;
+
+mod anchors;
+mod codepoints;
+mod compatibility;
+mod components;
+mod contours;
+mod instances;
+mod kerning;
+mod reachability;
+
+pub use anchors::MissingAnchors;
+pub use codepoints::DuplicateCodepoints;
+pub use compatibility::{
+    CompatibilityFix, CompatibilityIssue, CompatibilityReport, IncompatibilityKind,
+    InterpolationCompatibility, LayerRef,
+};
+pub use components::{ComponentCycles, MissingComponents};
+pub use contours::OpenContours;
+pub use instances::InstancesOutsideAxes;
+pub use kerning::UnknownKerningReferences;
+pub use reachability::UnreachableGlyphs;
+
+/// How serious a problem is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Worth knowing about, but probably intentional
+    Info,
+    /// Likely to be a mistake
+    Warning,
+    /// Will produce a broken or incorrect font
+    Error,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Info => write!(f, "info"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A problem found by a [`Check`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Finding {
+    /// The name of the check which found the problem
+    pub check: String,
+    /// How serious the problem is
+    pub severity: Severity,
+    /// A description of the problem
+    pub message: String,
+    /// The glyph the problem is in, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub glyph: Option<String>,
+    /// The layer the problem is in, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layer: Option<String>,
+}
+
+impl Finding {
+    /// Create a new finding; the check name is filled in when the check is run
+    pub fn new(severity: Severity, message: impl Into<String>) -> Self {
+        Finding {
+            check: String::new(),
+            severity,
+            message: message.into(),
+            glyph: None,
+            layer: None,
+        }
+    }
+
+    /// Attach the finding to a glyph
+    pub fn glyph(mut self, glyph: impl Into<String>) -> Self {
+        self.glyph = Some(glyph.into());
+        self
+    }
+
+    /// Attach the finding to a layer
+    pub fn layer(mut self, layer: impl Into<String>) -> Self {
+        self.layer = Some(layer.into());
+        self
+    }
+}
+
+impl Display for Finding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} [{}] ", self.severity, self.check)?;
+        match (&self.glyph, &self.layer) {
+            (Some(glyph), Some(layer)) => write!(f, "{} ({}): ", glyph, layer)?,
+            (Some(glyph), None) => write!(f, "{}: ", glyph)?,
+            _ => {}
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+/// The results of running checks over a font
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Report {
+    /// All problems found, in the order the checks were run
+    pub findings: Vec<Finding>,
+}
+
+impl Report {
+    /// The severity of the most serious problem found, if any
+    pub fn worst(&self) -> Option<Severity> {
+        self.findings.iter().map(|f| f.severity).max()
+    }
+
+    /// Whether any errors were found
+    pub fn has_errors(&self) -> bool {
+        self.worst() == Some(Severity::Error)
+    }
+}
+
+/// A check for problems in a font source
+pub trait Check {
+    /// A short, unique name for the check (e.g. `duplicate-codepoints`)
+    fn name(&self) -> &'static str;
+
+    /// A one-line description of what the check looks for
+    fn description(&self) -> &'static str;
+
+    /// Look for problems in the font
+    fn run(&self, font: &Font) -> Vec<Finding>;
+}
+
+/// All the built-in checks
+pub fn all_checks() -> Vec<Box<dyn Check>> {
+    vec![
+        Box::new(InterpolationCompatibility),
+        Box::new(DuplicateCodepoints),
+        Box::new(MissingComponents),
+        Box::new(ComponentCycles),
+        Box::new(OpenContours),
+        Box::new(MissingAnchors),
+        Box::new(UnknownKerningReferences),
+        Box::new(UnreachableGlyphs),
+        Box::new(InstancesOutsideAxes),
+    ]
+}
+
+/// Run the given checks over a font
+pub fn run_checks(font: &Font, checks: &[Box<dyn Check>]) -> Report {
+    let mut report = Report::default();
+    for check in checks {
+        log::info!("Running check {}", check.name());
+        report
+            .findings
+            .extend(check.run(font).into_iter().map(|finding| Finding {
+                check: check.name().to_string(),
+                ..finding
+            }));
+    }
+    report
+}
+
+/// Run all the built-in checks over a font
+pub fn check_font(font: &Font) -> Report {
+    run_checks(font, &all_checks())
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Glyph, Layer, LayerType, Master};
+
+    pub(crate) fn font_with_glyphs(glyphs: Vec<Glyph>) -> Font {
+        let mut font = Font::new();
+        font.masters
+            .push(Master::new("Regular", "m01", Default::default()));
+        font.glyphs = glyphs.into();
+        font
+    }
+
+    pub(crate) fn master_layer(id: &str) -> Layer {
+        let mut layer = Layer::new(500.0);
+        layer.id = Some(id.into());
+        layer.master = LayerType::DefaultForMaster(id.into());
+        layer
+    }
+
+    #[test]
+    fn test_report() {
+        let glyph = |name: &str| Glyph {
+            name: name.into(),
+            codepoints: vec![0x41],
+            exported: true,
+            layers: vec![master_layer("m01")],
+            ..Default::default()
+        };
+        let font = font_with_glyphs(vec![glyph("A"), glyph("A.alt")]);
+        let report = check_font(&font);
+        assert_eq!(report.findings.len(), 1);
+        assert_eq!(report.findings[0].check, "duplicate-codepoints");
+        assert!(report.has_errors());
+        assert_eq!(
+            report.findings[0].to_string(),
+            "error [duplicate-codepoints] U+0041 is mapped to more than one glyph: A, A.alt"
+        );
+    }
+}
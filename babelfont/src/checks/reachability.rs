@@ -0,0 +1,66 @@
+use std::collections::HashSet;
+
+use smol_str::SmolStr;
+
+use crate::{
+    checks::{Check, Finding, Severity},
+    close_layout, Font,
+};
+
+/// Reports exported glyphs which can never be displayed
+///
+/// A glyph is reachable if it is encoded, can be reached from an encoded glyph
+/// through the font's layout rules, or is used as a component of a reachable
+/// glyph. Exported glyphs which are none of these only take up space.
+#[derive(Debug, Clone, Default)]
+pub struct UnreachableGlyphs;
+
+impl Check for UnreachableGlyphs {
+    fn name(&self) -> &'static str {
+        "unreachable-glyphs"
+    }
+
+    fn description(&self) -> &'static str {
+        "Exported glyphs which are not encoded or reachable through layout rules"
+    }
+
+    fn run(&self, font: &Font) -> Vec<Finding> {
+        let encoded: HashSet<SmolStr> = font
+            .glyphs
+            .iter()
+            .filter(|g| !g.codepoints.is_empty() || g.name == ".notdef")
+            .map(|g| g.name.clone())
+            .collect();
+        let mut reachable = match close_layout(font, encoded) {
+            Ok(reachable) => reachable,
+            Err(e) => {
+                return vec![Finding::new(
+                    Severity::Warning,
+                    format!("Could not follow the font's layout rules: {}", e),
+                )]
+            }
+        };
+        let mut todo: Vec<SmolStr> = reachable.iter().cloned().collect();
+        while let Some(name) = todo.pop() {
+            let Some(glyph) = font.glyphs.get(&name) else {
+                continue;
+            };
+            for component in glyph.layers.iter().flat_map(|l| l.components()) {
+                if reachable.insert(component.reference.clone()) {
+                    todo.push(component.reference.clone());
+                }
+            }
+        }
+        font.glyphs
+            .iter()
+            .filter(|g| g.exported && !reachable.contains(&g.name))
+            .map(|g| {
+                Finding::new(
+                    Severity::Info,
+                    "Glyph is exported but not encoded or reachable through layout rules",
+                )
+                .glyph(g.name.as_str())
+            })
+            .collect()
+    }
+}
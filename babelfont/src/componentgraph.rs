@@ -0,0 +1,344 @@
+use std::collections::{HashMap, HashSet};
+
+use indexmap::{IndexMap, IndexSet};
+use smol_str::SmolStr;
+
+use crate::{BabelfontError, Font, Glyph};
+
+/// Which glyphs use which others as components
+///
+/// The graph is a snapshot of the font when it was built, and does not notice
+/// later edits. If glyphs are added, removed or have their components changed
+/// afterwards, either build a new graph or tell this one about the changed
+/// glyphs with [ComponentGraph::update_glyph] and
+/// [ComponentGraph::remove_glyph]; [ComponentGraph::is_current] checks that
+/// this was done.
+///
+/// Components in all layers, including backgrounds, are taken into account.
+/// References to glyphs which are not in the font are kept, so they show up
+/// as dependencies, but such glyphs are never part of an ordering.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ComponentGraph {
+    /// Each glyph in the font, in font order, and the glyphs it uses directly
+    uses: IndexMap<SmolStr, IndexSet<SmolStr>>,
+    /// The glyphs which use each glyph directly
+    used_by: HashMap<SmolStr, IndexSet<SmolStr>>,
+}
+
+fn references(glyph: &Glyph) -> IndexSet<SmolStr> {
+    glyph
+        .layers
+        .iter()
+        .flat_map(|layer| layer.components())
+        .map(|component| component.reference.clone())
+        .collect()
+}
+
+/// Every glyph reachable from `start` by following `edges`
+fn reachable<'a>(
+    start: &str,
+    edges: impl Fn(&str) -> Option<&'a IndexSet<SmolStr>>,
+) -> IndexSet<SmolStr> {
+    let mut found = IndexSet::new();
+    let mut todo: Vec<&SmolStr> = edges(start).into_iter().flatten().collect();
+    while let Some(name) = todo.pop() {
+        if found.insert(name.clone()) {
+            todo.extend(edges(name).into_iter().flatten());
+        }
+    }
+    found
+}
+
+impl ComponentGraph {
+    /// Build the component graph of a font
+    pub fn new(font: &Font) -> Self {
+        let mut graph = ComponentGraph::default();
+        for glyph in font.glyphs.iter() {
+            graph.update_glyph(glyph);
+        }
+        graph
+    }
+
+    /// Record the current components of a glyph, adding it to the graph if needed
+    pub fn update_glyph(&mut self, glyph: &Glyph) {
+        self.unlink(&glyph.name);
+        let components = references(glyph);
+        for component in components.iter() {
+            self.used_by
+                .entry(component.clone())
+                .or_default()
+                .insert(glyph.name.clone());
+        }
+        self.uses.insert(glyph.name.clone(), components);
+    }
+
+    /// Remove a glyph from the graph
+    ///
+    /// Glyphs which used it still do so; it becomes a missing component.
+    pub fn remove_glyph(&mut self, name: &str) {
+        self.unlink(name);
+        self.uses.shift_remove(name);
+    }
+
+    fn unlink(&mut self, name: &str) {
+        for component in self.uses.get(name).into_iter().flatten() {
+            if let Some(users) = self.used_by.get_mut(component) {
+                users.shift_remove(name);
+            }
+        }
+    }
+
+    /// Whether the graph still describes the font's glyphs and their components
+    ///
+    /// This costs about as much as building a new graph, so it is mostly
+    /// useful to check, for example in a debug assertion, that a graph kept
+    /// across edits was told about them.
+    pub fn is_current(&self, font: &Font) -> bool {
+        self.uses.len() == font.glyphs.len()
+            && font.glyphs.iter().all(|glyph| {
+                self.uses
+                    .get(&glyph.name)
+                    .is_some_and(|uses| *uses == references(glyph))
+            })
+    }
+
+    /// The glyphs a glyph uses directly as components
+    pub fn components_of(&self, glyph: &str) -> impl Iterator<Item = &SmolStr> {
+        self.uses.get(glyph).into_iter().flatten()
+    }
+
+    /// The glyphs which use a glyph directly as a component
+    pub fn users_of(&self, glyph: &str) -> impl Iterator<Item = &SmolStr> {
+        self.used_by.get(glyph).into_iter().flatten()
+    }
+
+    /// Everything a glyph uses as a component, directly or through other components
+    ///
+    /// A glyph only appears in its own dependencies if it is part of a cycle.
+    pub fn dependencies(&self, glyph: &str) -> IndexSet<SmolStr> {
+        reachable(glyph, |name| self.uses.get(name))
+    }
+
+    /// Every glyph which uses a glyph as a component, directly or through other components
+    pub fn dependents(&self, glyph: &str) -> IndexSet<SmolStr> {
+        reachable(glyph, |name| self.used_by.get(name))
+    }
+
+    /// How deeply a glyph's components are nested
+    ///
+    /// A glyph without components has depth 0, one made only of such glyphs
+    /// has depth 1, and so on. Missing components count as depth 0. Returns
+    /// `None` for glyphs which are not in the font, or which are in or lead to
+    /// a cycle.
+    pub fn depth(&self, glyph: &str) -> Option<usize> {
+        fn depth_of<'a>(
+            graph: &'a ComponentGraph,
+            name: &'a str,
+            visiting: &mut HashSet<&'a str>,
+            known: &mut HashMap<&'a str, usize>,
+        ) -> Option<usize> {
+            if let Some(depth) = known.get(name) {
+                return Some(*depth);
+            }
+            let Some(components) = graph.uses.get(name) else {
+                return Some(0);
+            };
+            if !visiting.insert(name) {
+                return None;
+            }
+            let mut depth = 0;
+            for component in components {
+                depth = depth.max(depth_of(graph, component, visiting, known)? + 1);
+            }
+            visiting.remove(name);
+            known.insert(name, depth);
+            Some(depth)
+        }
+        if !self.uses.contains_key(glyph) {
+            return None;
+        }
+        depth_of(self, glyph, &mut HashSet::new(), &mut HashMap::new())
+    }
+
+    /// Depth-first walk from every glyph in font order, calling `done` as each
+    /// glyph is finished and `cycle` with each cycle found
+    fn walk<'a>(&'a self, mut done: impl FnMut(&'a SmolStr), mut cycle: impl FnMut(Vec<SmolStr>)) {
+        enum State {
+            Visiting,
+            Done,
+        }
+        fn visit<'a>(
+            graph: &'a ComponentGraph,
+            name: &'a SmolStr,
+            state: &mut HashMap<&'a str, State>,
+            stack: &mut Vec<&'a SmolStr>,
+            done: &mut impl FnMut(&'a SmolStr),
+            cycle: &mut impl FnMut(Vec<SmolStr>),
+        ) {
+            state.insert(name, State::Visiting);
+            stack.push(name);
+            for component in graph.components_of(name) {
+                match state.get(component.as_str()) {
+                    None if graph.uses.contains_key(component) => {
+                        visit(graph, component, state, stack, done, cycle)
+                    }
+                    Some(State::Visiting) => {
+                        if let Some(start) = stack.iter().position(|n| *n == component) {
+                            let mut found: Vec<SmolStr> =
+                                stack[start..].iter().map(|n| (*n).clone()).collect();
+                            found.push(component.clone());
+                            cycle(found);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            stack.pop();
+            state.insert(name, State::Done);
+            done(name);
+        }
+        let mut state = HashMap::new();
+        for name in self.uses.keys() {
+            if !state.contains_key(name.as_str()) {
+                visit(self, name, &mut state, &mut vec![], &mut done, &mut cycle);
+            }
+        }
+    }
+
+    /// Every component cycle in the font, each starting and ending with the same glyph
+    pub fn cycles(&self) -> Vec<Vec<SmolStr>> {
+        let mut cycles = vec![];
+        self.walk(|_| {}, |cycle| cycles.push(cycle));
+        cycles
+    }
+
+    /// The glyphs of the font ordered so that components come before the
+    /// glyphs which use them
+    ///
+    /// Glyphs in a cycle are included once each, in no particular order with
+    /// respect to each other; use [ComponentGraph::topological_order] to
+    /// refuse fonts with cycles instead.
+    pub fn bottom_up(&self) -> Vec<SmolStr> {
+        let mut order = Vec::with_capacity(self.uses.len());
+        self.walk(|name| order.push(name.clone()), |_| {});
+        order
+    }
+
+    /// The glyphs of the font ordered so that components come before the
+    /// glyphs which use them, or an error if there is a component cycle
+    pub fn topological_order(&self) -> Result<Vec<SmolStr>, BabelfontError> {
+        if let Some(cycle) = self.cycles().into_iter().next() {
+            return Err(BabelfontError::ComponentCycle {
+                glyphs: cycle.iter().map(|g| g.to_string()).collect(),
+            });
+        }
+        Ok(self.bottom_up())
+    }
+}
+
+impl Font {
+    /// Build a graph of which glyphs use which others as components
+    ///
+    /// The graph is a snapshot: it does not follow later changes to the
+    /// font's glyphs. Rebuild it after changing components, or keep it up to
+    /// date with [ComponentGraph::update_glyph] and
+    /// [ComponentGraph::remove_glyph].
+    pub fn component_graph(&self) -> ComponentGraph {
+        ComponentGraph::new(self)
+    }
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{common::decomposition::DecomposedAffine, Component, Layer, Shape};
+
+    fn glyph(name: &str, components: &[&str]) -> Glyph {
+        let mut layer = Layer::new(500.0);
+        for component in components {
+            layer.shapes.push(Shape::Component(Component {
+                reference: (*component).into(),
+                transform: DecomposedAffine::default(),
+                location: Default::default(),
+                format_specific: Default::default(),
+            }));
+        }
+        Glyph {
+            name: name.into(),
+            layers: vec![layer],
+            ..Default::default()
+        }
+    }
+
+    fn names<'a>(names: impl IntoIterator<Item = &'a SmolStr>) -> Vec<&'a str> {
+        names.into_iter().map(|n| n.as_str()).collect()
+    }
+
+    #[test]
+    fn test_component_graph() {
+        let mut font = Font::new();
+        for g in [
+            glyph("Aacute", &["A", "acutecomb.case"]),
+            glyph("acutecomb.case", &["acutecomb"]),
+            glyph("A", &[]),
+            glyph("acutecomb", &[]),
+            glyph("Aogonek", &["A", "ogonekcomb"]),
+        ] {
+            font.glyphs.push(g);
+        }
+        let mut graph = font.component_graph();
+        assert_eq!(names(graph.users_of("A")), vec!["Aacute", "Aogonek"]);
+        let mut dependencies = names(&graph.dependencies("Aacute"));
+        dependencies.sort();
+        assert_eq!(dependencies, vec!["A", "acutecomb", "acutecomb.case"]);
+        let mut dependents = names(&graph.dependents("acutecomb"));
+        dependents.sort();
+        assert_eq!(dependents, vec!["Aacute", "acutecomb.case"]);
+        assert_eq!(graph.depth("Aacute"), Some(2));
+        assert_eq!(graph.depth("Aogonek"), Some(1));
+        assert_eq!(graph.depth("ogonekcomb"), None);
+        assert_eq!(
+            names(&graph.topological_order().unwrap()),
+            vec!["A", "acutecomb", "acutecomb.case", "Aacute", "Aogonek"]
+        );
+
+        graph.update_glyph(&glyph("acutecomb", &["Aacute"]));
+        assert_eq!(
+            graph.cycles(),
+            vec![vec![
+                SmolStr::from("Aacute"),
+                "acutecomb.case".into(),
+                "acutecomb".into(),
+                "Aacute".into()
+            ]]
+        );
+        assert!(graph.topological_order().is_err());
+        assert_eq!(graph.depth("Aacute"), None);
+        assert_eq!(graph.bottom_up().len(), 5);
+
+        graph.remove_glyph("acutecomb");
+        assert!(graph.cycles().is_empty());
+        assert_eq!(names(graph.users_of("Aacute")), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn test_is_current() {
+        let mut font = Font::new();
+        font.glyphs.push(glyph("A", &[]));
+        font.glyphs.push(glyph("Aacute", &["A", "acutecomb"]));
+        let mut graph = font.component_graph();
+        assert!(graph.is_current(&font));
+
+        let aacute = font.glyphs.get_mut("Aacute").unwrap();
+        aacute.layers[0].shapes.pop();
+        assert!(!graph.is_current(&font));
+        graph.update_glyph(font.glyphs.get("Aacute").unwrap());
+        assert!(graph.is_current(&font));
+
+        font.glyphs.push(glyph("acutecomb", &[]));
+        assert!(!graph.is_current(&font));
+        font.glyphs.remove("acutecomb");
+        assert!(graph.is_current(&font));
+    }
+}
@@ -1,3 +1,9 @@
+mod registry;
+pub(crate) use registry::first_json_key;
+pub use registry::{register, registry, Capabilities, Convertor, ConvertorRegistry};
+
+/// Babelfont JSON convertor
+pub mod babelfont;
 #[cfg(feature = "fontir")]
 /// fontc's Font Intermediate Representation (FIR) convertor
 pub mod fontir;
@@ -0,0 +1,50 @@
+use std::path::Path;
+
+use crate::{
+    convertors::{first_json_key, Capabilities, Convertor},
+    BabelfontError, Font,
+};
+#[cfg(feature = "cli")]
+extern crate serde_json_path_to_error as serde_json;
+
+/// Convertor for Babelfont's own JSON serialization
+pub struct BabelfontConvertor;
+
+impl Convertor for BabelfontConvertor {
+    fn name(&self) -> &str {
+        "Babelfont"
+    }
+
+    fn extensions(&self) -> &[&'static str] {
+        &["babelfont"]
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            load: true,
+            load_bytes: true,
+            save: true,
+        }
+    }
+
+    fn handles_bytes(&self, bytes: &[u8]) -> bool {
+        first_json_key(bytes) == Some("upm")
+    }
+
+    fn load(&self, path: &Path) -> Result<Font, BabelfontError> {
+        let buffered = std::io::BufReader::new(std::fs::File::open(path)?);
+        serde_json::from_reader(buffered).map_err(|e| BabelfontError::source_parse(path, e))
+    }
+
+    fn load_bytes(&self, bytes: &[u8], path: Option<&Path>) -> Result<Font, BabelfontError> {
+        serde_json::from_slice(bytes)
+            .map_err(|e| BabelfontError::source_parse(path.unwrap_or(Path::new("<memory>")), e))
+    }
+
+    fn save(&self, font: &Font, path: &Path) -> Result<(), BabelfontError> {
+        let file = std::fs::File::create(path)?;
+        let mut buffer = std::io::BufWriter::new(file);
+        serde_json::to_writer_pretty(&mut buffer, font)?;
+        Ok(())
+    }
+}
@@ -1,5 +1,6 @@
 use crate::{
     convertors::ufo::{as_norad, load_kerning, stash_lib, KEY_LIB},
+    convertors::{Capabilities, Convertor},
     glyph::GlyphList,
     names::Names,
     I18NDictionary, Instance, Layer, Tag,
@@ -24,17 +25,56 @@ pub const STYLENAME_KEY: &str = "norad.designspace.style";
 
 use crate::{
     convertors::ufo::{load_master_info, norad_glyph_to_babelfont_layer},
-    Axis, BabelfontError, Font, Master,
+    Axis, BabelfontError, Diagnostic, Diagnostics, Font, Master,
 };
 
+/// Convertor for DesignSpace documents and their UFO sources
+pub struct DesignspaceConvertor;
+
+impl Convertor for DesignspaceConvertor {
+    fn name(&self) -> &str {
+        "DesignSpace"
+    }
+
+    fn extensions(&self) -> &[&'static str] {
+        &["designspace"]
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            load: true,
+            load_bytes: false,
+            save: true,
+        }
+    }
+
+    fn load(&self, path: &std::path::Path) -> Result<Font, BabelfontError> {
+        load(path.to_path_buf())
+    }
+
+    fn save(&self, font: &Font, path: &std::path::Path) -> Result<(), BabelfontError> {
+        save_designspace(font, &path.to_path_buf())
+    }
+}
+
 /// Load a DesignSpace document and all referenced UFOs into a Babelfont Font
 pub fn load(path: PathBuf) -> Result<Font, BabelfontError> {
     let ds: DesignSpaceDocument = norad::designspace::DesignSpaceDocument::load(path.clone())?;
     let relative = path.parent();
+    let mut diagnostics = Diagnostics::default();
     let axes: Vec<Axis> = ds
         .axes
         .iter()
-        .filter_map(|dsax| dsax.try_into().ok())
+        .filter_map(|dsax| match Axis::try_from(dsax) {
+            Ok(axis) => Some(axis),
+            Err(e) => {
+                diagnostics.push(Diagnostic::warning(
+                    "designspace.invalid-axis",
+                    format!("Ignoring axis {}: {}", dsax.name, e),
+                ));
+                None
+            }
+        })
         .collect();
     #[allow(clippy::unwrap_used)] // We put a default there
     let axis_name_tag_map: HashMap<String, crate::Tag> = axes
@@ -54,7 +94,22 @@ pub fn load(path: PathBuf) -> Result<Font, BabelfontError> {
     let res: Vec<(Master, Vec<Vec<Layer>>)> = ds
         .sources
         .iter()
-        .filter_map(|source| load_master(&font.glyphs, source, relative, &axis_name_tag_map).ok())
+        .filter_map(|source| {
+            load_master(&font.glyphs, source, relative, &axis_name_tag_map)
+                .map_err(|e| {
+                    diagnostics.push(
+                        Diagnostic::error(
+                            "designspace.source-not-loaded",
+                            format!("Could not load source: {}", e),
+                        )
+                        .file(relative.map_or_else(
+                            || PathBuf::from(&source.filename),
+                            |r| r.join(&source.filename),
+                        )),
+                    )
+                })
+                .ok()
+        })
         .collect();
     // Drop the default master loaded from the UFO above
     font.masters.clear();
@@ -62,6 +117,7 @@ pub fn load(path: PathBuf) -> Result<Font, BabelfontError> {
     for g in font.glyphs.iter_mut() {
         g.layers.clear();
     }
+    font.diagnostics.extend(diagnostics);
     for (master, mut layerset) in res {
         font.masters.push(master);
         for (g, l) in font.glyphs.iter_mut().zip(layerset.iter_mut()) {
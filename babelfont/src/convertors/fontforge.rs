@@ -18,10 +18,11 @@ use crate::{
         offsetmetrics::{compute_font_bbox_y, compute_offset_delta},
         utf7::decode_utf7,
     },
+    convertors::{Capabilities, Convertor},
     features::PossiblyAutomaticCode,
     names::ot_lang_id_to_layout_tag,
-    BabelfontError, Component, Font, FormatSpecific, Glyph, GlyphCategory, Guide, Layer, LayerType,
-    MetricType, NameId, Path, Shape,
+    BabelfontError, Component, Diagnostic, Font, FormatSpecific, Glyph, GlyphCategory, Guide,
+    Layer, LayerType, MetricType, NameId, Path, Shape,
 };
 use indexmap::IndexMap;
 use smol_str::SmolStr;
@@ -70,6 +71,8 @@ struct SfdParser {
     // (and their anchors) have been parsed, so that the anchor Y coordinate can
     // serve as a final fallback signal.
     anchor_class_decls: Vec<(String, String)>,
+    // Glyph keys we don't understand, and how many glyphs they appeared in
+    unhandled_glyph_keys: IndexMap<String, usize>,
     content: Option<String>, // Optional pre-loaded content for load_str()
 }
 
@@ -138,6 +141,7 @@ impl SfdParser {
             sanitized_lookup_names: HashMap::new(),
             chain_pos_sub: IndexMap::new(),
             anchor_class_decls: Vec::new(),
+            unhandled_glyph_keys: IndexMap::new(),
             content: None,
         }
     }
@@ -155,6 +159,7 @@ impl SfdParser {
             sanitized_lookup_names: HashMap::new(),
             chain_pos_sub: IndexMap::new(),
             anchor_class_decls: Vec::new(),
+            unhandled_glyph_keys: IndexMap::new(),
             content: Some(content),
         }
     }
@@ -166,6 +171,12 @@ impl SfdParser {
         self.resolve_offset_metrics()?;
         self.process_kerning()?;
         self.insert_gtables();
+        for (key, count) in std::mem::take(&mut self.unhandled_glyph_keys) {
+            self.font.diagnostics.push(Diagnostic::info(
+                "sfd.unhandled-glyph-key",
+                format!("Ignored glyph key {} ({} occurrences)", key, count),
+            ));
+        }
         Ok(self.font)
     }
 
@@ -557,7 +568,10 @@ impl SfdParser {
                                 .or_default()
                                 .push((lang_id, name.to_string()));
                         } else {
-                            println!("Warning: invalid OTFeatureName format: {}", v);
+                            self.font.diagnostics.push(Diagnostic::warning(
+                                "sfd.invalid-feature-name",
+                                format!("Invalid OTFeatureName format: {}", v),
+                            ));
                         }
                     }
                 }
@@ -1406,7 +1420,16 @@ impl SfdParser {
                     if let Some((subtable_name, glyphs)) = self.parse_oneline_layout(value) {
                         let Some(subtable) = self.gsub_lookups.find_subtable_mut(&subtable_name)
                         else {
-                            log::error!("Ligature2 references unknown subtable: {}", subtable_name);
+                            self.font.diagnostics.push(
+                                Diagnostic::error(
+                                    "sfd.unknown-subtable",
+                                    format!(
+                                        "Ligature2 references unknown subtable: {}",
+                                        subtable_name
+                                    ),
+                                )
+                                .glyph(glyph_name),
+                            );
                             continue;
                         };
                         subtable.push(fea_rs_ast::Statement::LigatureSubst(
@@ -1417,17 +1440,29 @@ impl SfdParser {
                 "Substitution2" => {
                     if let Some((subtable_name, glyphs)) = self.parse_oneline_layout(value) {
                         let Some(replacement) = glyphs.first() else {
-                            log::error!(
-                                "Substitution2 has no replacement glyph for {}",
-                                glyph_name
+                            self.font.diagnostics.push(
+                                Diagnostic::error(
+                                    "sfd.missing-replacement",
+                                    format!(
+                                        "Substitution2 has no replacement glyph for {}",
+                                        glyph_name
+                                    ),
+                                )
+                                .glyph(glyph_name),
                             );
                             continue;
                         };
                         let Some(subtable) = self.gsub_lookups.find_subtable_mut(&subtable_name)
                         else {
-                            log::error!(
-                                "Substitution2 references unknown subtable: {}",
-                                subtable_name
+                            self.font.diagnostics.push(
+                                Diagnostic::error(
+                                    "sfd.unknown-subtable",
+                                    format!(
+                                        "Substitution2 references unknown subtable: {}",
+                                        subtable_name
+                                    ),
+                                )
+                                .glyph(glyph_name),
                             );
                             continue;
                         };
@@ -1446,14 +1481,29 @@ impl SfdParser {
                 "AlternateSubs2" => {
                     if let Some((subtable_name, glyphs)) = self.parse_oneline_layout(value) {
                         if glyphs.is_empty() {
-                            log::error!("AlternateSubs2 has no replacements for {}", glyph_name);
+                            self.font.diagnostics.push(
+                                Diagnostic::error(
+                                    "sfd.missing-replacement",
+                                    format!(
+                                        "AlternateSubs2 has no replacements for {}",
+                                        glyph_name
+                                    ),
+                                )
+                                .glyph(glyph_name),
+                            );
                             continue;
                         }
                         let Some(subtable) = self.gsub_lookups.find_subtable_mut(&subtable_name)
                         else {
-                            log::error!(
-                                "AlternateSubs2 references unknown subtable: {}",
-                                subtable_name
+                            self.font.diagnostics.push(
+                                Diagnostic::error(
+                                    "sfd.unknown-subtable",
+                                    format!(
+                                        "AlternateSubs2 references unknown subtable: {}",
+                                        subtable_name
+                                    ),
+                                )
+                                .glyph(glyph_name),
                             );
                             continue;
                         };
@@ -1477,14 +1527,26 @@ impl SfdParser {
                 "MultipleSubs2" => {
                     if let Some((subtable_name, glyphs)) = self.parse_oneline_layout(value) {
                         if glyphs.is_empty() {
-                            log::error!("MultipleSubs2 has no replacements for {}", glyph_name);
+                            self.font.diagnostics.push(
+                                Diagnostic::error(
+                                    "sfd.missing-replacement",
+                                    format!("MultipleSubs2 has no replacements for {}", glyph_name),
+                                )
+                                .glyph(glyph_name),
+                            );
                             continue;
                         }
                         let Some(subtable) = self.gsub_lookups.find_subtable_mut(&subtable_name)
                         else {
-                            log::error!(
-                                "MultipleSubs2 references unknown subtable: {}",
-                                subtable_name
+                            self.font.diagnostics.push(
+                                Diagnostic::error(
+                                    "sfd.unknown-subtable",
+                                    format!(
+                                        "MultipleSubs2 references unknown subtable: {}",
+                                        subtable_name
+                                    ),
+                                )
+                                .glyph(glyph_name),
                             );
                             continue;
                         };
@@ -1503,12 +1565,30 @@ impl SfdParser {
                 "Position2" => {
                     if let Some((subtable_name, tokens)) = self.parse_oneline_layout(value) {
                         let Some(vr) = Self::parse_pos_value_record(&tokens) else {
-                            log::error!("Position2 has invalid value record for {}", glyph_name);
+                            self.font.diagnostics.push(
+                                Diagnostic::error(
+                                    "sfd.invalid-value-record",
+                                    format!(
+                                        "Position2 has invalid value record for {}",
+                                        glyph_name
+                                    ),
+                                )
+                                .glyph(glyph_name),
+                            );
                             continue;
                         };
                         let Some(subtable) = self.gpos_lookups.find_subtable_mut(&subtable_name)
                         else {
-                            log::error!("Position2 references unknown subtable: {}", subtable_name);
+                            self.font.diagnostics.push(
+                                Diagnostic::error(
+                                    "sfd.unknown-subtable",
+                                    format!(
+                                        "Position2 references unknown subtable: {}",
+                                        subtable_name
+                                    ),
+                                )
+                                .glyph(glyph_name),
+                            );
                             continue;
                         };
                         subtable.push(fea_rs_ast::Statement::SinglePos(
@@ -1525,27 +1605,54 @@ impl SfdParser {
                 "PairPos2" => {
                     if let Some((subtable_name, tokens)) = self.parse_oneline_layout(value) {
                         if tokens.len() < 9 {
-                            log::error!("PairPos2 has insufficient tokens for {}", glyph_name);
+                            self.font.diagnostics.push(
+                                Diagnostic::error(
+                                    "sfd.invalid-layout-rule",
+                                    format!("PairPos2 has insufficient tokens for {}", glyph_name),
+                                )
+                                .glyph(glyph_name),
+                            );
                             continue;
                         }
                         let right = &tokens[0];
                         let Some(vr1) = Self::parse_pos_value_record(&tokens[1..5]) else {
-                            log::error!(
-                                "PairPos2 first value record is invalid for {}",
-                                glyph_name
+                            self.font.diagnostics.push(
+                                Diagnostic::error(
+                                    "sfd.invalid-value-record",
+                                    format!(
+                                        "PairPos2 first value record is invalid for {}",
+                                        glyph_name
+                                    ),
+                                )
+                                .glyph(glyph_name),
                             );
                             continue;
                         };
                         let Some(vr2) = Self::parse_pos_value_record(&tokens[5..9]) else {
-                            log::error!(
-                                "PairPos2 second value record is invalid for {}",
-                                glyph_name
+                            self.font.diagnostics.push(
+                                Diagnostic::error(
+                                    "sfd.invalid-value-record",
+                                    format!(
+                                        "PairPos2 second value record is invalid for {}",
+                                        glyph_name
+                                    ),
+                                )
+                                .glyph(glyph_name),
                             );
                             continue;
                         };
                         let Some(subtable) = self.gpos_lookups.find_subtable_mut(&subtable_name)
                         else {
-                            log::error!("PairPos2 references unknown subtable: {}", subtable_name);
+                            self.font.diagnostics.push(
+                                Diagnostic::error(
+                                    "sfd.unknown-subtable",
+                                    format!(
+                                        "PairPos2 references unknown subtable: {}",
+                                        subtable_name
+                                    ),
+                                )
+                                .glyph(glyph_name),
+                            );
                             continue;
                         };
                         subtable.push(fea_rs_ast::Statement::PairPos(
@@ -1562,6 +1669,10 @@ impl SfdParser {
                 }
                 _ => {
                     log::debug!("Unhandled FontForge glyph key: {}", key);
+                    *self
+                        .unhandled_glyph_keys
+                        .entry(key.to_string())
+                        .or_default() += 1;
                 }
             }
         }
@@ -1659,7 +1770,13 @@ impl SfdParser {
 
         // Convert OpenType language ID to OT layout tag
         let Some(otl_tag) = ot_lang_id_to_layout_tag(lang_id) else {
-            log::warn!("Unknown OpenType language ID: {}", lang_id);
+            self.font.diagnostics.push(Diagnostic::warning(
+                "sfd.unknown-language",
+                format!(
+                    "Ignoring names for unknown OpenType language ID {}",
+                    lang_id
+                ),
+            ));
             return;
         };
 
@@ -2540,7 +2657,7 @@ impl SfdParser {
             .map(|g| g.name.to_string())
             .collect();
 
-        for glyph in &mut self.font.glyphs.0 {
+        for glyph in self.font.glyphs.iter_mut() {
             for layer in &mut glyph.layers {
                 // Extract and process stored references
                 if let Some(serde_json::Value::Array(refer_array)) =
@@ -2732,7 +2849,10 @@ impl SfdParser {
         }
 
         let Some(caps) = CHAIN_POSSUB_RE.captures(possub[0]) else {
-            log::error!("Failed to parse ChainPosSub header: {}", possub[0]);
+            self.font.diagnostics.push(Diagnostic::error(
+                "sfd.invalid-chain-rule",
+                format!("Failed to parse ChainPosSub header: {}", possub[0]),
+            ));
             return;
         };
 
@@ -3230,6 +3350,74 @@ impl SfdParser {
     }
 }
 
+/// Convertor for FontForge SFD files
+pub struct FontforgeConvertor;
+
+impl Convertor for FontforgeConvertor {
+    fn name(&self) -> &str {
+        "FontForge SFD"
+    }
+
+    fn extensions(&self) -> &[&'static str] {
+        &["sfd"]
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            load: true,
+            load_bytes: true,
+            save: true,
+        }
+    }
+
+    fn handles_bytes(&self, bytes: &[u8]) -> bool {
+        bytes.starts_with(b"SplineFontDB:")
+    }
+
+    fn load(&self, path: &std::path::Path) -> Result<Font, BabelfontError> {
+        load(path.to_path_buf())
+    }
+
+    fn load_bytes(
+        &self,
+        bytes: &[u8],
+        _path: Option<&std::path::Path>,
+    ) -> Result<Font, BabelfontError> {
+        let content = std::str::from_utf8(bytes)
+            .map_err(|e| BabelfontError::General(format!("SFD is not valid UTF-8: {}", e)))?;
+        load_str(content)
+    }
+
+    fn save(&self, font: &Font, path: &std::path::Path) -> Result<(), BabelfontError> {
+        save_sfd(font, &path.to_path_buf())
+    }
+}
+
+/// Convertor for FontForge SFDir directories
+pub struct SfdirConvertor;
+
+impl Convertor for SfdirConvertor {
+    fn name(&self) -> &str {
+        "FontForge SFDir"
+    }
+
+    fn extensions(&self) -> &[&'static str] {
+        &["sfdir"]
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            load: true,
+            load_bytes: false,
+            save: false,
+        }
+    }
+
+    fn load(&self, path: &std::path::Path) -> Result<Font, BabelfontError> {
+        load(path.to_path_buf())
+    }
+}
+
 /// Load a FontForge SFD font or SFDir from a file path
 pub fn load(path: PathBuf) -> Result<Font, BabelfontError> {
     SfdParser::new(path).into_font()
@@ -4900,7 +5088,7 @@ mod tests {
         // fixture, b.glyph has GID 1 and a.glyph has GID 2.
         let font =
             load(PathBuf::from("resources/fontforge/simple.sfdir")).expect("Failed to load SFDir");
-        let names: Vec<&str> = font.glyphs.0.iter().map(|g| g.name.as_str()).collect();
+        let names: Vec<&str> = font.glyphs.iter().map(|g| g.name.as_str()).collect();
         assert_eq!(names, vec![".notdef", "b", "a"]);
         assert_eq!(font.upm, 1000); // Ascent 800 + Descent 200
         assert_eq!(
@@ -1,13 +1,25 @@
-use std::sync::Arc;
+use std::{
+    ops::Range,
+    sync::{Arc, LazyLock},
+};
 
+use fea_rs_ast::AsFea;
 use fontdrasil::orchestration::Work;
 use fontir::{
-    error::Error,
+    error::{BadSourceKind, Error},
     ir::FeaturesSource,
     orchestration::{Context, WorkId},
 };
+use indexmap::IndexMap;
+use regex::Regex;
+
+use crate::{
+    filters::{master_user_locations, variable_metric},
+    BabelfontError, FeatureSourceMap, Font, LayerType,
+};
 
-use crate::Font;
+#[allow(clippy::unwrap_used)]
+static GDEF_END: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\}\s*GDEF\s*;").unwrap());
 
 #[derive(Debug)]
 pub struct FeatureWork {
@@ -28,10 +40,16 @@ impl Work<Context, WorkId, Error> for FeatureWork {
             .and_then(|p| p.parent())
             .map(|p| p.to_path_buf());
 
-        context.features.set(to_ir_features(
-            &Some(self.font.features.to_fea()),
-            include_dir,
-        )?);
+        let (fea, _) = compiled_features(&self.font).map_err(|e| {
+            Error::BadSource(fontir::error::BadSource::new(
+                self.font.source.clone().unwrap_or("unknown source".into()),
+                BadSourceKind::Custom(format!("Error generating ligature carets: {e}")),
+            ))
+        })?;
+
+        context
+            .features
+            .set(to_ir_features(&Some(fea), include_dir)?);
         Ok(())
     }
 }
@@ -48,3 +66,226 @@ pub(crate) fn to_ir_features(
         include_dir,
     })
 }
+
+/// The feature code which is compiled for a font, and where it came from
+///
+/// This is the font's own feature code with any ligature carets added.
+pub(crate) fn compiled_features(font: &Font) -> Result<(String, FeatureSourceMap), BabelfontError> {
+    let (mut fea, mut map) = font.features.to_fea_with_source_map();
+    let carets = ligature_carets(font)?;
+    if !carets.is_empty() {
+        if let Some(inserted) = add_ligature_carets(&mut fea, &carets) {
+            map.insert_generated(inserted);
+        }
+    }
+    Ok((fea, map))
+}
+
+/// Add ligature caret statements to feature code
+///
+/// If the code already has a `table GDEF` block, the statements are added to
+/// it; otherwise a new block is appended. Carets are not added if the code
+/// already defines any, as fea-rs does not allow them to be defined twice.
+/// Returns where the new code was put, if it was added.
+pub(crate) fn add_ligature_carets(fea: &mut String, carets: &[String]) -> Option<Range<usize>> {
+    if fea.contains("LigatureCaretBy") {
+        log::warn!(
+            "Not generating ligature carets from anchors; the font's features already define ligature carets"
+        );
+        return None;
+    }
+    let existing = fea
+        .find("table GDEF")
+        .and_then(|start| GDEF_END.find_at(fea, start))
+        .map(|end| end.start());
+    let (at, code) = match existing {
+        Some(end) => {
+            let separator = if fea[..end].ends_with('\n') { "" } else { "\n" };
+            (end, format!("{}{}\n", separator, carets.join("\n")))
+        }
+        None => (
+            fea.len(),
+            format!("\ntable GDEF {{\n{}\n}} GDEF;\n", carets.join("\n")),
+        ),
+    };
+    fea.insert_str(at, &code);
+    Some(at..at + code.len())
+}
+
+/// Ligature caret statements for a GDEF table, built from `caret_N` and `vcaret_N` anchors
+///
+/// As in ufo2ft, horizontal carets take their position from the anchor's x
+/// coordinate and vertical carets from its y coordinate. The set of carets is
+/// taken from the default master; positions vary across masters.
+pub(crate) fn ligature_carets(font: &Font) -> Result<Vec<String>, BabelfontError> {
+    let masters = master_user_locations(font)?;
+    let default_master = font
+        .default_master()
+        .ok_or(BabelfontError::NoDefaultMaster)?;
+    let mut statements = vec![];
+    for glyph in font.glyphs.iter().filter(|g| g.exported) {
+        let layer_for = |master_id: &str| {
+            glyph
+                .layers
+                .iter()
+                .find(|layer| layer.master == LayerType::DefaultForMaster(master_id.to_string()))
+        };
+        let Some(default_layer) = layer_for(&default_master.id) else {
+            continue;
+        };
+        let mut carets = IndexMap::new();
+        for anchor in default_layer.anchors.iter() {
+            let Some((index, vertical)) = anchor.ligature_caret() else {
+                continue;
+            };
+            let mut values = vec![];
+            for (master, location) in masters.iter() {
+                let Some(master_anchor) = layer_for(&master.id)
+                    .and_then(|layer| layer.anchors.iter().find(|a| a.name == anchor.name))
+                else {
+                    continue;
+                };
+                let position = if vertical {
+                    master_anchor.y
+                } else {
+                    master_anchor.x
+                };
+                values.push((location.clone(), position.round() as i16));
+            }
+            carets.insert(index, variable_metric(values));
+        }
+        if carets.is_empty() {
+            continue;
+        }
+        carets.sort_keys();
+        statements.push(format!(
+            "    LigatureCaretByPos {} {};",
+            glyph.name,
+            carets
+                .values()
+                .map(|metric| metric.as_fea(""))
+                .collect::<Vec<_>>()
+                .join(" ")
+        ));
+    }
+    Ok(statements)
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        features::PossiblyAutomaticCode, Anchor, Axis, DesignCoord, DesignLocation,
+        FeatureCodeOrigin, FeatureError, Features, Glyph, Layer, Master, Tag, UserCoord,
+    };
+
+    #[test]
+    fn test_variable_ligature_carets() {
+        let mut font = Font::new();
+        let mut axis = Axis::new("Weight", Tag::new(b"wght"));
+        axis.min = Some(UserCoord::new(400.0));
+        axis.default = Some(UserCoord::new(400.0));
+        axis.max = Some(UserCoord::new(700.0));
+        font.axes.push(axis);
+        let mut glyph = Glyph {
+            name: "f_i".into(),
+            exported: true,
+            ..Default::default()
+        };
+        for (id, weight, caret) in [("m01", 400.0, 300.0), ("m02", 700.0, 320.0)] {
+            font.masters.push(Master::new(
+                id,
+                id,
+                DesignLocation::from(vec![(Tag::new(b"wght"), DesignCoord::new(weight))]),
+            ));
+            let mut layer = Layer::new(600.0);
+            layer.master = LayerType::DefaultForMaster(id.into());
+            for (name, x, y) in [("caret_1", caret, 0.0), ("top_1", 100.0, 500.0)] {
+                layer.anchors.push(Anchor {
+                    name: name.into(),
+                    x,
+                    y,
+                    ..Default::default()
+                });
+            }
+            glyph.layers.push(layer);
+        }
+        font.glyphs.push(glyph);
+
+        let carets = ligature_carets(&font).unwrap();
+        assert_eq!(
+            carets,
+            vec!["    LigatureCaretByPos f_i (wght=400:300 wght=700:320);"]
+        );
+
+        let mut fea = "feature liga { sub f i by f_i; } liga;".to_string();
+        add_ligature_carets(&mut fea, &carets);
+        assert!(fea.ends_with(
+            "table GDEF {\n    LigatureCaretByPos f_i (wght=400:300 wght=700:320);\n} GDEF;\n"
+        ));
+    }
+
+    #[test]
+    fn test_ligature_carets_merge_into_gdef() {
+        let carets = vec!["    LigatureCaretByPos f_i 300;".to_string()];
+        let mut fea = "table GDEF {\n    GlyphClassDef [f], [f_i], , ;\n} GDEF;\n".to_string();
+        add_ligature_carets(&mut fea, &carets);
+        assert_eq!(
+            fea,
+            "table GDEF {\n    GlyphClassDef [f], [f_i], , ;\n    LigatureCaretByPos f_i 300;\n} GDEF;\n"
+        );
+
+        let manual = "table GDEF {\n    LigatureCaretByIndex f_i 3;\n} GDEF;\n".to_string();
+        let mut fea = manual.clone();
+        add_ligature_carets(&mut fea, &carets);
+        assert_eq!(fea, manual);
+    }
+
+    #[test]
+    fn test_compiled_feature_errors() {
+        let mut original = Features::default();
+        original.prefixes.insert(
+            "gdef".into(),
+            PossiblyAutomaticCode::new(
+                "table GDEF {\n    GlyphClassDef [f], [f_i], , ;\n} GDEF;\nlookup bad {\n    sub a by ;\n} bad;",
+            ),
+        );
+        original.features.push((
+            "liga".into(),
+            PossiblyAutomaticCode::new("sub f i by f_i;\nsub f l by ;"),
+        ));
+        // As if a filter had rewritten the feature before compiling
+        let mut compiled = original.clone();
+        compiled.features[0].1.code = format!("# Rewritten\n{}", compiled.features[0].1.code);
+        let (mut fea, mut map) = compiled.to_fea_with_source_map();
+        let carets = vec!["    LigatureCaretByPos f_i 300 ;".to_string()];
+        map.insert_generated(add_ligature_carets(&mut fea, &carets).unwrap());
+
+        let error_at = |text: &str| {
+            let start = fea.find(text).unwrap();
+            FeatureError::new("Oops".to_string(), start..start + 1, true)
+        };
+        let error = BabelfontError::FeatureParsing(vec![
+            error_at("300 ;"),
+            error_at("a by ;"),
+            error_at("f l by ;"),
+        ])
+        .with_compiled_feature_sources(&fea, &map, &original, None);
+        let BabelfontError::FeatureParsing(errors) = &error else {
+            panic!("Expected a feature parsing error");
+        };
+        let located: Vec<_> = errors
+            .iter()
+            .map(|e| (e.origin.clone().unwrap(), e.line))
+            .collect();
+        assert_eq!(
+            located,
+            vec![
+                (FeatureCodeOrigin::Generated, None),
+                (FeatureCodeOrigin::Prefix("gdef".into()), Some(5)),
+                (FeatureCodeOrigin::Feature("liga".into()), Some(2)),
+            ]
+        );
+    }
+}
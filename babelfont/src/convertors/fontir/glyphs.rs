@@ -152,7 +152,12 @@ impl Work<Context, WorkId, Error> for GlyphIrWork {
             // we only care about anchors from exportable glyphs
             // https://github.com/googlefonts/fontc/issues/1397
             if glyph.exported && !self.options.skip_outlines {
-                for anchor in layer.anchors.iter() {
+                // Ligature carets become GDEF carets in the feature work, not attachments
+                for anchor in layer
+                    .anchors
+                    .iter()
+                    .filter(|a| a.ligature_caret().is_none())
+                {
                     ir_anchors.add(
                         anchor.name.clone().into(),
                         location.clone(),
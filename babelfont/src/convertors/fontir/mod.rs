@@ -1,11 +1,13 @@
 use crate::{
-    convertors::fontir::varc::insert_varc_table,
+    convertors::{
+        fontir::varc::insert_varc_table, glyphs3::KEY_CUSTOM_PARAMETERS, Capabilities, Convertor,
+    },
     error::FeatureError,
     filters::{
         DropIncompatiblePaths, FontFilter as _, GlyphsBracketLayers, GlyphsData, GlyphsNumberValue,
-        GlyphsStylisticSetLabel, RetainGlyphs, RewriteSmartAxes,
+        GlyphsStylisticSetLabel, PropagateAnchors, RetainGlyphs, RewriteSmartAxes,
     },
-    BabelfontError, Font,
+    BabelfontError, Features, Font,
 };
 use fontc::Options;
 use fontdrasil::{
@@ -66,6 +68,33 @@ impl Default for CompilationOptions {
     }
 }
 
+/// Convertor which compiles fonts to TrueType binaries with fontc
+pub struct FontirConvertor;
+
+impl Convertor for FontirConvertor {
+    fn name(&self) -> &str {
+        "fontc"
+    }
+
+    fn extensions(&self) -> &[&'static str] {
+        &["ttf"]
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            load: false,
+            load_bytes: false,
+            save: true,
+        }
+    }
+
+    fn save(&self, font: &Font, path: &std::path::Path) -> Result<(), BabelfontError> {
+        let bytes = BabelfontIrSource::compile(font.clone(), CompilationOptions::default())?;
+        std::fs::write(path, bytes)?;
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone)]
 /// A FontIR source for a Babelfont Font
 pub struct BabelfontIrSource {
@@ -95,6 +124,7 @@ impl BabelfontIrSource {
 
     /// Compile the Babelfont Font to a font binary
     pub fn compile(font: Font, options: CompilationOptions) -> Result<Vec<u8>, BabelfontError> {
+        let original_features = font.features.clone();
         let mut font = font.clone();
         assert!(!font.masters.is_empty());
         if options.drop_incompatible_paths {
@@ -104,6 +134,12 @@ impl BabelfontIrSource {
         if options.produce_varc_table {
             RewriteSmartAxes.apply(&mut font)?;
         }
+        // Glyphs.app gives composites the anchors of their components; this must
+        // happen before unexported components are decomposed away
+        if should_propagate_anchors(&font) {
+            PropagateAnchors.apply(&mut font)?;
+        }
+
         // Unexported glyphs - decompose and drop
         RetainGlyphs::new(
             font.glyphs
@@ -143,7 +179,7 @@ impl BabelfontIrSource {
             options,
         };
         let mut binary = fontc::generate_font(Box::new(source.clone()), Options::default())
-            .map_err(improve_ir_error)?;
+            .map_err(|e| improve_ir_error(e, &source.font, &original_features))?;
         if !source.font.cross_axis_mappings.is_empty() {
             binary = avar2::insert_avar2_table(&binary, &source.font)?;
         }
@@ -155,21 +191,45 @@ impl BabelfontIrSource {
     }
 }
 
-fn improve_ir_error(e: fontc::Error) -> BabelfontError {
+/// Whether the font comes from Glyphs.app and has not turned off anchor propagation
+fn should_propagate_anchors(font: &Font) -> bool {
+    let from_glyphs = font
+        .source
+        .as_ref()
+        .and_then(|p| p.extension())
+        .is_some_and(|ext| ext == "glyphs" || ext == "glyphspackage");
+    let disabled = font
+        .format_specific
+        .get(&format!("{}Propagate Anchors", KEY_CUSTOM_PARAMETERS))
+        .and_then(|cp| cp.get("value"))
+        .is_some_and(|value| value.as_bool() == Some(false) || value.as_i64() == Some(0));
+    from_glyphs && !disabled
+}
+
+/// Turn a compilation error into a BabelfontError, pointing any feature code
+/// errors back at the font's own feature code
+///
+/// `font` is the font as it was compiled, after preprocessing, and `original`
+/// the feature code of the font as it was given to us.
+fn improve_ir_error(e: fontc::Error, font: &Font, original: &Features) -> BabelfontError {
     match e {
         fontc::Error::Backend(fontbe::error::Error::FeaCompileError(x)) => {
             if let Some(diags) = x.diagnostics() {
+                let (fea, map) = features::compiled_features(font)
+                    .unwrap_or_else(|_| font.features.to_fea_with_source_map());
                 BabelfontError::FeatureParsing(
                     diags
                         .diagnostics()
                         .iter()
-                        .map(|d| FeatureError {
-                            message: d.message.text.clone(),
-                            span: d.span(),
-                            is_error: d.is_error(),
-                        })
+                        .map(|d| FeatureError::new(d.message.text.clone(), d.span(), d.is_error()))
                         .collect(),
                 )
+                .with_compiled_feature_sources(
+                    &fea,
+                    &map,
+                    original,
+                    font.source.as_deref(),
+                )
             } else {
                 BabelfontError::General(format!(
                     "Feature compilation error: {}",
@@ -1,7 +1,8 @@
 use crate::{
     common::{decomposition::TransformOrder, tag_from_string},
-    Anchor, Axis, BabelfontError, Component, Font, Glyph, GlyphCategory, Layer, LayerType, Master,
-    MetricType, Node, NodeType, Path, Shape, Tag,
+    convertors::{Capabilities, Convertor},
+    Anchor, Axis, BabelfontError, Component, Diagnostic, Font, Glyph, GlyphCategory, Layer,
+    LayerType, Master, MetricType, Node, NodeType, Path, Shape, Tag,
 };
 use fontdrasil::{
     coords::{DesignCoord, DesignLocation, Location, UserCoord},
@@ -670,6 +671,45 @@ struct FontlabFontWrapper {
     font: FontlabFont,
 }
 
+/// Convertor for FontLab VFJ files
+pub struct FontlabConvertor;
+
+impl Convertor for FontlabConvertor {
+    fn name(&self) -> &str {
+        "FontLab VFJ"
+    }
+
+    fn extensions(&self) -> &[&'static str] {
+        &["vfj"]
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            load: true,
+            load_bytes: true,
+            save: false,
+        }
+    }
+
+    fn handles_bytes(&self, bytes: &[u8]) -> bool {
+        crate::convertors::first_json_key(bytes) == Some("version")
+    }
+
+    fn load(&self, path: &std::path::Path) -> Result<Font, BabelfontError> {
+        load(path.to_path_buf())
+    }
+
+    fn load_bytes(
+        &self,
+        bytes: &[u8],
+        _path: Option<&std::path::Path>,
+    ) -> Result<Font, BabelfontError> {
+        let s = std::str::from_utf8(bytes)
+            .map_err(|e| BabelfontError::General(format!("Couldn't parse VFJ: {:}", e)))?;
+        load_str(s)
+    }
+}
+
 /// Load a Fontlab VFJ font from a file path
 pub fn load(path: PathBuf) -> Result<Font, BabelfontError> {
     let s = fs::read_to_string(&path)?;
@@ -686,6 +726,12 @@ pub fn load_str(contents: &str) -> Result<Font, BabelfontError> {
     let fontlab = p.font;
     // log::debug!("{:#?}", fontlab);
     for axis in fontlab.axes {
+        if tag_from_string(&axis.tag).is_err() {
+            font.diagnostics.push(Diagnostic::warning(
+                "vfj.invalid-axis-tag",
+                format!("Invalid tag '{}' for axis {}", axis.tag, axis.name),
+            ));
+        }
         let sn = axis.shortName.clone();
         let new_axis: Axis = axis.into();
         axes_short_name_to_tag.insert(sn, new_axis.tag);
@@ -6,8 +6,9 @@ use serde_json::Value;
 use std::{collections::HashMap, path::PathBuf};
 
 use crate::{
-    BabelfontError, CustomOTValues, Font, FormatSpecific, Glyph, GlyphList, LayerType, Master,
-    Position,
+    convertors::{Capabilities, Convertor},
+    BabelfontError, CustomOTValues, Diagnostic, Font, FormatSpecific, Glyph, GlyphList, LayerType,
+    Master, Position,
 };
 
 fn custom_data_to_format_specific(cd: &HashMap<String, Value>) -> FormatSpecific {
@@ -18,6 +19,31 @@ fn custom_data_to_format_specific(cd: &HashMap<String, Value>) -> FormatSpecific
     fs
 }
 
+/// Convertor for Fontra font directories
+pub struct FontraConvertor;
+
+impl Convertor for FontraConvertor {
+    fn name(&self) -> &str {
+        "Fontra"
+    }
+
+    fn extensions(&self) -> &[&'static str] {
+        &["fontra"]
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            load: true,
+            load_bytes: false,
+            save: false,
+        }
+    }
+
+    fn load(&self, path: &std::path::Path) -> Result<Font, BabelfontError> {
+        load(path.to_path_buf())
+    }
+}
+
 pub fn load(path: PathBuf) -> Result<Font, BabelfontError> {
     let mut font_data: FontraFont = serde_json::from_str(
         &std::fs::read_to_string(path.join("font-data.json"))
@@ -56,12 +82,19 @@ pub fn load(path: PathBuf) -> Result<Font, BabelfontError> {
     for results in rdr.records() {
         let record = results.map_err(|e| BabelfontError::IO(e.to_string()))?;
         let glyph_name = record.get(0).unwrap_or_default().to_string();
-        let codepoints: Vec<u32> = record
-            .get(1)
-            .unwrap_or_default()
-            .split_whitespace()
-            .filter_map(|s| u32::from_str_radix(&s.replace("U+", ""), 16).ok())
-            .collect();
+        let mut codepoints: Vec<u32> = vec![];
+        for codepoint in record.get(1).unwrap_or_default().split_whitespace() {
+            match u32::from_str_radix(&codepoint.replace("U+", ""), 16) {
+                Ok(codepoint) => codepoints.push(codepoint),
+                Err(_) => our_font.diagnostics.push(
+                    Diagnostic::warning(
+                        "fontra.invalid-codepoint",
+                        format!("Ignoring invalid codepoint {}", codepoint),
+                    )
+                    .glyph(glyph_name.as_str()),
+                ),
+            }
+        }
         let mut other_info = HashMap::new();
         for (i, field) in record.iter().enumerate().skip(2) {
             let header = &header_names[i];
@@ -115,6 +148,15 @@ pub fn load(path: PathBuf) -> Result<Font, BabelfontError> {
                         .iter()
                         .map(|(source_id, layer)| load_layer(layer, source_map.get(source_id)))
                         .collect();
+                } else {
+                    our_font.diagnostics.push(
+                        Diagnostic::warning(
+                            "fontra.unknown-glyph",
+                            "Dropping glyph data for a glyph missing from glyph-info.csv",
+                        )
+                        .file(&path)
+                        .glyph(glyph_data.name.as_str()),
+                    );
                 }
             }
         }
@@ -207,6 +249,7 @@ impl TryFrom<FontraFont> for Font {
             second_kern_groups: Default::default(),
             format_specific: Default::default(),
             source: Default::default(),
+            diagnostics: Default::default(),
         };
 
         // Populate the glyphs vector from the glyphs HashMap
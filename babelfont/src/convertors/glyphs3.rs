@@ -1,9 +1,10 @@
 use crate::{
     common::FormatSpecific,
+    convertors::{Capabilities, Convertor},
     glyph::{self, glyphs::glyph_to_glyphs},
     i18ndictionary::I18NDictionary,
     names::Names,
-    Axis, BabelfontError, CustomOTValues, Font, GlyphList, Master, Tag,
+    Axis, BabelfontError, CustomOTValues, Diagnostic, Diagnostics, Font, GlyphList, Master, Tag,
 };
 use fontdrasil::coords::{DesignCoord, DesignLocation, UserCoord};
 use glyphslib::glyphs3::{self, Property};
@@ -122,11 +123,59 @@ pub(crate) fn copy_user_data(
     }
 }
 
+/// Convertor for Glyphs 2 and 3 files and packages
+pub struct GlyphsConvertor;
+
+impl Convertor for GlyphsConvertor {
+    fn name(&self) -> &str {
+        "Glyphs"
+    }
+
+    fn extensions(&self) -> &[&'static str] {
+        &["glyphs", "glyphspackage"]
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            load: true,
+            load_bytes: true,
+            save: true,
+        }
+    }
+
+    fn handles_bytes(&self, bytes: &[u8]) -> bool {
+        // Glyphs files are property lists which open with the app version
+        let head = &bytes[..bytes.len().min(256)];
+        head.iter().find(|b| !b.is_ascii_whitespace()) == Some(&b'{')
+            && head.windows(11).any(|w| w == b".appVersion")
+    }
+
+    fn load(&self, path: &std::path::Path) -> Result<Font, BabelfontError> {
+        load(path.to_path_buf())
+    }
+
+    fn load_bytes(
+        &self,
+        bytes: &[u8],
+        path: Option<&std::path::Path>,
+    ) -> Result<Font, BabelfontError> {
+        let path = path.map(|p| p.to_path_buf()).unwrap_or_default();
+        let s = std::str::from_utf8(bytes).map_err(|e| BabelfontError::source_parse(&path, e))?;
+        load_str(s, path)
+    }
+
+    fn save(&self, font: &Font, path: &std::path::Path) -> Result<(), BabelfontError> {
+        font.as_glyphslib()?
+            .save(path)
+            .map_err(|x| BabelfontError::PlistParse(x.to_string()))
+    }
+}
+
 /// Load a Glyphs font from a file path
 pub fn load(path: PathBuf) -> Result<Font, BabelfontError> {
     if path.extension().and_then(|x| x.to_str()) == Some("glyphspackage") {
         return _load(
-            &glyphslib::Font::load(&path).map_err(|x| BabelfontError::PlistParse(x.to_string()))?,
+            &glyphslib::Font::load(&path).map_err(|x| BabelfontError::source_parse(&path, x))?,
             path,
         );
     }
@@ -137,7 +186,7 @@ pub fn load(path: PathBuf) -> Result<Font, BabelfontError> {
 /// Load a Glyphs font from a string
 pub fn load_str(s: &str, path: PathBuf) -> Result<Font, BabelfontError> {
     let glyphs_font =
-        glyphslib::Font::load_str(s).map_err(|x| BabelfontError::PlistParse(x.to_string()))?;
+        glyphslib::Font::load_str(s).map_err(|x| BabelfontError::source_parse(&path, x))?;
     _load(&glyphs_font, path)
 }
 
@@ -153,6 +202,7 @@ pub fn load_package_entries(
 
 fn _load(glyphs_font: &glyphslib::Font, path: PathBuf) -> Result<Font, BabelfontError> {
     let mut font = Font::new();
+    let mut diagnostics = Diagnostics::default();
     let mut upgraded = glyphs_font.clone();
     let glyphs_font = if glyphs_font.as_glyphs2().is_some() {
         font.format_specific.insert(
@@ -180,7 +230,13 @@ fn _load(glyphs_font: &glyphslib::Font, path: PathBuf) -> Result<Font, Babelfont
         .axes
         .iter()
         .map(|axis| Axis {
-            tag: Tag::from_str(&axis.tag).unwrap_or_else(|_| Tag::new(b"????")),
+            tag: Tag::from_str(&axis.tag).unwrap_or_else(|_| {
+                diagnostics.push(Diagnostic::warning(
+                    "glyphs.invalid-axis-tag",
+                    format!("Invalid tag '{}' for axis {}", axis.tag, axis.name),
+                ));
+                Tag::new(b"????")
+            }),
             name: axis.name.clone().into(),
             hidden: axis.hidden,
             ..Default::default()
@@ -221,18 +277,16 @@ fn _load(glyphs_font: &glyphslib::Font, path: PathBuf) -> Result<Font, Babelfont
         .map(|master| load_master(master, glyphs_font, &font))
         .collect();
     // Glyphs
-    font.glyphs = GlyphList(
-        glyphs_font
-            .glyphs
-            .iter()
-            .map(|g| glyph::glyphs::from_glyphs(g, &axes_order))
-            .collect::<Result<Vec<_>, BabelfontError>>()?,
-    );
+    font.glyphs = glyphs_font
+        .glyphs
+        .iter()
+        .map(|g| glyph::glyphs::from_glyphs(g, &axes_order, &mut diagnostics))
+        .collect::<Result<GlyphList, BabelfontError>>()?;
     // Instances
     font.instances = glyphs_font
         .instances
         .iter()
-        .map(|i| load_instance(&font, i))
+        .map(|i| load_instance(&font, i, &mut diagnostics))
         .collect();
     // Handle kerning when we do masters
     // Metrics
@@ -248,6 +302,7 @@ fn _load(glyphs_font: &glyphslib::Font, path: PathBuf) -> Result<Font, Babelfont
         &mut font.names,
         &mut font.custom_ot_values,
         &glyphs_font.properties,
+        &mut diagnostics,
     );
     // Settings
     font.format_specific.insert(
@@ -304,6 +359,8 @@ fn _load(glyphs_font: &glyphslib::Font, path: PathBuf) -> Result<Font, Babelfont
         }
     }
 
+    font.diagnostics = diagnostics;
+
     // Interpret metrics
     // Interpret axes
     interpret_axes(&mut font)?;
@@ -316,7 +373,11 @@ fn _load(glyphs_font: &glyphslib::Font, path: PathBuf) -> Result<Font, Babelfont
     Ok(font)
 }
 
-fn load_instance(font: &Font, instance: &glyphs3::Instance) -> crate::Instance {
+fn load_instance(
+    font: &Font,
+    instance: &glyphs3::Instance,
+    diagnostics: &mut Diagnostics,
+) -> crate::Instance {
     let designspace_to_location = |numbers: &[f32]| -> DesignLocation {
         numbers
             .iter()
@@ -336,9 +397,20 @@ fn load_instance(font: &Font, instance: &glyphs3::Instance) -> crate::Instance {
     let mut custom_ot_values = CustomOTValues::default();
     load_properties(
         &mut names,
-        &mut custom_ot_values, // XXX
+        &mut custom_ot_values,
         &instance.properties,
+        diagnostics,
     );
+    if !custom_ot_values.is_empty() {
+        // Instances have nowhere to keep these
+        diagnostics.push(Diagnostic::warning(
+            "glyphs.instance-properties-dropped",
+            format!(
+                "Dropping OpenType values (such as the vendor ID) set on instance {}",
+                instance.name
+            ),
+        ));
+    }
     crate::Instance {
         id: instance.name.clone(),
         name: I18NDictionary::from(&instance.name),
@@ -407,6 +479,7 @@ fn load_properties(
     names: &mut Names,
     custom_ot_values: &mut CustomOTValues,
     glyphs_properties: &[Property],
+    diagnostics: &mut Diagnostics,
 ) {
     for property in glyphs_properties.iter() {
         match property {
@@ -476,7 +549,10 @@ fn load_properties(
                 }
             }
             glyphs3::Property::Junk(plist) => {
-                log::warn!("Ignoring junk property in Glyphs3 font: {:?}", plist);
+                diagnostics.push(Diagnostic::warning(
+                    "glyphs.junk-property",
+                    format!("Ignoring junk property: {:?}", plist),
+                ));
             }
         }
     }
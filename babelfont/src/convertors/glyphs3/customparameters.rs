@@ -1,7 +1,8 @@
 use glyphslib::{common::CustomParameter, Plist};
 
 use crate::{
-    common::FormatSpecific, convertors::glyphs3::get_cp, BabelfontError, Font, MetricType,
+    common::FormatSpecific, convertors::glyphs3::get_cp, BabelfontError, Diagnostic, Font,
+    MetricType,
 };
 
 /// A set of paired functions to interpret/export font-level custom parameters
@@ -154,6 +155,14 @@ fn interpret_variable_font_origin(font: &mut Font) -> Result<(), BabelfontError>
                     axis.default = Some(axis.designspace_to_userspace(loc)?);
                 }
             }
+        } else {
+            font.diagnostics.push(Diagnostic::warning(
+                "glyphs.unknown-origin-master",
+                format!(
+                    "Variable Font Origin refers to unknown master {}; ignoring it",
+                    origin_id
+                ),
+            ));
         }
     }
     Ok(())
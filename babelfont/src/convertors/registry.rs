@@ -0,0 +1,316 @@
+use std::{
+    path::Path,
+    sync::{Arc, LazyLock, RwLock},
+};
+
+use crate::{BabelfontError, Font};
+
+/// The operations a [Convertor] supports
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Fonts can be loaded from a path
+    pub load: bool,
+    /// Fonts can be loaded from an in-memory buffer
+    pub load_bytes: bool,
+    /// Fonts can be saved to a path
+    pub save: bool,
+}
+
+/// A font file format which Babelfont can read, write, or both
+///
+/// Convertors are registered with a [ConvertorRegistry], which picks one
+/// for a given file by its extension or by the first bytes of its contents.
+/// Operations outside of the convertor's [Capabilities] return
+/// [BabelfontError::UnsupportedOperation] by default.
+pub trait Convertor: Send + Sync {
+    /// A short human-readable name for the format
+    fn name(&self) -> &str;
+
+    /// The file extensions (without the leading dot) handled by this convertor
+    fn extensions(&self) -> &[&'static str];
+
+    /// The operations this convertor supports
+    fn capabilities(&self) -> Capabilities;
+
+    /// Whether this convertor handles the given path
+    ///
+    /// By default, this checks the path's extension against [Convertor::extensions].
+    fn handles_path(&self, path: &Path) -> bool {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| self.extensions().contains(&ext))
+    }
+
+    /// Whether the buffer looks like a file in this format, judging by its first bytes
+    fn handles_bytes(&self, _bytes: &[u8]) -> bool {
+        false
+    }
+
+    /// Load a font from a path
+    fn load(&self, _path: &Path) -> Result<Font, BabelfontError> {
+        Err(self.unsupported("load"))
+    }
+
+    /// Load a font from an in-memory buffer
+    ///
+    /// The path, if given, is used for error reporting and to resolve any
+    /// files the font refers to.
+    fn load_bytes(&self, _bytes: &[u8], _path: Option<&Path>) -> Result<Font, BabelfontError> {
+        Err(self.unsupported("load from bytes"))
+    }
+
+    /// Save a font to a path
+    fn save(&self, _font: &Font, _path: &Path) -> Result<(), BabelfontError> {
+        Err(self.unsupported("save"))
+    }
+
+    /// The error returned for an operation this convertor does not support
+    fn unsupported(&self, operation: &str) -> BabelfontError {
+        BabelfontError::UnsupportedOperation {
+            convertor: self.name().to_string(),
+            operation: operation.to_string(),
+        }
+    }
+}
+
+/// A set of [Convertor]s, consulted to load and save fonts
+///
+/// When several convertors handle the same file, the one registered last
+/// wins, so that a registered convertor can override a built-in one.
+#[derive(Clone)]
+pub struct ConvertorRegistry {
+    convertors: Vec<Arc<dyn Convertor>>,
+}
+
+impl Default for ConvertorRegistry {
+    /// A registry containing the convertors for all enabled formats
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry.register(super::babelfont::BabelfontConvertor);
+        #[cfg(feature = "ufo")]
+        registry.register(super::ufo::UfoConvertor);
+        #[cfg(feature = "ufo")]
+        registry.register(super::designspace::DesignspaceConvertor);
+        #[cfg(feature = "fontra")]
+        registry.register(super::fontra::FontraConvertor);
+        #[cfg(feature = "glyphs")]
+        registry.register(super::glyphs3::GlyphsConvertor);
+        #[cfg(feature = "fontir")]
+        registry.register(super::fontir::FontirConvertor);
+        #[cfg(feature = "ttf")]
+        registry.register(super::ttf::TtfConvertor);
+        #[cfg(feature = "fontlab")]
+        registry.register(super::fontlab::FontlabConvertor);
+        #[cfg(feature = "vfb")]
+        registry.register(super::vfb::VfbConvertor);
+        #[cfg(feature = "robocjk")]
+        registry.register(super::robocjk::RobocjkConvertor);
+        #[cfg(feature = "fontforge")]
+        registry.register(super::fontforge::FontforgeConvertor);
+        #[cfg(feature = "fontforge")]
+        registry.register(super::fontforge::SfdirConvertor);
+        registry
+    }
+}
+
+impl ConvertorRegistry {
+    /// A registry with no convertors
+    pub fn empty() -> Self {
+        Self { convertors: vec![] }
+    }
+
+    /// Add a convertor to the registry
+    pub fn register(&mut self, convertor: impl Convertor + 'static) {
+        self.convertors.push(Arc::new(convertor));
+    }
+
+    /// The registered convertors, in registration order
+    pub fn convertors(&self) -> impl Iterator<Item = &dyn Convertor> {
+        self.convertors.iter().map(|c| c.as_ref())
+    }
+
+    fn find(
+        &self,
+        capable: impl Fn(Capabilities) -> bool,
+        handles: impl Fn(&dyn Convertor) -> bool,
+    ) -> Option<&dyn Convertor> {
+        self.convertors
+            .iter()
+            .rev()
+            .map(|c| c.as_ref())
+            .find(|c| capable(c.capabilities()) && handles(*c))
+    }
+
+    /// The convertor which would be used to load the given path
+    pub fn loader_for_path(&self, path: &Path) -> Option<&dyn Convertor> {
+        self.find(|caps| caps.load, |c| c.handles_path(path))
+    }
+
+    /// The convertor which would be used to load the given buffer
+    pub fn loader_for_bytes(&self, bytes: &[u8]) -> Option<&dyn Convertor> {
+        self.find(|caps| caps.load_bytes, |c| c.handles_bytes(bytes))
+    }
+
+    /// The convertor which would be used to save to the given path
+    pub fn saver_for_path(&self, path: &Path) -> Option<&dyn Convertor> {
+        self.find(|caps| caps.save, |c| c.handles_path(path))
+    }
+
+    fn extensions(&self, capable: impl Fn(Capabilities) -> bool) -> Vec<&'static str> {
+        let mut extensions = vec![];
+        for convertor in self.convertors.iter().filter(|c| capable(c.capabilities())) {
+            for ext in convertor.extensions() {
+                if !extensions.contains(ext) {
+                    extensions.push(*ext);
+                }
+            }
+        }
+        extensions
+    }
+
+    /// The file extensions which can be loaded
+    pub fn loadable_extensions(&self) -> Vec<&'static str> {
+        self.extensions(|caps| caps.load)
+    }
+
+    /// The file extensions which can be saved
+    pub fn saveable_extensions(&self) -> Vec<&'static str> {
+        self.extensions(|caps| caps.save)
+    }
+
+    /// Load a font from a path, using the convertor which handles it
+    pub fn load(&self, path: &Path) -> Result<Font, BabelfontError> {
+        let convertor =
+            self.loader_for_path(path)
+                .ok_or_else(|| BabelfontError::UnknownFileType {
+                    path: path.to_path_buf(),
+                })?;
+        let mut font = convertor.load(path)?;
+        font.diagnostics.set_file(path);
+        font.source = Some(path.to_path_buf());
+        Ok(font)
+    }
+
+    /// Load a font from an in-memory buffer, detecting its format from its contents
+    ///
+    /// If the contents are not recognised, the extension of `path` is used instead.
+    pub fn load_bytes(&self, bytes: &[u8], path: Option<&Path>) -> Result<Font, BabelfontError> {
+        let convertor = self
+            .loader_for_bytes(bytes)
+            .or_else(|| path.and_then(|p| self.find(|caps| caps.load_bytes, |c| c.handles_path(p))))
+            .ok_or_else(|| BabelfontError::UnknownFileType {
+                path: path.map(Path::to_path_buf).unwrap_or_default(),
+            })?;
+        let mut font = convertor.load_bytes(bytes, path)?;
+        if let Some(path) = path {
+            font.diagnostics.set_file(path);
+            font.source = Some(path.to_path_buf());
+        }
+        Ok(font)
+    }
+
+    /// Save a font to a path, using the convertor which handles it
+    pub fn save(&self, font: &Font, path: &Path) -> Result<(), BabelfontError> {
+        self.saver_for_path(path)
+            .ok_or_else(|| BabelfontError::UnknownFileType {
+                path: path.to_path_buf(),
+            })?
+            .save(font, path)
+    }
+}
+
+static REGISTRY: LazyLock<RwLock<ConvertorRegistry>> =
+    LazyLock::new(|| RwLock::new(ConvertorRegistry::default()));
+
+/// A snapshot of the global convertor registry, used by [crate::load] and [Font::save]
+pub fn registry() -> ConvertorRegistry {
+    REGISTRY
+        .read()
+        .map(|registry| registry.clone())
+        .unwrap_or_else(|poisoned| poisoned.into_inner().clone())
+}
+
+/// Add a convertor to the global registry, making its format available to
+/// [crate::load] and [Font::save]
+pub fn register(convertor: impl Convertor + 'static) {
+    let mut registry = REGISTRY
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    registry.register(convertor);
+}
+
+/// The name of the first key of a JSON object, if the buffer starts with one
+pub(crate) fn first_json_key(bytes: &[u8]) -> Option<&str> {
+    let start = bytes.iter().position(|b| !b.is_ascii_whitespace())?;
+    let rest = bytes[start..].strip_prefix(b"{")?;
+    let quote = rest.iter().position(|b| !b.is_ascii_whitespace())?;
+    let key = rest[quote..].strip_prefix(b"\"")?;
+    let end = key.iter().take(64).position(|b| *b == b'"')?;
+    std::str::from_utf8(&key[..end]).ok()
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Upper;
+
+    impl Convertor for Upper {
+        fn name(&self) -> &str {
+            "Upper"
+        }
+        fn extensions(&self) -> &[&'static str] {
+            &["upper", "babelfont"]
+        }
+        fn capabilities(&self) -> Capabilities {
+            Capabilities {
+                load_bytes: true,
+                ..Default::default()
+            }
+        }
+        fn handles_bytes(&self, bytes: &[u8]) -> bool {
+            bytes.starts_with(b"UPPER")
+        }
+        fn load_bytes(&self, bytes: &[u8], _path: Option<&Path>) -> Result<Font, BabelfontError> {
+            let mut font = Font::new();
+            font.upm = bytes.len() as u16;
+            Ok(font)
+        }
+    }
+
+    #[test]
+    fn test_registry_dispatch() {
+        let mut registry = ConvertorRegistry::default();
+        assert_eq!(
+            registry
+                .loader_for_path(Path::new("x.babelfont"))
+                .unwrap()
+                .name(),
+            "Babelfont"
+        );
+        assert!(registry.loader_for_path(Path::new("x.upper")).is_none());
+
+        registry.register(Upper);
+        // Upper can't load from paths, so the built-in convertor is still used
+        assert_eq!(
+            registry
+                .loader_for_path(Path::new("x.babelfont"))
+                .unwrap()
+                .name(),
+            "Babelfont"
+        );
+        assert!(!registry.loadable_extensions().contains(&"upper"));
+        let font = registry.load_bytes(b"UPPER CASE", None).unwrap();
+        assert_eq!(font.upm, 10);
+        let err = Upper.save(&font, Path::new("x.upper")).unwrap_err();
+        assert!(matches!(err, BabelfontError::UnsupportedOperation { .. }));
+
+        let json = serde_json::to_string(&Font::new()).unwrap().into_bytes();
+        assert_eq!(first_json_key(&json), Some("upm"));
+        let font = registry
+            .load_bytes(&json, Some(Path::new("x.babelfont")))
+            .unwrap();
+        assert_eq!(font.source.as_deref(), Some(Path::new("x.babelfont")));
+    }
+}
@@ -17,11 +17,12 @@ use crate::{
         },
         ufo::{load_component, load_path},
     },
+    convertors::{Capabilities, Convertor},
     error::BabelfontError,
     font::Font,
     layer::Layer,
     master::Master,
-    LayerType, Shape,
+    Diagnostic, LayerType, Shape,
 };
 
 mod axes;
@@ -235,7 +236,18 @@ fn load_variation_glyphs(
         let location_obj = item.get("location").and_then(|v| v.as_object());
         if let (Some(source_name), Some(file_name)) = (source_name, file_name.as_ref()) {
             let var_path = glyph_dir.join(source_name).join(file_name);
-            let var_glyph = norad::Glyph::load(&var_path).ok();
+            let var_glyph = norad::Glyph::load(&var_path)
+                .map_err(|e| {
+                    font.diagnostics.push(
+                        Diagnostic::warning(
+                            "rcjk.variation-glyph-not-loaded",
+                            format!("Using the default glyph for source {}: {}", source_name, e),
+                        )
+                        .file(&var_path)
+                        .glyph(bf_glyph.name.as_str()),
+                    )
+                })
+                .ok();
             let glyph_for_layer = var_glyph.as_ref().unwrap_or(base_glyph);
 
             let mut var_layer = layer_basics_from_norad_glyph(
@@ -340,6 +352,31 @@ fn ensure_smart_component_defaults(var_layer: &mut Layer, bf_glyph: &crate::Glyp
     }
 }
 
+/// Convertor for RoboCJK projects
+pub struct RobocjkConvertor;
+
+impl Convertor for RobocjkConvertor {
+    fn name(&self) -> &str {
+        "RoboCJK"
+    }
+
+    fn extensions(&self) -> &[&'static str] {
+        &["rcjk"]
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            load: true,
+            load_bytes: false,
+            save: false,
+        }
+    }
+
+    fn load(&self, path: &std::path::Path) -> Result<Font, BabelfontError> {
+        load(path.to_path_buf())
+    }
+}
+
 /// Load a RoboCJK font from a directory
 pub fn load(path: PathBuf) -> Result<Font, BabelfontError> {
     let designspace_path = path.join("designspace.json");
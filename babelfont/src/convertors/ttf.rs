@@ -1,12 +1,12 @@
-use fea_rs_ast::LanguageSystemStatement;
 use fontdrasil::{
     coords::{
-        ConvertSpace, DesignCoord, DesignLocation, NormalizedCoord, NormalizedSpace, UserCoord,
+        ConvertSpace, DesignCoord, DesignLocation, NormalizedCoord, NormalizedLocation,
+        NormalizedSpace, UserCoord, UserLocation,
     },
     types::Axes,
+    variations::VariationModel,
 };
-use indexmap::IndexMap;
-use itertools::Itertools;
+use indexmap::{IndexMap, IndexSet};
 use skrifa::{
     outline::DrawSettings,
     prelude::{LocationRef, Size},
@@ -15,21 +15,77 @@ use skrifa::{
     GlyphId, GlyphNames, MetadataProvider,
 };
 use smol_str::SmolStr;
-use sr_aef::fea_rs_ast::AsFea;
 use std::collections::{HashMap, HashSet};
 use write_fonts::types::F2Dot14;
 
 use crate::{
-    features::PossiblyAutomaticCode, Anchor, BabelfontError, Features, Font, FormatSpecific, Glyph,
-    Instance, Layer, LayerType, MetricType, PathBuilder, Tag,
+    convertors::{Capabilities, Convertor},
+    kerning::resolve,
+    Anchor, BabelfontError, Diagnostic, Features, Font, FormatSpecific, Glyph, Instance, Layer,
+    LayerType, MetricType, PathBuilder, Tag,
 };
 
+/// Convertor for loading compiled TrueType and OpenType binaries
+pub struct TtfConvertor;
+
+impl Convertor for TtfConvertor {
+    fn name(&self) -> &str {
+        "TrueType"
+    }
+
+    fn extensions(&self) -> &[&'static str] {
+        &["ttf"]
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            load: true,
+            load_bytes: true,
+            save: false,
+        }
+    }
+
+    fn handles_bytes(&self, bytes: &[u8]) -> bool {
+        [b"\x00\x01\x00\x00", b"OTTO", b"true"]
+            .iter()
+            .any(|magic| bytes.starts_with(*magic))
+    }
+
+    fn load(&self, path: &std::path::Path) -> Result<Font, BabelfontError> {
+        load(path)
+    }
+
+    fn load_bytes(
+        &self,
+        bytes: &[u8],
+        _path: Option<&std::path::Path>,
+    ) -> Result<Font, BabelfontError> {
+        load_bytes_with_options(bytes, sr_aef::UncompileOptions::default())
+    }
+}
+
 /// Load a TTF font from a file path
 pub fn load<T: AsRef<std::path::Path>>(path: T) -> Result<Font, BabelfontError> {
-    let mut font = Font::new();
+    load_with_options(path, sr_aef::UncompileOptions::default())
+}
+
+/// Load a TTF font from a file path, controlling how its layout is uncompiled
+pub fn load_with_options<T: AsRef<std::path::Path>>(
+    path: T,
+    options: sr_aef::UncompileOptions,
+) -> Result<Font, BabelfontError> {
     let binary = std::fs::read(path.as_ref()).map_err(|e| BabelfontError::IO(e.to_string()))?;
+    load_bytes_with_options(&binary, options)
+}
+
+/// Load a TTF font from an in-memory binary, controlling how its layout is uncompiled
+pub fn load_bytes_with_options(
+    binary: &[u8],
+    options: sr_aef::UncompileOptions,
+) -> Result<Font, BabelfontError> {
+    let mut font = Font::new();
     let fontref =
-        skrifa::FontRef::new(&binary).map_err(|e| BabelfontError::BinaryFontRead(e.to_string()))?;
+        skrifa::FontRef::new(binary).map_err(|e| BabelfontError::BinaryFontRead(e.to_string()))?;
     font.upm = fontref.head()?.units_per_em();
 
     load_axes(&fontref, &mut font)?;
@@ -38,10 +94,34 @@ pub fn load<T: AsRef<std::path::Path>>(path: T) -> Result<Font, BabelfontError>
     load_instances(&fontref, &mut font)?;
     load_masters(&fontref, &mut font)?;
     load_glyphs(&fontref, &mut font)?;
-    load_features(&fontref, &mut font)?;
+    load_features(&fontref, &mut font, options)?;
     Ok(font)
 }
 
+/// Collect the anchors of a source font's default master, to use as
+/// [sr_aef::UncompileOptions::anchor_name_hints] when loading a binary built from it
+pub fn anchor_name_hints(source: &Font) -> IndexMap<SmolStr, IndexMap<SmolStr, (i16, i16)>> {
+    let mut hints: IndexMap<SmolStr, IndexMap<SmolStr, (i16, i16)>> = IndexMap::new();
+    let Some(master) = source.default_master() else {
+        return hints;
+    };
+    for glyph in source.glyphs.iter() {
+        let Some(layer) = source.master_layer_for(&glyph.name, master) else {
+            continue;
+        };
+        for anchor in layer.anchors.iter().filter(|a| !a.name.starts_with('_')) {
+            hints
+                .entry(anchor.name.as_str().into())
+                .or_default()
+                .insert(
+                    glyph.name.clone(),
+                    (anchor.x.round() as i16, anchor.y.round() as i16),
+                );
+        }
+    }
+    hints
+}
+
 fn name_id_to_i18n(fontref: &skrifa::FontRef, name_id: StringId) -> crate::I18NDictionary {
     let names = fontref.localized_strings(name_id);
     let mut dict = crate::I18NDictionary::new();
@@ -324,16 +404,32 @@ fn expand_glyphcontainer(glyphs: &fea_rs_ast::GlyphContainer) -> Vec<SmolStr> {
     r
 }
 
-fn load_features(fontref: &skrifa::FontRef, font: &mut Font) -> Result<(), BabelfontError> {
+fn load_features(
+    fontref: &skrifa::FontRef,
+    font: &mut Font,
+    options: sr_aef::UncompileOptions,
+) -> Result<(), BabelfontError> {
     let axes = font.fontdrasil_axes()?;
-    let uncompile_context = sr_aef::uncompile_context(fontref)
+    let mut uncompile_context = sr_aef::uncompile_context_with_options(fontref, options)
         .map_err(|e| BabelfontError::BinaryFontRead(e.to_string()))?;
+    let kerning_lookups = load_kerning(&uncompile_context, font, &axes)?;
+    // The converted lookups are only referenced from the kern feature, which
+    // load_kerning has checked, so they can be dropped along with their references
+    uncompile_context
+        .lookups
+        .retain(|name, _| !kerning_lookups.contains(name));
+    for references in uncompile_context.features.values_mut() {
+        references.retain(|reference| !kerning_lookups.contains(reference.lookup_name.as_str()));
+    }
+    uncompile_context
+        .features
+        .retain(|_, references| !references.is_empty());
+    let features = Features::from(&uncompile_context);
     let master_ids = font
         .masters
         .iter()
         .map(|m| (m.id.clone(), m))
         .collect::<HashMap<_, _>>();
-    let mut features = Features::default();
     for (anchor_name, anchors) in uncompile_context.anchors {
         for (glyph_name, anchor) in anchors {
             if let Some(glyph) = font.glyphs.get_mut(&glyph_name) {
@@ -342,10 +438,15 @@ fn load_features(fontref: &skrifa::FontRef, font: &mut Font) -> Result<(), Babel
                         continue;
                     };
                     let Some(master) = master_ids.get(mid) else {
-                        log::warn!(
-                            "Master ID {} from anchor {} does not exist in the font",
-                            mid,
-                            anchor_name
+                        font.diagnostics.push(
+                            Diagnostic::warning(
+                                "ttf.unknown-anchor-master",
+                                format!(
+                                    "Master ID {} from anchor {} does not exist in the font",
+                                    mid, anchor_name
+                                ),
+                            )
+                            .glyph(glyph.name.as_str()),
                         );
                         continue;
                     };
@@ -359,11 +460,13 @@ fn load_features(fontref: &skrifa::FontRef, font: &mut Font) -> Result<(), Babel
                     });
                 }
             } else {
-                log::warn!(
-                    "Glyph {} from anchor {} does not exist in the font",
-                    glyph_name,
-                    anchor_name
-                );
+                font.diagnostics.push(Diagnostic::warning(
+                    "ttf.unknown-anchor-glyph",
+                    format!(
+                        "Glyph {} from anchor {} does not exist in the font",
+                        glyph_name, anchor_name
+                    ),
+                ));
             }
         }
     }
@@ -382,10 +485,15 @@ fn load_features(fontref: &skrifa::FontRef, font: &mut Font) -> Result<(), Babel
                             continue;
                         };
                         let Some(master) = master_ids.get(mid) else {
-                            log::warn!(
-                                "Master ID {} from anchor {} does not exist in the font",
-                                mid,
-                                anchor_name
+                            font.diagnostics.push(
+                                Diagnostic::warning(
+                                    "ttf.unknown-anchor-master",
+                                    format!(
+                                        "Master ID {} from anchor {} does not exist in the font",
+                                        mid, anchor_name
+                                    ),
+                                )
+                                .glyph(glyph.name.as_str()),
                             );
                             continue;
                         };
@@ -399,49 +507,18 @@ fn load_features(fontref: &skrifa::FontRef, font: &mut Font) -> Result<(), Babel
                         });
                     }
                 } else {
-                    log::warn!(
-                        "Glyph {} from anchor {} does not exist in the font",
-                        glyph_name,
-                        anchor_name
-                    );
+                    font.diagnostics.push(Diagnostic::warning(
+                        "ttf.unknown-anchor-glyph",
+                        format!(
+                            "Glyph {} from anchor {} does not exist in the font",
+                            glyph_name, anchor_name
+                        ),
+                    ));
                 }
             }
         }
     }
 
-    for (class_name, glyphs) in uncompile_context.named_classes.iter() {
-        features.classes.insert(
-            class_name.clone(),
-            PossiblyAutomaticCode::new(glyphs.as_fea("")),
-        );
-    }
-    let mut language_systems = vec![];
-    for (script_tag, lang_sys_tags) in &uncompile_context.language_systems {
-        for lang_sys_tag in lang_sys_tags {
-            let lss =
-                LanguageSystemStatement::new(script_tag.to_string(), lang_sys_tag.to_string());
-            language_systems.push(lss.as_fea(""));
-        }
-    }
-    let language_systems_block = language_systems.join("\n");
-    if !language_systems_block.is_empty() {
-        features.prefixes.insert(
-            "LanguageSystems".into(),
-            PossiblyAutomaticCode::new(language_systems_block),
-        );
-    }
-    for (lookup_name, statements) in uncompile_context.lookups.iter() {
-        features.prefixes.insert(
-            lookup_name.clone(),
-            PossiblyAutomaticCode::new(statements.as_fea("")),
-        );
-    }
-    for (feature_name, lookups) in uncompile_context.features.iter() {
-        features.features.push((
-            feature_name.clone(),
-            PossiblyAutomaticCode::new(lookups.iter().map(|l| l.as_fea("")).join("\n")),
-        ));
-    }
     font.features = features;
     Ok(())
 }
@@ -451,49 +528,407 @@ fn get_x_y_location_for_anchor(
     anchor: &sr_aef::fea_rs_ast::Anchor,
     axes: &Axes,
 ) -> Result<(f64, f64), BabelfontError> {
-    let user_loc = location.to_user(axes)?;
-    let simple_user_loc = user_loc
+    let x = resolve_metric_at(&anchor.x, &location, axes)?;
+    let y = resolve_metric_at(&anchor.y, &location, axes)?;
+    Ok((x, y))
+}
+
+/// Evaluate a (possibly variable) metric from the uncompiled layout at a master location
+///
+/// Variable metrics are expressed at the peaks of the GDEF variation regions, which
+/// need not coincide with the masters we found in `gvar`; in that case we interpolate.
+fn resolve_metric_at(
+    metric: &fea_rs_ast::Metric,
+    location: &DesignLocation,
+    axes: &Axes,
+) -> Result<f64, BabelfontError> {
+    let items = match metric {
+        fea_rs_ast::Metric::Scalar(scalar) => return Ok(*scalar as f64),
+        fea_rs_ast::Metric::Variable(items) => items,
+        fea_rs_ast::Metric::GlyphsAppNumber(_) => unreachable!(),
+    };
+    let simple_user_loc = location
+        .to_user(axes)?
         .iter()
         .map(|(tag, coord)| (SmolStr::from(tag.to_string()), coord.to_f64() as i16))
         .collect::<IndexMap<SmolStr, i16>>();
+    if let Some((_, value)) = items.iter().find(|(loc, _)| loc == &simple_user_loc) {
+        return Ok(*value as f64);
+    }
+    let mut values: HashMap<NormalizedLocation, Vec<f64>> = HashMap::new();
+    for (loc, value) in items {
+        let user_loc = loc
+            .iter()
+            .map(|(tag, coord)| {
+                Tag::new_checked(tag.as_bytes())
+                    .map(|tag| (tag, UserCoord::new(*coord as f64)))
+                    .map_err(|e| BabelfontError::General(e.to_string()))
+            })
+            .collect::<Result<UserLocation, BabelfontError>>()?;
+        values.insert(user_loc.to_normalized(axes)?, vec![*value as f64]);
+    }
+    let model = VariationModel::new(values.keys().cloned().collect(), axes.axis_order());
+    let deltas = model.deltas(&values)?;
+    Ok(model.interpolate_from_deltas(&location.to_normalized(axes)?, &deltas)[0])
+}
 
-    let x = match &anchor.x {
-        fea_rs_ast::Metric::Scalar(x_scalar) => *x_scalar as f64,
-        fea_rs_ast::Metric::Variable(items) => {
-            // Anchor must exist at this location, so find the first item that matches the location
-            items
-                .iter()
-                .find_map(|(loc, item)| {
-                    if loc == &simple_user_loc {
-                        Some(*item as f64)
-                    } else {
-                        None
-                    }
-                })
-                .unwrap_or_default()
+/// Flatten one side of a pair positioning statement into glyph names
+///
+/// Returns `None` if the side refers to something we can't resolve here.
+fn kern_side_glyphs(glyphs: &fea_rs_ast::GlyphContainer) -> Option<Vec<SmolStr>> {
+    match glyphs {
+        fea_rs_ast::GlyphContainer::GlyphName(glyph_name) => Some(vec![glyph_name.name.clone()]),
+        fea_rs_ast::GlyphContainer::GlyphClass(glyph_class) => {
+            let mut r = vec![];
+            for g in glyph_class.glyphs.iter() {
+                r.extend(kern_side_glyphs(g)?);
+            }
+            Some(r)
         }
-        fea_rs_ast::Metric::GlyphsAppNumber(_) => unreachable!(),
+        _ => None,
+    }
+}
+
+/// A pair positioning statement which only adjusts the advance of the first glyph
+fn as_kerning_pair(statement: &fea_rs_ast::Statement) -> Option<&fea_rs_ast::PairPosStatement> {
+    let fea_rs_ast::Statement::PairPos(pp) = statement else {
+        return None;
     };
+    let vr = &pp.value_record_1;
+    if pp.value_record_2.is_some()
+        || vr.x_placement.is_some()
+        || vr.y_placement.is_some()
+        || vr.y_advance.is_some()
+        || vr.x_advance.is_none()
+    {
+        return None;
+    }
+    Some(pp)
+}
 
-    let y = match &anchor.y {
-        fea_rs_ast::Metric::Scalar(y_scalar) => *y_scalar as f64,
-        fea_rs_ast::Metric::Variable(items) => {
-            // Anchor must exist at this location, so find the first item that matches the location
-            items
+/// Assigns kerning classes to kern groups on one side of a pair
+///
+/// Each distinct class becomes a group. A class which shares glyphs with an
+/// earlier one gets a group of its own, rather than being flattened into
+/// glyph pairs which would act as exceptions to the earlier group's kerning.
+#[derive(Default)]
+struct KernGroupBuilder {
+    groups: IndexMap<SmolStr, Vec<SmolStr>>,
+    /// The groups each glyph is in, oldest first
+    glyph_to_groups: HashMap<SmolStr, Vec<SmolStr>>,
+}
+
+impl KernGroupBuilder {
+    /// Returns the kerning side ("@group") for a class, or `None` for an empty class
+    fn group_for(&mut self, members: Vec<SmolStr>) -> Option<SmolStr> {
+        let first = members.first().cloned()?;
+        // Reuse an identical existing group
+        if let Some(group) = self
+            .groups_of(&first)
+            .iter()
+            .find(|group| self.groups.get(*group) == Some(&members))
+        {
+            return Some(SmolStr::from(format!("@{}", group)));
+        }
+        let mut group_name = first.clone();
+        let mut suffix = 1;
+        while self.groups.contains_key(&group_name) {
+            group_name = SmolStr::from(format!("{}_{}", first, suffix));
+            suffix += 1;
+        }
+        for glyph in members.iter() {
+            self.glyph_to_groups
+                .entry(glyph.clone())
+                .or_default()
+                .push(group_name.clone());
+        }
+        self.groups.insert(group_name.clone(), members);
+        Some(SmolStr::from(format!("@{}", group_name)))
+    }
+
+    /// The groups a glyph is in, oldest first
+    fn groups_of(&self, glyph: &str) -> &[SmolStr] {
+        self.glyph_to_groups
+            .get(glyph)
+            .map_or(&[], |groups| groups.as_slice())
+    }
+}
+
+/// Kerning pairs of one lookup, or the combined kerning of several, for each master
+type PairsPerMaster = Vec<IndexMap<(SmolStr, SmolStr), i16>>;
+
+/// Combine the kerning of several lookups into one set of pairs per master
+///
+/// The values of different lookups add up, so each pair gets the sum of what
+/// every lookup applies to it, with glyphs resolved to their groups as
+/// babelfont does when reading kerning.
+fn sum_lookup_kerning(
+    lookups: &[PairsPerMaster],
+    master_count: usize,
+    first_groups: &KernGroupBuilder,
+    second_groups: &KernGroupBuilder,
+) -> PairsPerMaster {
+    let pairs: IndexSet<&(SmolStr, SmolStr)> = lookups
+        .iter()
+        .flatten()
+        .flat_map(|pairs| pairs.keys())
+        .collect();
+    (0..master_count)
+        .map(|master| {
+            pairs
                 .iter()
-                .find_map(|(loc, item)| {
-                    if loc == &simple_user_loc {
-                        Some(*item as f64)
-                    } else {
-                        None
-                    }
+                .map(|(left, right)| {
+                    let value = lookups
+                        .iter()
+                        .filter_map(|lookup| {
+                            resolve(
+                                lookup.get(master)?,
+                                left,
+                                first_groups.groups_of(left),
+                                right,
+                                second_groups.groups_of(right),
+                            )
+                        })
+                        .map(|(_, value)| value)
+                        .sum::<i16>();
+                    ((left.clone(), right.clone()), value)
                 })
-                .unwrap_or_default()
+                .collect()
+        })
+        .collect()
+}
+
+/// Lookup flags which kerning lookups are compiled with: `IgnoreMarks`, optionally
+/// with `RightToLeft`
+const KERNING_LOOKUP_FLAGS: [u16; 2] = [0x8, 0x9];
+
+/// The `RightToLeft` lookup flag
+const RIGHT_TO_LEFT: u16 = 0x1;
+
+/// Remove pairs which are zero in every master, unless they are exceptions to
+/// non-zero group kerning
+///
+/// A zero glyph–glyph pair which cancels out its groups' kerning changes how
+/// the font renders, so it must be kept; other zero pairs are just noise.
+fn drop_redundant_zero_pairs(
+    masters: &mut [IndexMap<(SmolStr, SmolStr), i16>],
+    first_groups: &KernGroupBuilder,
+    second_groups: &KernGroupBuilder,
+) {
+    let sides = |name: &SmolStr, groups: &KernGroupBuilder| {
+        let mut sides = vec![name.clone()];
+        if !name.starts_with('@') {
+            sides.extend(
+                groups
+                    .groups_of(name)
+                    .iter()
+                    .map(|group| SmolStr::from(format!("@{}", group))),
+            );
         }
-        fea_rs_ast::Metric::GlyphsAppNumber(_) => unreachable!(),
+        sides
+    };
+    let zero_pairs = masters
+        .first()
+        .map(|pairs| pairs.keys().cloned().collect::<Vec<_>>())
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|pair| {
+            masters
+                .iter()
+                .all(|pairs| pairs.get(pair).is_none_or(|v| *v == 0))
+        })
+        .collect::<Vec<_>>();
+    for pair in zero_pairs {
+        let (left, right) = &pair;
+        // Less specific pairs which would apply if this one were removed
+        let lefts = sides(left, first_groups);
+        let rights = sides(right, second_groups);
+        let overrides_groups = lefts
+            .iter()
+            .flat_map(|l| rights.iter().map(move |r| (l.clone(), r.clone())))
+            .filter(|fallback| *fallback != pair)
+            .any(|fallback| {
+                masters
+                    .iter()
+                    .any(|pairs| pairs.get(&fallback).is_some_and(|v| *v != 0))
+            });
+        if !overrides_groups {
+            for pairs in masters.iter_mut() {
+                pairs.shift_remove(&pair);
+            }
+        }
+    }
+}
+
+/// Move pure pair adjustment lookups from the `kern` feature into per-master kerning
+///
+/// Returns the names of the lookups which were converted, so that they can be
+/// omitted from the feature code.
+fn load_kerning(
+    uncompile_context: &sr_aef::UncompileContext,
+    font: &mut Font,
+    axes: &Axes,
+) -> Result<HashSet<SmolStr>, BabelfontError> {
+    let mut kerning_lookups = HashSet::new();
+    let Some(kern_lookups) = uncompile_context.features.get("kern") else {
+        return Ok(kerning_lookups);
     };
+    // Lookups used anywhere else (other features, contextual lookups) must stay as code
+    let mut used_elsewhere: HashSet<SmolStr> = uncompile_context
+        .features
+        .iter()
+        .filter(|(feature, _)| feature.as_str() != "kern")
+        .flat_map(|(_, lookups)| {
+            lookups
+                .iter()
+                .map(|l| SmolStr::from(l.lookup_name.as_str()))
+        })
+        .collect();
+    for lookup in uncompile_context.lookups.values() {
+        for statement in lookup.statements.iter() {
+            let nested = match statement {
+                fea_rs_ast::Statement::ChainedContextPos(s) => &s.lookups,
+                fea_rs_ast::Statement::ChainedContextSubst(s) => &s.lookups,
+                _ => continue,
+            };
+            used_elsewhere.extend(nested.iter().flatten().cloned());
+        }
+    }
 
-    Ok((x, y))
+    let mut first_groups = KernGroupBuilder::default();
+    let mut second_groups = KernGroupBuilder::default();
+    let mut ltr_lookups: Vec<PairsPerMaster> = vec![];
+    let mut rtl_lookups: Vec<PairsPerMaster> = vec![];
+    for reference in kern_lookups {
+        let lookup_name = SmolStr::from(reference.lookup_name.as_str());
+        if used_elsewhere.contains(&lookup_name) {
+            continue;
+        }
+        let Some(lookup) = uncompile_context.lookups.get(&lookup_name) else {
+            continue;
+        };
+        let mut pairs = vec![];
+        let mut convertible = !lookup.statements.is_empty();
+        let mut flags = 0;
+        for statement in lookup.statements.iter() {
+            if let fea_rs_ast::Statement::LookupFlag(flag) = statement {
+                if flag.mark_attachment.is_some() || flag.mark_filtering_set.is_some() {
+                    convertible = false;
+                    break;
+                }
+                flags = flag.value;
+                continue;
+            }
+            let Some(pp) = as_kerning_pair(statement) else {
+                convertible = false;
+                break;
+            };
+            let (Some(left), Some(right)) = (
+                kern_side_glyphs(&pp.glyphs_1),
+                kern_side_glyphs(&pp.glyphs_2),
+            ) else {
+                convertible = false;
+                break;
+            };
+            pairs.push((left, right, pp));
+        }
+        // Kerning is written back with these flags; anything else must stay as code
+        if !convertible || !KERNING_LOOKUP_FLAGS.contains(&flags) {
+            continue;
+        }
+        let mut lookup_pairs: PairsPerMaster = vec![IndexMap::new(); font.masters.len()];
+        for (left, right, pp) in pairs {
+            #[allow(clippy::unwrap_used)] // We checked this in as_kerning_pair
+            let metric = pp.value_record_1.x_advance.as_ref().unwrap();
+            let values = font
+                .masters
+                .iter()
+                .map(|m| resolve_metric_at(metric, &m.location, axes).map(|v| v.round() as i16))
+                .collect::<Result<Vec<i16>, BabelfontError>>()?;
+            let single_glyphs = matches!(pp.glyphs_1, fea_rs_ast::GlyphContainer::GlyphName(_))
+                && matches!(pp.glyphs_2, fea_rs_ast::GlyphContainer::GlyphName(_));
+            let (left, right) = if single_glyphs {
+                (left.first().cloned(), right.first().cloned())
+            } else {
+                (first_groups.group_for(left), second_groups.group_for(right))
+            };
+            let (Some(left), Some(right)) = (left, right) else {
+                continue;
+            };
+            for (pairs, value) in lookup_pairs.iter_mut().zip(values) {
+                // Within a lookup, earlier subtables take precedence
+                pairs.entry((left.clone(), right.clone())).or_insert(value);
+            }
+        }
+        if flags & RIGHT_TO_LEFT != 0 {
+            rtl_lookups.push(lookup_pairs);
+        } else {
+            ltr_lookups.push(lookup_pairs);
+        }
+        kerning_lookups.insert(lookup_name);
+    }
+    let master_count = font.masters.len();
+    let mut ltr = sum_lookup_kerning(&ltr_lookups, master_count, &first_groups, &second_groups);
+    drop_redundant_zero_pairs(&mut ltr, &first_groups, &second_groups);
+    for (master, pairs) in font.masters.iter_mut().zip(ltr) {
+        master.kerning.extend(pairs);
+    }
+    let mut rtl = sum_lookup_kerning(&rtl_lookups, master_count, &first_groups, &second_groups);
+    drop_redundant_zero_pairs(&mut rtl, &first_groups, &second_groups);
+    mark_rtl_kerning_glyphs(font, &rtl, &first_groups, &second_groups);
+    let master_ids: Vec<String> = font.masters.iter().map(|m| m.id.clone()).collect();
+    for (master_id, pairs) in master_ids.iter().zip(rtl) {
+        font.set_rtl_kerning(master_id, pairs);
+    }
+    for (name, members) in first_groups.groups {
+        font.first_kern_groups.insert(name, members);
+    }
+    for (name, members) in second_groups.groups {
+        font.second_kern_groups.insert(name, members);
+    }
+    Ok(kerning_lookups)
+}
+
+/// Give the glyphs in RTL kerning the group properties Glyphs would have
+///
+/// RTL kerning is stored the Glyphs way, and glyphs which take part in it
+/// are placed in kern groups according to their `kern_left` and `kern_right`
+/// properties (see [Font::kern_groups_with_rtl_swaps]). Setting these to the
+/// glyphs' own groups keeps them where they are.
+fn mark_rtl_kerning_glyphs(
+    font: &mut Font,
+    rtl: &[IndexMap<(SmolStr, SmolStr), i16>],
+    first_groups: &KernGroupBuilder,
+    second_groups: &KernGroupBuilder,
+) {
+    let members = |name: &SmolStr, groups: &KernGroupBuilder| match name.strip_prefix('@') {
+        Some(group) => groups.groups.get(group).cloned().unwrap_or_default(),
+        None => vec![name.clone()],
+    };
+    let glyphs: IndexSet<SmolStr> = rtl
+        .iter()
+        .flat_map(|pairs| pairs.keys())
+        .flat_map(|(left, right)| {
+            members(left, first_groups)
+                .into_iter()
+                .chain(members(right, second_groups))
+        })
+        .collect();
+    for name in glyphs {
+        let Some(glyph) = font.glyphs.get_mut(&name) else {
+            continue;
+        };
+        if let Some(group) = first_groups.groups_of(&name).first() {
+            glyph
+                .format_specific
+                .insert("kern_right".into(), serde_json::json!(group));
+        }
+        if let Some(group) = second_groups.groups_of(&name).first() {
+            glyph
+                .format_specific
+                .insert("kern_left".into(), serde_json::json!(group));
+        }
+    }
 }
 
 fn fontdrasil_location_to_skrifa_location<Space: ConvertSpace<NormalizedSpace>>(
@@ -587,3 +1022,51 @@ fn load_layer(
 
     Ok(layer)
 }
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn class(glyphs: &[&str]) -> Vec<SmolStr> {
+        glyphs.iter().map(|g| SmolStr::from(*g)).collect()
+    }
+
+    #[test]
+    fn test_overlapping_classes_make_groups() {
+        let mut groups = KernGroupBuilder::default();
+        assert_eq!(groups.group_for(class(&["A", "Aacute"])), Some("@A".into()));
+        assert_eq!(groups.group_for(class(&["A", "Aacute"])), Some("@A".into()));
+        // A class sharing a glyph with an earlier one is still group kerning
+        assert_eq!(
+            groups.group_for(class(&["A", "Agrave"])),
+            Some("@A_1".into())
+        );
+        assert_eq!(groups.groups_of("A"), ["A", "A_1"]);
+        assert_eq!(groups.group_for(vec![]), None);
+    }
+
+    #[test]
+    fn test_lookup_kerning_adds_up() {
+        let mut first = KernGroupBuilder::default();
+        let mut second = KernGroupBuilder::default();
+        let a = first.group_for(class(&["A", "Aacute"])).unwrap();
+        let v = second.group_for(class(&["V", "W"])).unwrap();
+        let lookup = |pairs: &[(&str, &str, i16)]| -> PairsPerMaster {
+            vec![pairs
+                .iter()
+                .map(|(l, r, value)| ((SmolStr::from(*l), SmolStr::from(*r)), *value))
+                .collect()]
+        };
+        let lookups = [
+            lookup(&[(a.as_str(), v.as_str(), -50), ("A", "W", -20)]),
+            lookup(&[("A", "V", -10)]),
+        ];
+        let summed = sum_lookup_kerning(&lookups, 1, &first, &second);
+        let pair = |l: &str, r: &str| summed[0][&(SmolStr::from(l), SmolStr::from(r))];
+        assert_eq!(pair("@A", "@V"), -50);
+        assert_eq!(pair("A", "W"), -20);
+        // The second lookup adds to the group kerning of the first
+        assert_eq!(pair("A", "V"), -60);
+    }
+}
@@ -1,7 +1,10 @@
 use crate::{
-    common::decomposition::DecomposedAffine, features::Features, glyph::GlyphCategory,
-    BabelfontError, Component, Font, Glyph, Layer, LayerType, Master, MetricType, Node, Path,
-    Shape,
+    common::decomposition::DecomposedAffine,
+    convertors::{Capabilities, Convertor},
+    features::Features,
+    glyph::GlyphCategory,
+    BabelfontError, Component, Diagnostic, Font, Glyph, Layer, LayerType, Master, MetricType, Node,
+    Path, Shape,
 };
 use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
 use fontdrasil::{coords::Location, types::Tag};
@@ -67,6 +70,35 @@ pub(crate) fn stat(path: &std::path::Path) -> Option<DateTime<chrono::Local>> {
         .map(DateTime::<chrono::Local>::from)
 }
 
+/// Convertor for single-master UFO sources
+pub struct UfoConvertor;
+
+impl Convertor for UfoConvertor {
+    fn name(&self) -> &str {
+        "UFO"
+    }
+
+    fn extensions(&self) -> &[&'static str] {
+        &["ufo"]
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            load: true,
+            load_bytes: false,
+            save: true,
+        }
+    }
+
+    fn load(&self, path: &std::path::Path) -> Result<Font, BabelfontError> {
+        load(path)
+    }
+
+    fn save(&self, font: &Font, path: &std::path::Path) -> Result<(), BabelfontError> {
+        save_ufo(font, path)
+    }
+}
+
 /// Load a UFO font from a file path
 pub fn load<T: AsRef<std::path::Path>>(path: T) -> Result<Font, BabelfontError> {
     let mut font = Font::new();
@@ -798,10 +830,15 @@ pub(crate) fn load_font_info(
     font.custom_ot_values.cff_family_other_blues = info.postscript_family_other_blues.clone();
     font.custom_ot_values.cff_stem_snap_h = info.postscript_stem_snap_h.clone();
     font.custom_ot_values.cff_stem_snap_v = info.postscript_stem_snap_v.clone();
-    font.custom_ot_values.os2_vendor_id = info
-        .open_type_os2_vendor_id
-        .as_ref()
-        .and_then(|x| Tag::from_str(x).ok());
+    if let Some(vendor_id) = &info.open_type_os2_vendor_id {
+        match Tag::from_str(vendor_id) {
+            Ok(tag) => font.custom_ot_values.os2_vendor_id = Some(tag),
+            Err(_) => font.diagnostics.push(Diagnostic::warning(
+                "ufo.invalid-vendor-id",
+                format!("Ignoring invalid OS/2 vendor ID '{}'", vendor_id),
+            )),
+        }
+    }
     // XXX and much more
     if let Some(v) = &info.trademark {
         font.names.trademark = v.into();
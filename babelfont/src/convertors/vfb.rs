@@ -9,9 +9,38 @@ use uuid::Uuid;
 use vfbreader::{read_vfb, GlyphEntry, Node as VFBNode, Vfb, VfbEntry};
 
 use crate::{
-    common::decomposition::DecomposedAffine, features::PossiblyAutomaticCode, Axis, BabelfontError,
-    Features, Font, FormatSpecific, Glyph, Layer, LayerType, Master, OutlinePen as _, Shape,
+    common::decomposition::DecomposedAffine,
+    convertors::{Capabilities, Convertor},
+    features::PossiblyAutomaticCode,
+    Axis, BabelfontError, Diagnostic, Features, Font, FormatSpecific, Glyph, Layer, LayerType,
+    Master, OutlinePen as _, Shape,
 };
+
+/// Convertor for FontLab Studio 5 VFB files
+pub struct VfbConvertor;
+
+impl Convertor for VfbConvertor {
+    fn name(&self) -> &str {
+        "FontLab VFB"
+    }
+
+    fn extensions(&self) -> &[&'static str] {
+        &["vfb"]
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            load: true,
+            load_bytes: false,
+            save: false,
+        }
+    }
+
+    fn load(&self, path: &std::path::Path) -> Result<Font, BabelfontError> {
+        load(path.to_path_buf())
+    }
+}
+
 /// VFB convertor
 pub fn load(path: PathBuf) -> Result<Font, BabelfontError> {
     let vfb: Vfb = read_vfb(&path).map_err(|e| BabelfontError::VfbLoad(e.to_string()))?;
@@ -115,8 +144,12 @@ pub fn load(path: PathBuf) -> Result<Font, BabelfontError> {
             VfbEntry::DropoutPpm(_) => {}          // => todo!(),
             VfbEntry::MeasurementLine(_) => {}     // => todo!(),
             VfbEntry::ExportPcltTable(_) => {}     // => todo!(),
-            VfbEntry::Note(_) => {}                // => todo!(),
-            VfbEntry::CustomData(_) => {}          // => todo!(),
+            VfbEntry::Note(_) | VfbEntry::CustomData(_) => {
+                font.diagnostics.push(Diagnostic::warning(
+                    "vfb.unsupported-entry",
+                    "Dropping font note or custom data, which is not supported yet",
+                ));
+            }
             VfbEntry::OpenTypeClass(s) => {
                 let splits = s.splitn(2, ":").collect::<Vec<_>>();
                 if splits.len() == 2 {
@@ -126,6 +159,11 @@ pub fn load(path: PathBuf) -> Result<Font, BabelfontError> {
                     font.features
                         .classes
                         .insert(classname.into(), PossiblyAutomaticCode::new(contents));
+                } else {
+                    font.diagnostics.push(Diagnostic::warning(
+                        "vfb.invalid-class",
+                        format!("Ignoring OpenType class without a name: {}", s),
+                    ));
                 }
             }
             VfbEntry::AxisCount(_) => {}         // => todo!(),
@@ -0,0 +1,185 @@
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::checks::Severity;
+
+/// A problem noticed while loading a font which did not stop it from loading
+///
+/// Most diagnostics describe source data which could not be represented and
+/// was dropped or replaced with a default.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    /// A short, stable identifier for the kind of problem, prefixed with the
+    /// format it came from (e.g. `glyphs.junk-property`)
+    pub code: String,
+    /// How serious the problem is
+    pub severity: Severity,
+    /// A description of the problem
+    pub message: String,
+    /// The file being loaded, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<PathBuf>,
+    /// The glyph the problem is in, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub glyph: Option<String>,
+    /// The layer the problem is in, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layer: Option<String>,
+}
+
+impl Diagnostic {
+    /// Create a new diagnostic
+    pub fn new(severity: Severity, code: impl Into<String>, message: impl Into<String>) -> Self {
+        Diagnostic {
+            code: code.into(),
+            severity,
+            message: message.into(),
+            file: None,
+            glyph: None,
+            layer: None,
+        }
+    }
+
+    /// Create a new error
+    pub fn error(code: impl Into<String>, message: impl Into<String>) -> Self {
+        Self::new(Severity::Error, code, message)
+    }
+
+    /// Create a new warning
+    pub fn warning(code: impl Into<String>, message: impl Into<String>) -> Self {
+        Self::new(Severity::Warning, code, message)
+    }
+
+    /// Create a new informational diagnostic
+    pub fn info(code: impl Into<String>, message: impl Into<String>) -> Self {
+        Self::new(Severity::Info, code, message)
+    }
+
+    /// Attach the diagnostic to a file
+    pub fn file(mut self, file: impl Into<PathBuf>) -> Self {
+        self.file = Some(file.into());
+        self
+    }
+
+    /// Attach the diagnostic to a glyph
+    pub fn glyph(mut self, glyph: impl Into<String>) -> Self {
+        self.glyph = Some(glyph.into());
+        self
+    }
+
+    /// Attach the diagnostic to a layer
+    pub fn layer(mut self, layer: impl Into<String>) -> Self {
+        self.layer = Some(layer.into());
+        self
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} [{}] ", self.severity, self.code)?;
+        if let Some(file) = &self.file {
+            write!(f, "{}: ", file.display())?;
+        }
+        match (&self.glyph, &self.layer) {
+            (Some(glyph), Some(layer)) => write!(f, "{} ({}): ", glyph, layer)?,
+            (Some(glyph), None) => write!(f, "{}: ", glyph)?,
+            _ => {}
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+/// The diagnostics collected while loading a font
+///
+/// Convertors push into this as they go; each diagnostic is also logged, so
+/// nothing is lost for callers who only watch the log.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Diagnostics(Vec<Diagnostic>);
+
+impl Diagnostics {
+    /// Record (and log) a diagnostic
+    pub fn push(&mut self, diagnostic: Diagnostic) {
+        match diagnostic.severity {
+            Severity::Info => log::info!("{}", diagnostic),
+            Severity::Warning => log::warn!("{}", diagnostic),
+            Severity::Error => log::error!("{}", diagnostic),
+        }
+        self.0.push(diagnostic);
+    }
+
+    /// Iterate over the diagnostics in the order they were recorded
+    pub fn iter(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.0.iter()
+    }
+
+    /// The number of diagnostics recorded
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether no diagnostics were recorded
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Whether any diagnostic is an error
+    pub fn has_errors(&self) -> bool {
+        self.0.iter().any(|d| d.severity == Severity::Error)
+    }
+
+    /// Add diagnostics which have already been logged
+    pub(crate) fn extend(&mut self, other: Diagnostics) {
+        self.0.extend(other.0);
+    }
+
+    /// Fill in the file for any diagnostics which don't name one
+    pub(crate) fn set_file(&mut self, file: &Path) {
+        for diagnostic in self.0.iter_mut().filter(|d| d.file.is_none()) {
+            diagnostic.file = Some(file.to_path_buf());
+        }
+    }
+}
+
+impl IntoIterator for Diagnostics {
+    type Item = Diagnostic;
+    type IntoIter = std::vec::IntoIter<Diagnostic>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diagnostics() {
+        let mut diagnostics = Diagnostics::default();
+        diagnostics.push(Diagnostic::info(
+            "sfd.unhandled-glyph-key",
+            "Ignored 2 x Foo",
+        ));
+        diagnostics.push(
+            Diagnostic::warning("glyphs.invalid-direction", "Invalid direction 'sideways'")
+                .glyph("A")
+                .file("other.glyphs"),
+        );
+        assert!(!diagnostics.has_errors());
+        diagnostics.set_file(Path::new("font.sfd"));
+        let messages: Vec<String> = diagnostics.iter().map(|d| d.to_string()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "info [sfd.unhandled-glyph-key] font.sfd: Ignored 2 x Foo",
+                "warning [glyphs.invalid-direction] other.glyphs: A: Invalid direction 'sideways'",
+            ]
+        );
+    }
+}
@@ -0,0 +1,540 @@
+use std::{collections::HashMap, fmt::Display};
+
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use smol_str::SmolStr;
+
+use crate::{features::PossiblyAutomaticCode, Font, Glyph, Layer, Master, Shape};
+
+/// A single difference between two fonts
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Change {
+    /// Where the change is, from the font downwards (e.g. `["glyphs", "A", "layers", "m01", "width"]`)
+    pub path: Vec<String>,
+    /// What changed
+    #[serde(flatten)]
+    pub kind: ChangeKind,
+}
+
+/// The kind of a [`Change`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum ChangeKind {
+    /// Something was added in the new font
+    Added {
+        /// The new value (not given for whole glyphs, layers, masters and so on)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        value: Option<Value>,
+    },
+    /// Something was removed from the old font
+    Removed {
+        /// The old value (not given for whole glyphs, layers, masters and so on)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        value: Option<Value>,
+    },
+    /// A value was changed
+    Changed {
+        /// The value in the old font
+        old: Value,
+        /// The value in the new font
+        new: Value,
+    },
+    /// Points in a layer's outlines were moved, without changing its structure
+    NodesMoved {
+        /// The nodes which moved
+        deltas: Vec<NodeDelta>,
+    },
+}
+
+/// The movement of one node in a layer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodeDelta {
+    /// The index of the path in the layer's shapes
+    pub shape: usize,
+    /// The index of the node in the path
+    pub node: usize,
+    /// The horizontal movement
+    pub dx: f64,
+    /// The vertical movement
+    pub dy: f64,
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let path = self.path.join("/");
+        match &self.kind {
+            ChangeKind::Added { value: Some(value) } => write!(f, "+ {}: {}", path, short(value)),
+            ChangeKind::Added { value: None } => write!(f, "+ {}", path),
+            ChangeKind::Removed { value: Some(value) } => {
+                write!(f, "- {}: {}", path, short(value))
+            }
+            ChangeKind::Removed { value: None } => write!(f, "- {}", path),
+            ChangeKind::Changed { old, new } => {
+                write!(f, "~ {}: {} -> {}", path, short(old), short(new))
+            }
+            ChangeKind::NodesMoved { deltas } => {
+                write!(f, "~ {}: {} nodes moved", path, deltas.len())?;
+                for delta in deltas {
+                    write!(
+                        f,
+                        "\n    shape {} node {}: ({:+}, {:+})",
+                        delta.shape, delta.node, delta.dx, delta.dy
+                    )?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Abbreviate a value for human-readable output
+fn short(value: &Value) -> String {
+    let s = value.to_string();
+    if s.chars().count() > 60 {
+        format!("{}...", s.chars().take(57).collect::<String>())
+    } else {
+        s
+    }
+}
+
+/// Compare two fonts and list the differences between them
+///
+/// Glyphs, layers, anchors, masters, axes and instances are matched up by
+/// name or ID rather than by position, so reordering them is not reported as
+/// a change. Where a layer's outlines have the same structure in both fonts,
+/// moved points are reported as per-node deltas.
+pub fn diff_fonts(old: &Font, new: &Font) -> Vec<Change> {
+    let mut differ = Differ::default();
+    let root: Vec<String> = vec![];
+    differ.values(&at(&root, "upm"), &json!(old.upm), &json!(new.upm));
+    differ.values(
+        &at(&root, "version"),
+        &json!(old.version),
+        &json!(new.version),
+    );
+    differ.values(&at(&root, "note"), &json!(old.note), &json!(new.note));
+    differ.values(
+        &at(&root, "names"),
+        &to_value(&old.names),
+        &to_value(&new.names),
+    );
+    differ.values(
+        &at(&root, "custom_ot_values"),
+        &to_value(&old.custom_ot_values),
+        &to_value(&new.custom_ot_values),
+    );
+    differ.keyed(
+        &at(&root, "axes"),
+        &old.axes,
+        &new.axes,
+        |axis| axis.tag.to_string(),
+        |differ, path, old, new| differ.values(path, &to_value(old), &to_value(new)),
+    );
+    differ.keyed(
+        &at(&root, "masters"),
+        &old.masters,
+        &new.masters,
+        |master| master.id.clone(),
+        Differ::masters,
+    );
+    differ.keyed(
+        &at(&root, "instances"),
+        &old.instances,
+        &new.instances,
+        |instance| instance.id.clone(),
+        |differ, path, old, new| differ.values(path, &to_value(old), &to_value(new)),
+    );
+    differ.values(
+        &at(&root, "first_kern_groups"),
+        &to_value(&old.first_kern_groups),
+        &to_value(&new.first_kern_groups),
+    );
+    differ.values(
+        &at(&root, "second_kern_groups"),
+        &to_value(&old.second_kern_groups),
+        &to_value(&new.second_kern_groups),
+    );
+    differ.features(&at(&root, "features"), old, new);
+    differ.keyed(
+        &at(&root, "glyphs"),
+        old.glyphs.as_slice(),
+        new.glyphs.as_slice(),
+        |glyph| glyph.name.to_string(),
+        Differ::glyphs,
+    );
+    differ.changes
+}
+
+pub(crate) fn at(path: &[String], segment: impl Into<String>) -> Vec<String> {
+    let mut path = path.to_vec();
+    path.push(segment.into());
+    path
+}
+
+pub(crate) fn to_value<T: Serialize>(value: &T) -> Value {
+    serde_json::to_value(value).unwrap_or_default()
+}
+
+/// Serialize a value, leaving out the given fields
+fn to_value_without<T: Serialize>(value: &T, fields: &[&str]) -> Value {
+    let mut value = to_value(value);
+    if let Value::Object(map) = &mut value {
+        for field in fields {
+            map.remove(*field);
+        }
+    }
+    value
+}
+
+/// The code of named classes or prefixes, keyed by name
+fn code_map(code: &IndexMap<SmolStr, PossiblyAutomaticCode>) -> Value {
+    code.iter()
+        .map(|(name, code)| (name.to_string(), json!(code.code)))
+        .collect()
+}
+
+#[derive(Default)]
+struct Differ {
+    changes: Vec<Change>,
+}
+
+impl Differ {
+    fn push(&mut self, path: &[String], kind: ChangeKind) {
+        self.changes.push(Change {
+            path: path.to_vec(),
+            kind,
+        });
+    }
+
+    /// Compare two serialized values, descending into objects
+    fn values(&mut self, path: &[String], old: &Value, new: &Value) {
+        if old == new {
+            return;
+        }
+        match (old, new) {
+            (Value::Object(old), Value::Object(new)) => {
+                for (key, old_value) in old.iter() {
+                    match new.get(key) {
+                        Some(new_value) => self.values(&at(path, key), old_value, new_value),
+                        None => self.push(
+                            &at(path, key),
+                            ChangeKind::Removed {
+                                value: Some(old_value.clone()),
+                            },
+                        ),
+                    }
+                }
+                for (key, new_value) in new.iter().filter(|(k, _)| !old.contains_key(*k)) {
+                    self.push(
+                        &at(path, key),
+                        ChangeKind::Added {
+                            value: Some(new_value.clone()),
+                        },
+                    );
+                }
+            }
+            _ => self.push(
+                path,
+                ChangeKind::Changed {
+                    old: old.clone(),
+                    new: new.clone(),
+                },
+            ),
+        }
+    }
+
+    /// Match up two lists by key, reporting additions and removals and comparing the rest
+    ///
+    /// Items sharing a key are told apart by their order, as `key#2` and so on.
+    fn keyed<T>(
+        &mut self,
+        path: &[String],
+        old: &[T],
+        new: &[T],
+        key: impl Fn(&T) -> String,
+        mut compare: impl FnMut(&mut Self, &[String], &T, &T),
+    ) {
+        let by_key = |items: &'_ [T]| {
+            let mut counts: HashMap<String, usize> = HashMap::new();
+            items
+                .iter()
+                .map(|item| {
+                    let key = key(item);
+                    let count = counts.entry(key.clone()).or_default();
+                    *count += 1;
+                    if *count > 1 {
+                        (format!("{}#{}", key, count), item)
+                    } else {
+                        (key, item)
+                    }
+                })
+                .collect::<IndexMap<String, &T>>()
+        };
+        let old_items = by_key(old);
+        let new_items = by_key(new);
+        for (name, old_item) in old_items.iter() {
+            match new_items.get(name) {
+                Some(new_item) => compare(self, &at(path, name), old_item, new_item),
+                None => self.push(&at(path, name), ChangeKind::Removed { value: None }),
+            }
+        }
+        for name in new_items.keys().filter(|k| !old_items.contains_key(*k)) {
+            self.push(&at(path, name), ChangeKind::Added { value: None });
+        }
+    }
+
+    fn masters(&mut self, path: &[String], old: &Master, new: &Master) {
+        self.values(
+            path,
+            &to_value_without(old, &["kerning", "metrics"]),
+            &to_value_without(new, &["kerning", "metrics"]),
+        );
+        let metrics = |master: &Master| -> Value {
+            master
+                .metrics
+                .iter()
+                .map(|(metric, value)| (metric.as_str().to_string(), json!(value)))
+                .collect()
+        };
+        self.values(&at(path, "metrics"), &metrics(old), &metrics(new));
+        let kerning = |master: &Master| -> Value {
+            master
+                .kerning
+                .iter()
+                .map(|((left, right), value)| (format!("{} {}", left, right), json!(value)))
+                .collect()
+        };
+        self.values(&at(path, "kerning"), &kerning(old), &kerning(new));
+    }
+
+    fn features(&mut self, path: &[String], old: &Font, new: &Font) {
+        self.values(
+            &at(path, "classes"),
+            &code_map(&old.features.classes),
+            &code_map(&new.features.classes),
+        );
+        self.values(
+            &at(path, "prefixes"),
+            &code_map(&old.features.prefixes),
+            &code_map(&new.features.prefixes),
+        );
+        // Feature blocks can repeat, so number repeated tags
+        let blocks = |font: &Font| -> Value {
+            let mut seen: IndexMap<&str, usize> = IndexMap::new();
+            font.features
+                .features
+                .iter()
+                .map(|(tag, code)| {
+                    let count = seen.entry(tag.as_str()).or_default();
+                    *count += 1;
+                    let key = if *count > 1 {
+                        format!("{}#{}", tag, count)
+                    } else {
+                        tag.to_string()
+                    };
+                    (key, json!(code.code))
+                })
+                .collect()
+        };
+        self.values(&at(path, "features"), &blocks(old), &blocks(new));
+    }
+
+    fn glyphs(&mut self, path: &[String], old: &Glyph, new: &Glyph) {
+        self.values(
+            path,
+            &to_value_without(old, &["layers"]),
+            &to_value_without(new, &["layers"]),
+        );
+        self.keyed(
+            &at(path, "layers"),
+            &old.layers,
+            &new.layers,
+            |layer| layer.id.clone().unwrap_or_else(|| layer.debug_name()),
+            Differ::layers,
+        );
+    }
+
+    fn layers(&mut self, path: &[String], old: &Layer, new: &Layer) {
+        self.values(
+            path,
+            &to_value_without(old, &["shapes", "anchors"]),
+            &to_value_without(new, &["shapes", "anchors"]),
+        );
+        self.keyed(
+            &at(path, "anchors"),
+            &old.anchors,
+            &new.anchors,
+            |anchor| anchor.name.clone(),
+            |differ, path, old, new| differ.values(path, &to_value(old), &to_value(new)),
+        );
+
+        let path = at(path, "shapes");
+        let same_structure = old.shapes.len() == new.shapes.len()
+            && old
+                .shapes
+                .iter()
+                .zip(new.shapes.iter())
+                .all(|pair| match pair {
+                    (Shape::Path(a), Shape::Path(b)) => {
+                        a.closed == b.closed
+                            && a.nodes.len() == b.nodes.len()
+                            && a.nodes
+                                .iter()
+                                .zip(b.nodes.iter())
+                                .all(|(a, b)| a.nodetype == b.nodetype)
+                    }
+                    (Shape::Component(_), Shape::Component(_)) => true,
+                    _ => false,
+                });
+        if !same_structure {
+            self.push(
+                &path,
+                ChangeKind::Changed {
+                    old: to_value(&old.shapes),
+                    new: to_value(&new.shapes),
+                },
+            );
+            return;
+        }
+        let mut deltas = vec![];
+        for (index, pair) in old.shapes.iter().zip(new.shapes.iter()).enumerate() {
+            match pair {
+                (Shape::Path(a), Shape::Path(b)) => {
+                    for (node, (a, b)) in a.nodes.iter().zip(b.nodes.iter()).enumerate() {
+                        if a.x != b.x || a.y != b.y {
+                            deltas.push(NodeDelta {
+                                shape: index,
+                                node,
+                                dx: b.x - a.x,
+                                dy: b.y - a.y,
+                            });
+                        }
+                    }
+                }
+                (Shape::Component(a), Shape::Component(b)) => {
+                    self.values(&at(&path, index.to_string()), &to_value(a), &to_value(b))
+                }
+                _ => {}
+            }
+        }
+        if !deltas.is_empty() {
+            self.push(&path, ChangeKind::NodesMoved { deltas });
+        }
+    }
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Anchor, LayerType, Node, Path};
+
+    fn font() -> Font {
+        let mut font = Font::new();
+        font.masters
+            .push(Master::new("Regular", "m01", Default::default()));
+        let mut layer = Layer::new(500.0);
+        layer.id = Some("m01".into());
+        layer.master = LayerType::DefaultForMaster("m01".into());
+        layer.shapes.push(Shape::Path(Path {
+            nodes: vec![
+                Node::new_line(0.0, 0.0),
+                Node::new_line(100.0, 0.0),
+                Node::new_line(100.0, 100.0),
+            ],
+            closed: true,
+            format_specific: Default::default(),
+        }));
+        layer.anchors.push(Anchor {
+            x: 50.0,
+            y: 100.0,
+            name: "top".into(),
+            format_specific: Default::default(),
+        });
+        font.glyphs.push(Glyph {
+            name: "A".into(),
+            codepoints: vec![0x41],
+            layers: vec![layer],
+            ..Default::default()
+        });
+        font
+    }
+
+    #[test]
+    fn test_identical() {
+        assert!(diff_fonts(&font(), &font()).is_empty());
+    }
+
+    #[test]
+    fn test_diff_fonts() {
+        let old = font();
+        let mut new = font();
+        new.upm = 2000;
+        new.glyphs.push(Glyph::new("B"));
+        let layer = &mut new.glyphs.get_mut("A").unwrap().layers[0];
+        layer.width = 600.0;
+        layer.anchors[0].y = 120.0;
+        if let Shape::Path(path) = &mut layer.shapes[0] {
+            path.nodes[1].x = 110.0;
+        }
+        new.masters[0].kerning.insert(("A".into(), "B".into()), -20);
+
+        let changes = diff_fonts(&old, &new);
+        let paths: Vec<String> = changes.iter().map(|c| c.path.join("/")).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "upm",
+                "masters/m01/kerning/A B",
+                "glyphs/A/layers/m01/width",
+                "glyphs/A/layers/m01/anchors/top/y",
+                "glyphs/A/layers/m01/shapes",
+                "glyphs/B",
+            ]
+        );
+        assert_eq!(
+            changes[4].kind,
+            ChangeKind::NodesMoved {
+                deltas: vec![NodeDelta {
+                    shape: 0,
+                    node: 1,
+                    dx: 10.0,
+                    dy: 0.0
+                }]
+            }
+        );
+        // The change list round-trips through JSON
+        let json = serde_json::to_string(&changes).unwrap();
+        let back: Vec<Change> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, changes);
+    }
+
+    #[test]
+    fn test_diff_keys_by_id() {
+        let mut old = font();
+        // Two layers with the same name, told apart by their ids
+        for (id, width) in [("bracket1", 500.0), ("bracket2", 520.0)] {
+            let mut layer = Layer::new(width);
+            layer.id = Some(id.into());
+            layer.name = Some("Bold [120]".into());
+            layer.master = LayerType::AssociatedWithMaster("m01".into());
+            old.glyphs.get_mut("A").unwrap().layers.push(layer);
+        }
+        old.instances.push(crate::Instance {
+            id: "i01".into(),
+            name: "Regular".into(),
+            ..Default::default()
+        });
+        let mut new = old.clone();
+        new.glyphs.get_mut("A").unwrap().layers[2].width = 540.0;
+        new.instances[0].name = "Book".into();
+
+        let changes = diff_fonts(&old, &new);
+        let paths: Vec<String> = changes.iter().map(|c| c.path.join("/")).collect();
+        assert_eq!(
+            paths,
+            vec!["instances/i01/name/dflt", "glyphs/A/layers/bracket2/width"]
+        );
+    }
+}
@@ -1,6 +1,12 @@
 use serde::Serialize;
-use std::{ops::Range, path::PathBuf};
+use std::{
+    fmt::Display,
+    ops::Range,
+    path::{Path, PathBuf},
+};
 use thiserror::Error;
+
+use crate::{FeatureCodeOrigin, FeatureSourceMap, Features, KernSide};
 #[cfg(feature = "cli")]
 extern crate serde_json_path_to_error as serde_json;
 
@@ -21,6 +27,15 @@ pub enum BabelfontError {
         path: PathBuf,
     },
 
+    /// The convertor does not support the requested operation
+    #[error("The {convertor} convertor cannot {operation}")]
+    UnsupportedOperation {
+        /// The name of the convertor
+        convertor: String,
+        /// The operation which was attempted
+        operation: String,
+    },
+
     /// General error with a message
     #[error("Error parsing font: {0}")]
     General(String),
@@ -44,6 +59,19 @@ pub enum BabelfontError {
     /// Error parsing Glyphs file
     PlistParse(String),
 
+    #[error("Error parsing {}{}: {message}", .path.display(), position(.line, .column))]
+    /// A source file could not be parsed
+    SourceParse {
+        /// The path of the file
+        path: PathBuf,
+        /// The (one-based) line of the problem, if known
+        line: Option<usize>,
+        /// The (one-based) column of the problem, if known
+        column: Option<usize>,
+        /// A description of the problem
+        message: String,
+    },
+
     #[cfg(feature = "ufo")]
     #[error("Error loading UFO: {0}")]
     /// Error loading UFO
@@ -99,6 +127,24 @@ pub enum BabelfontError {
         reason: String,
     },
 
+    #[error("No {side} kern group @{group}")]
+    /// A kern group was not found
+    KernGroupNotFound {
+        /// The name of the group, without the `@`
+        group: String,
+        /// Which side of a pair the group was expected on
+        side: KernSide,
+    },
+
+    #[error("There is already a {side} kern group @{group}")]
+    /// A kern group could not be created because one of that name exists
+    KernGroupExists {
+        /// The name of the group, without the `@`
+        group: String,
+        /// Which side of a pair the group is on
+        side: KernSide,
+    },
+
     #[error("Glyph {glyph} not found")]
     /// A glyph was not found
     GlyphNotFound {
@@ -147,7 +193,7 @@ pub enum BabelfontError {
     #[error("Glyphset grew unreasonably during layout closure")]
     LayoutClosureError,
     /// Feature parsing error
-    #[error("Feature parsing error: {0:#?}")]
+    #[error("Feature parsing error:\n{}", render_feature_errors(.0))]
     FeatureParsing(Vec<FeatureError>),
     /// Multiple masters are not supported when saving a UFO font
     #[cfg(feature = "ufo")]
@@ -156,13 +202,221 @@ pub enum BabelfontError {
     /// A component referenced a glyph that was not found in the font
     #[error("Component references missing glyph: {0}")]
     MissingGlyphReference(String),
+    /// Glyphs use each other as components in a loop
+    #[error("Component cycle: {}", .glyphs.join(" -> "))]
+    ComponentCycle {
+        /// The glyphs in the cycle, starting and ending with the same glyph
+        glyphs: Vec<String>,
+    },
+}
+
+fn position(line: &Option<usize>, column: &Option<usize>) -> String {
+    match (line, column) {
+        (Some(line), Some(column)) => format!(" at line {} column {}", line, column),
+        (Some(line), None) => format!(" at line {}", line),
+        _ => String::new(),
+    }
+}
+
+impl BabelfontError {
+    /// A parse error for a file, picking the position out of the parser's
+    /// message if it gives one as "line X column Y"
+    pub(crate) fn source_parse(path: &Path, message: impl Display) -> Self {
+        let message = message.to_string();
+        let number_after = |word: &str| {
+            let words: Vec<&str> = message.split_whitespace().collect();
+            words
+                .windows(2)
+                .rev()
+                .find(|pair| pair[0].trim_end_matches(':') == word)
+                .and_then(|pair| {
+                    pair[1]
+                        .trim_end_matches(|c: char| !c.is_ascii_digit())
+                        .parse()
+                        .ok()
+                })
+        };
+        BabelfontError::SourceParse {
+            path: path.to_path_buf(),
+            line: number_after("line"),
+            column: number_after("column"),
+            message,
+        }
+    }
+
+    /// Point feature errors back at the class, prefix or feature they came from
+    ///
+    /// Spans in feature errors are offsets into the output of
+    /// [Features::to_fea]; this fills in where they are in the font's own
+    /// feature code. Other errors are returned unchanged.
+    pub(crate) fn with_feature_sources(self, features: &Features, file: Option<&Path>) -> Self {
+        let (fea, map) = features.to_fea_with_source_map();
+        self.with_compiled_feature_sources(&fea, &map, features, file)
+    }
+
+    /// Point errors in compiled feature code back at the font's own feature code
+    ///
+    /// `fea` is the code which was compiled, and `map` where it came from. As
+    /// the compiled code may have been rewritten by filters before
+    /// compilation, each error is looked for in `original`, the feature code
+    /// of the font as the user wrote it; code which is not there at all is
+    /// reported as generated.
+    pub(crate) fn with_compiled_feature_sources(
+        self,
+        fea: &str,
+        map: &FeatureSourceMap,
+        original: &Features,
+        file: Option<&Path>,
+    ) -> Self {
+        match self {
+            BabelfontError::FeatureParsing(errors) => BabelfontError::FeatureParsing(
+                errors
+                    .into_iter()
+                    .map(|mut error| {
+                        error.file = file.map(Path::to_path_buf);
+                        if let Some((origin, line)) = map.locate(fea, error.span.start) {
+                            error.snippet = snippet(fea, &error.span);
+                            let (origin, line) =
+                                original_line(fea, &error.span, origin, line, original);
+                            error.origin = Some(origin);
+                            error.line = line;
+                        }
+                        error
+                    })
+                    .collect(),
+            ),
+            other => other,
+        }
+    }
+}
+
+/// Where a line of compiled feature code is in the font's own feature code
+///
+/// If the entry's code no longer has the offending line where the compiled
+/// code did, the first line with the same text is used instead.
+fn original_line(
+    fea: &str,
+    span: &Range<usize>,
+    origin: &FeatureCodeOrigin,
+    line: usize,
+    original: &Features,
+) -> (FeatureCodeOrigin, Option<usize>) {
+    let code = match origin {
+        FeatureCodeOrigin::Class(name) => original.classes.get(name),
+        FeatureCodeOrigin::Prefix(name) => original.prefixes.get(name),
+        FeatureCodeOrigin::Feature(tag) => original
+            .features
+            .iter()
+            .find(|(t, _)| t == tag)
+            .map(|(_, code)| code),
+        FeatureCodeOrigin::Generated => None,
+    };
+    let Some(code) = code else {
+        return (FeatureCodeOrigin::Generated, None);
+    };
+    let start = span.start.min(fea.len());
+    let line_start = fea
+        .get(..start)
+        .and_then(|f| f.rfind('\n'))
+        .map_or(0, |ix| ix + 1);
+    let line_end = fea
+        .get(start..)
+        .and_then(|f| f.find('\n'))
+        .map_or(fea.len(), |ix| start + ix);
+    let text = fea.get(line_start..line_end).unwrap_or_default().trim();
+    let mut lines = code.code.lines().map(str::trim);
+    let line = if lines.clone().nth(line - 1) == Some(text) {
+        Some(line)
+    } else {
+        lines.position(|l| l == text).map(|ix| ix + 1)
+    };
+    (origin.clone(), line)
 }
 
+/// An error or warning from the feature compiler
 #[derive(Debug, Serialize)]
 pub struct FeatureError {
+    /// A description of the problem
     pub message: String,
+    /// The byte range of the problem in the generated feature code
     pub span: Range<usize>,
+    /// Whether this is an error rather than a warning
     pub is_error: bool,
+    /// The class, prefix or feature the problem is in, if known
+    pub origin: Option<FeatureCodeOrigin>,
+    /// The (one-based) line within the origin's code, if known
+    pub line: Option<usize>,
+    /// The source file the feature code came from, if known
+    pub file: Option<PathBuf>,
+    /// The offending line of code, with the problem underlined
+    pub snippet: Option<String>,
+}
+
+impl FeatureError {
+    pub(crate) fn new(message: String, span: Range<usize>, is_error: bool) -> Self {
+        FeatureError {
+            message,
+            span,
+            is_error,
+            origin: None,
+            line: None,
+            file: None,
+            snippet: None,
+        }
+    }
+}
+
+impl Display for FeatureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let severity = if self.is_error { "error" } else { "warning" };
+        writeln!(f, "{}: {}", severity, self.message)?;
+        write!(f, "  --> ")?;
+        if let Some(file) = &self.file {
+            write!(f, "{}: ", file.display())?;
+        }
+        match (&self.origin, self.line) {
+            (Some(origin), Some(line)) => write!(f, "{}, line {}", origin, line)?,
+            (Some(origin), None) => write!(f, "{}", origin)?,
+            _ => write!(
+                f,
+                "bytes {}..{} of generated feature code",
+                self.span.start, self.span.end
+            )?,
+        }
+        if let Some(snippet) = &self.snippet {
+            for line in snippet.lines() {
+                write!(f, "\n   | {}", line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The line of `fea` containing the start of `span`, with the span underlined
+fn snippet(fea: &str, span: &Range<usize>) -> Option<String> {
+    let start = span.start.min(fea.len());
+    let line_start = fea.get(..start)?.rfind('\n').map_or(0, |ix| ix + 1);
+    let line_end = fea[start..].find('\n').map_or(fea.len(), |ix| start + ix);
+    let line = &fea[line_start..line_end];
+    let indent = fea[line_start..start].chars().count();
+    let width = fea
+        .get(start..span.end.clamp(start, line_end))
+        .map_or(0, |s| s.chars().count())
+        .max(1);
+    Some(format!(
+        "{}\n{}{}",
+        line,
+        " ".repeat(indent),
+        "^".repeat(width)
+    ))
+}
+
+fn render_feature_errors(errors: &[FeatureError]) -> String {
+    errors
+        .iter()
+        .map(|e| e.to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
 impl From<fea_rs_ast::Error> for BabelfontError {
@@ -176,11 +430,7 @@ impl From<fea_rs_ast::Error> for BabelfontError {
                 diagnostic_set
                     .diagnostics()
                     .iter()
-                    .map(|d| FeatureError {
-                        message: d.message.text.clone(),
-                        span: d.span(),
-                        is_error: d.is_error(),
-                    })
+                    .map(|d| FeatureError::new(d.message.text.clone(), d.span(), d.is_error()))
                     .collect(),
             ),
         }
@@ -276,3 +526,54 @@ impl From<std::io::Error> for BabelfontError {
         BabelfontError::IO(e.to_string()) // Can we do better?
     }
 }
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::PossiblyAutomaticCode;
+
+    #[test]
+    fn test_feature_error_origin() {
+        let mut features = Features::default();
+        features
+            .classes
+            .insert("lc".into(), PossiblyAutomaticCode::new("a b c"));
+        features.features.push((
+            "liga".into(),
+            PossiblyAutomaticCode::new("sub f i by f_i;\nsub f l by ;"),
+        ));
+        let fea = features.to_fea();
+        let start = fea.find("by ;").unwrap() + 3;
+        let error = BabelfontError::FeatureParsing(vec![FeatureError::new(
+            "Expected glyph or glyph class".to_string(),
+            start..start + 1,
+            true,
+        )])
+        .with_feature_sources(&features, Some(Path::new("Test.glyphs")));
+        let BabelfontError::FeatureParsing(errors) = &error else {
+            panic!("Expected a feature parsing error");
+        };
+        assert_eq!(
+            errors[0].origin,
+            Some(FeatureCodeOrigin::Feature("liga".into()))
+        );
+        assert_eq!(errors[0].line, Some(2));
+        assert_eq!(
+            errors[0].to_string(),
+            "error: Expected glyph or glyph class\n  --> Test.glyphs: feature 'liga', line 2\n   | sub f l by ;\n   |            ^"
+        );
+    }
+
+    #[test]
+    fn test_source_parse() {
+        let error = BabelfontError::source_parse(
+            Path::new("font.babelfont"),
+            "expected `,` or `}` at line 12 column 5",
+        );
+        assert_eq!(
+            error.to_string(),
+            "Error parsing font.babelfont at line 12 column 5: expected `,` or `}` at line 12 column 5"
+        );
+    }
+}
@@ -2,6 +2,7 @@ use crate::common::FormatSpecific;
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 use smol_str::SmolStr;
+use std::{fmt::Display, ops::Range};
 use typeshare::typeshare;
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -30,6 +31,15 @@ impl PossiblyAutomaticCode {
             format_specific: FormatSpecific::default(),
         }
     }
+
+    /// Create a new PossiblyAutomaticCode which is marked as automatically generated.
+    pub fn new_automatic<S: Into<String>>(code: S) -> Self {
+        Self {
+            code: code.into(),
+            automatic: true,
+            format_specific: FormatSpecific::default(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -68,27 +78,147 @@ pub struct Features {
     pub format_specific: FormatSpecific,
 }
 
+/// The entry in [Features] which a piece of feature code came from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "name", rename_all = "snake_case")]
+pub enum FeatureCodeOrigin {
+    /// A class definition, by class name
+    Class(SmolStr),
+    /// A prefix, by prefix name
+    Prefix(SmolStr),
+    /// A feature block, by feature tag
+    Feature(SmolStr),
+    /// Code generated while compiling, such as ligature carets built from anchors
+    Generated,
+}
+
+impl Display for FeatureCodeOrigin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FeatureCodeOrigin::Class(name) => write!(f, "class @{}", name),
+            FeatureCodeOrigin::Prefix(name) => write!(f, "prefix '{}'", name),
+            FeatureCodeOrigin::Feature(tag) => write!(f, "feature '{}'", tag),
+            FeatureCodeOrigin::Generated => write!(f, "generated code"),
+        }
+    }
+}
+
+/// Where each entry of [Features] ended up in the output of [Features::to_fea]
+#[derive(Debug, Clone, Default)]
+pub struct FeatureSourceMap {
+    /// For each entry, the byte range of everything written for it, the
+    /// offset where its own code starts, and where it came from
+    entries: Vec<(Range<usize>, usize, FeatureCodeOrigin)>,
+}
+
+impl FeatureSourceMap {
+    /// Find the entry containing a byte offset into the generated feature code
+    ///
+    /// Returns the entry and the (one-based) line within the entry's own code.
+    pub fn locate(&self, fea: &str, offset: usize) -> Option<(&FeatureCodeOrigin, usize)> {
+        let (_, code_start, origin) = self
+            .entries
+            .iter()
+            .find(|(range, _, _)| range.contains(&offset))?;
+        let lines =
+            |range: Range<usize>| fea.get(range).map_or(0, |code| code.matches('\n').count());
+        let mut line = lines(*code_start..offset.max(*code_start));
+        if *origin != FeatureCodeOrigin::Generated {
+            // Generated code spliced into this entry isn't part of its own code
+            for (range, _, _) in self
+                .entries
+                .iter()
+                .filter(|(_, _, origin)| *origin == FeatureCodeOrigin::Generated)
+                .filter(|(range, _, _)| range.start >= *code_start && range.end <= offset)
+            {
+                line -= lines(range.clone());
+            }
+        }
+        Some((origin, line + 1))
+    }
+
+    /// Record that generated code was inserted into the feature code
+    ///
+    /// Entries after the insertion point are moved along, and an entry the
+    /// code was inserted into grows to cover it.
+    #[cfg(feature = "fontir")]
+    pub(crate) fn insert_generated(&mut self, inserted: Range<usize>) {
+        let length = inserted.len();
+        for (range, code_start, _) in self.entries.iter_mut() {
+            if range.start >= inserted.start {
+                *range = range.start + length..range.end + length;
+                *code_start += length;
+            } else if range.end > inserted.start {
+                range.end += length;
+            }
+        }
+        // Put it first, so that it is found before any entry it was inserted into
+        self.entries.insert(
+            0,
+            (
+                inserted.clone(),
+                inserted.start,
+                FeatureCodeOrigin::Generated,
+            ),
+        );
+    }
+}
+
 impl Features {
     /// Serialize to a single string of AFDKO feature code.
     pub fn to_fea(&self) -> String {
+        self.to_fea_with_source_map().0
+    }
+
+    /// Serialize to a single string of AFDKO feature code, recording where
+    /// each class, prefix and feature was placed
+    ///
+    /// This is used to point errors in the generated code back to the entry
+    /// the user actually wrote.
+    pub fn to_fea_with_source_map(&self) -> (String, FeatureSourceMap) {
         let mut fea = String::new();
+        let mut map = FeatureSourceMap::default();
+        let mut push = |fea: &mut String, before: String, code: &str, after: &str, origin| {
+            let start = fea.len();
+            fea.push_str(&before);
+            let code_start = fea.len();
+            fea.push_str(code);
+            fea.push_str(after);
+            map.entries.push((start..fea.len(), code_start, origin));
+        };
         for (name, glyphs) in &self.classes {
-            fea.push_str(&format!("@{} = [{}];\n", name, glyphs.code));
+            push(
+                &mut fea,
+                format!("@{} = [", name),
+                &glyphs.code,
+                "];\n",
+                FeatureCodeOrigin::Class(name.clone()),
+            );
         }
         for (prefix, code) in &self.prefixes {
-            if prefix != "anonymous" {
-                fea.push_str(&format!("# Prefix: {}\n", prefix));
-            }
-            fea.push_str(&code.code);
-            fea.push('\n');
+            let before = if prefix != "anonymous" {
+                format!("# Prefix: {}\n", prefix)
+            } else {
+                String::new()
+            };
+            push(
+                &mut fea,
+                before,
+                &code.code,
+                "\n",
+                FeatureCodeOrigin::Prefix(prefix.clone()),
+            );
         }
         for (name, code) in &self.features {
-            fea.push_str(&format!(
-                "feature {} {{\n{}\n}} {};\n",
-                name, code.code, name
-            ));
+            push(
+                &mut fea,
+                format!("feature {} {{\n", name),
+                &code.code,
+                &format!("\n}} {};\n", name),
+                FeatureCodeOrigin::Feature(name.clone()),
+            );
         }
-        fea
+        (fea, map)
     }
 
     /// A very naive parser that just puts everything into the anonymous prefix.
@@ -313,3 +443,75 @@ mod fontra {
         }
     }
 }
+
+mod uncompiled {
+    use itertools::Itertools;
+    use sr_aef::{
+        fea_rs_ast::{AsFea, LanguageSystemStatement},
+        UncompileContext,
+    };
+
+    use super::{Features, PossiblyAutomaticCode};
+
+    /// Split the result of uncompiling a binary font into the babelfont features model.
+    ///
+    /// Named classes become [Features::classes]; language systems, mark classes and
+    /// lookups become [Features::prefixes]; each feature becomes an entry in
+    /// [Features::features] referencing its lookups. Everything is marked as automatic,
+    /// since it was generated from the binary and can be generated again.
+    impl From<&UncompileContext<'_>> for Features {
+        fn from(context: &UncompileContext<'_>) -> Self {
+            let mut features = Features::default();
+            for (class_name, glyphs) in context.named_classes.iter() {
+                let glyphs = glyphs.glyphs.iter().map(|g| g.as_fea("")).join(" ");
+                features.classes.insert(
+                    class_name.clone(),
+                    PossiblyAutomaticCode::new_automatic(glyphs),
+                );
+            }
+            let language_systems = context
+                .language_systems
+                .iter()
+                .flat_map(|(script, languages)| {
+                    languages.iter().map(move |language| {
+                        LanguageSystemStatement::new(script.to_string(), language.to_string())
+                            .as_fea("")
+                    })
+                })
+                .join("\n");
+            if !language_systems.is_empty() {
+                features.prefixes.insert(
+                    "LanguageSystems".into(),
+                    PossiblyAutomaticCode::new_automatic(language_systems),
+                );
+            }
+            let mark_classes = context
+                .mark_classes
+                .values()
+                .flatten()
+                .map(|definition| definition.as_fea(""))
+                .join("\n");
+            if !mark_classes.is_empty() {
+                features.prefixes.insert(
+                    "MarkClasses".into(),
+                    PossiblyAutomaticCode::new_automatic(mark_classes),
+                );
+            }
+            for (lookup_name, lookup) in context.lookups.iter() {
+                features.prefixes.insert(
+                    lookup_name.clone(),
+                    PossiblyAutomaticCode::new_automatic(lookup.as_fea("")),
+                );
+            }
+            for (feature_name, lookups) in context.features.iter() {
+                features.features.push((
+                    feature_name.clone(),
+                    PossiblyAutomaticCode::new_automatic(
+                        lookups.iter().map(|l| l.as_fea("")).join("\n"),
+                    ),
+                ));
+            }
+            features
+        }
+    }
+}
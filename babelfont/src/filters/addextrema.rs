@@ -0,0 +1,394 @@
+use kurbo::{CubicBez, ParamCurve, ParamCurveExtrema, PathSeg, Point, QuadBez};
+
+use crate::{filters::FontFilter, BabelfontError, Glyph, Node, NodeType, Path, Shape};
+
+/// Extrema closer than this (in font units) to a segment's end points are not added
+const MIN_DISTANCE: f64 = 0.5;
+/// Parameters closer together than this are treated as the same split
+const PARAMETER_EPSILON: f64 = 1e-3;
+
+/// A filter that adds points at the horizontal and vertical extrema of curves
+///
+/// Curve segments are split at the extrema using kurbo. Where the layers of a glyph
+/// are interpolation-compatible, a split needed in any one layer is made at the
+/// same curve parameter in every layer, so the layers stay compatible. New points
+/// are inserted into the existing paths, so the other nodes keep their smooth flags
+/// and format-specific data.
+#[derive(Debug, Clone, Default)]
+pub struct AddExtrema;
+
+/// A curved segment of a path, ending at an on-curve node
+struct CurveSegment {
+    /// The index of the on-curve node ending the segment
+    end: usize,
+    /// The indices of the segment's off-curve nodes
+    offcurves: Vec<usize>,
+    /// The segment's curves: one cubic, or one quadratic per off-curve point
+    curves: Vec<PathSeg>,
+}
+
+impl AddExtrema {
+    /// Create a new AddExtrema filter
+    pub fn new() -> Self {
+        AddExtrema
+    }
+
+    fn add_extrema_to_glyph(&self, glyph: &mut Glyph) -> Result<(), BabelfontError> {
+        let segments: Vec<Vec<Vec<CurveSegment>>> = glyph
+            .layers
+            .iter()
+            .map(|layer| layer.paths().map(curve_segments).collect())
+            .collect();
+        let interpolating: Vec<usize> = glyph
+            .layers
+            .iter()
+            .enumerate()
+            .filter(|(_, layer)| layer.should_interpolate())
+            .map(|(index, _)| index)
+            .collect();
+        let compatible = interpolating.windows(2).all(|pair| {
+            let (a, b) = (&glyph.layers[pair[0]], &glyph.layers[pair[1]]);
+            a.paths().count() == b.paths().count()
+                && a.paths()
+                    .zip(b.paths())
+                    .all(|(a, b)| a.closed == b.closed && a.signature() == b.signature())
+        });
+        if !compatible {
+            log::warn!(
+                "Layers of glyph {} are not compatible; adding extrema separately",
+                glyph.name
+            );
+        }
+
+        // Work out where to split every curve of every segment of every path in every layer
+        let mut splits: Vec<Vec<Vec<Vec<Vec<f64>>>>> = segments
+            .iter()
+            .map(|paths| {
+                paths
+                    .iter()
+                    .map(|segments| {
+                        segments
+                            .iter()
+                            .map(|segment| segment.curves.iter().map(split_points).collect())
+                            .collect()
+                    })
+                    .collect()
+            })
+            .collect();
+        if compatible && interpolating.len() > 1 {
+            let first = interpolating[0];
+            for path_ix in 0..segments[first].len() {
+                for segment_ix in 0..segments[first][path_ix].len() {
+                    for curve_ix in 0..segments[first][path_ix][segment_ix].curves.len() {
+                        let mut merged: Vec<f64> = interpolating
+                            .iter()
+                            .flat_map(|&layer_ix| {
+                                splits[layer_ix][path_ix][segment_ix][curve_ix].clone()
+                            })
+                            .collect();
+                        merged.sort_by(|a, b| a.total_cmp(b));
+                        merged.dedup_by(|a, b| (*a - *b).abs() < PARAMETER_EPSILON);
+                        for &layer_ix in interpolating.iter() {
+                            splits[layer_ix][path_ix][segment_ix][curve_ix] = merged.clone();
+                        }
+                    }
+                }
+            }
+        }
+
+        for ((layer, segments), splits) in glyph.layers.iter_mut().zip(segments).zip(splits) {
+            let mut paths = segments.iter().zip(splits.iter());
+            for shape in layer.shapes.iter_mut() {
+                if let Shape::Path(path) = shape {
+                    let Some((segments, splits)) = paths.next() else {
+                        continue;
+                    };
+                    if splits.iter().flatten().any(|ts| !ts.is_empty()) {
+                        split_path(path, segments, splits);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn point(node: &Node) -> Point {
+    Point::new(node.x, node.y)
+}
+
+/// The curved segments of a path, in node order
+fn curve_segments(path: &Path) -> Vec<CurveSegment> {
+    let len = path.nodes.len();
+    let oncurves: Vec<usize> = (0..len)
+        .filter(|&ix| path.nodes[ix].nodetype != NodeType::OffCurve)
+        .collect();
+    let mut segments = vec![];
+    for (position, &end) in oncurves.iter().enumerate() {
+        let start = match position.checked_sub(1) {
+            Some(previous) => oncurves[previous],
+            None if path.closed => oncurves[oncurves.len() - 1],
+            None => continue,
+        };
+        let offcurves: Vec<usize> = (1..len)
+            .map(|step| (start + step) % len)
+            .take_while(|&ix| ix != end)
+            .collect();
+        let start_point = point(&path.nodes[start]);
+        let end_point = point(&path.nodes[end]);
+        let controls: Vec<Point> = offcurves.iter().map(|&ix| point(&path.nodes[ix])).collect();
+        let curves = match (path.nodes[end].nodetype, controls.as_slice()) {
+            (NodeType::Curve, &[p1, p2]) => {
+                vec![PathSeg::Cubic(CubicBez::new(start_point, p1, p2, end_point))]
+            }
+            (NodeType::QCurve, controls) if !controls.is_empty() => {
+                // Quadratic splines have implied on-curve points between off-curve points
+                (0..controls.len())
+                    .map(|ix| {
+                        let from = if ix == 0 {
+                            start_point
+                        } else {
+                            controls[ix - 1].midpoint(controls[ix])
+                        };
+                        let to = if ix == controls.len() - 1 {
+                            end_point
+                        } else {
+                            controls[ix].midpoint(controls[ix + 1])
+                        };
+                        PathSeg::Quad(QuadBez::new(from, controls[ix], to))
+                    })
+                    .collect()
+            }
+            _ => continue,
+        };
+        segments.push(CurveSegment {
+            end,
+            offcurves,
+            curves,
+        });
+    }
+    segments
+}
+
+/// The parameters at which a curve needs splitting
+fn split_points(curve: &PathSeg) -> Vec<f64> {
+    let start = curve.start();
+    let end = curve.end();
+    let mut ts: Vec<f64> = curve
+        .extrema()
+        .into_iter()
+        .filter(|&t| {
+            let point = curve.eval(t);
+            point.distance(start) > MIN_DISTANCE && point.distance(end) > MIN_DISTANCE
+        })
+        .collect();
+    ts.sort_by(|a, b| a.total_cmp(b));
+    ts.dedup_by(|a, b| (*a - *b).abs() < PARAMETER_EPSILON);
+    ts
+}
+
+/// A smooth on-curve node of the given type
+fn smooth_node(at: Point, nodetype: NodeType) -> Node {
+    let mut node = match nodetype {
+        NodeType::QCurve => Node::new_qcurve(at.x, at.y),
+        _ => Node::new_curve(at.x, at.y),
+    };
+    node.smooth = true;
+    node
+}
+
+/// The nodes replacing a segment once its curves are split, ending with its final node
+fn split_segment(path: &Path, segment: &CurveSegment, splits: &[Vec<f64>]) -> Vec<Node> {
+    let end = &path.nodes[segment.end];
+    let mut nodes = vec![];
+    for (ix, (curve, ts)) in segment.curves.iter().zip(splits.iter()).enumerate() {
+        if ts.is_empty() {
+            // Keep the original off-curve points of curves which aren't split
+            match curve {
+                PathSeg::Cubic(_) => {
+                    nodes.extend(segment.offcurves.iter().map(|&o| path.nodes[o].clone()))
+                }
+                _ => nodes.push(path.nodes[segment.offcurves[ix]].clone()),
+            }
+        } else {
+            let mut from = 0.0;
+            for (sub_ix, &to) in ts.iter().chain(std::iter::once(&1.0)).enumerate() {
+                let sub = curve.subsegment(from..to);
+                from = to;
+                match sub {
+                    PathSeg::Cubic(c) => {
+                        nodes.push(Node::new_offcurve(c.p1.x, c.p1.y));
+                        nodes.push(Node::new_offcurve(c.p2.x, c.p2.y));
+                    }
+                    PathSeg::Quad(q) => nodes.push(Node::new_offcurve(q.p1.x, q.p1.y)),
+                    PathSeg::Line(_) => {}
+                }
+                if sub_ix < ts.len() {
+                    nodes.push(smooth_node(sub.end(), end.nodetype));
+                }
+            }
+        }
+        if ix + 1 < segment.curves.len() {
+            // Make the implied on-curve point between two quadratic curves explicit
+            // if either side of it has moved
+            let next_split = splits.get(ix + 1).is_some_and(|ts| !ts.is_empty());
+            if !ts.is_empty() || next_split {
+                nodes.push(smooth_node(curve.end(), NodeType::QCurve));
+            }
+        }
+    }
+    nodes.push(end.clone());
+    nodes
+}
+
+/// Insert the points splitting a path's curve segments at the given parameters
+fn split_path(path: &mut Path, segments: &[CurveSegment], splits: &[Vec<Vec<f64>>]) {
+    let mut replacements = std::collections::HashMap::new();
+    let mut replaced_offcurves = std::collections::HashSet::new();
+    for (segment, splits) in segments.iter().zip(splits.iter()) {
+        if splits.iter().all(|ts| ts.is_empty()) {
+            continue;
+        }
+        replacements.insert(segment.end, split_segment(path, segment, splits));
+        replaced_offcurves.extend(segment.offcurves.iter().copied());
+    }
+    let mut nodes = vec![];
+    for (ix, node) in path.nodes.iter().enumerate() {
+        if let Some(replacement) = replacements.remove(&ix) {
+            nodes.extend(replacement);
+        } else if !replaced_offcurves.contains(&ix) {
+            nodes.push(node.clone());
+        }
+    }
+    path.nodes = nodes;
+    path.rotate_to_preferred_representation();
+}
+
+impl FontFilter for AddExtrema {
+    fn apply(&self, font: &mut crate::Font) -> Result<(), BabelfontError> {
+        log::info!("Adding extrema");
+        for glyph in font.glyphs.iter_mut() {
+            self.add_extrema_to_glyph(glyph)?;
+        }
+        Ok(())
+    }
+
+    fn from_str(_s: &str) -> Result<Self, BabelfontError>
+    where
+        Self: Sized,
+    {
+        Ok(AddExtrema::new())
+    }
+
+    #[cfg(feature = "cli")]
+    fn arg() -> clap::Arg
+    where
+        Self: Sized,
+    {
+        clap::Arg::new("addextrema")
+            .long("add-extrema")
+            .help("Add points at the extrema of curves")
+            .action(clap::ArgAction::SetTrue)
+    }
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Font, Layer, LayerType, Master, Node, NodeType};
+
+    // A closed "D" shape whose curve has its rightmost point at a different
+    // place in each master
+    fn d_shape(lower: f64) -> Shape {
+        Shape::Path(Path {
+            nodes: vec![
+                Node::new_line(0.0, 200.0),
+                Node::new_offcurve(200.0, 200.0),
+                Node::new_offcurve(lower, 0.0),
+                Node::new_curve(0.0, 0.0),
+            ],
+            closed: true,
+            format_specific: Default::default(),
+        })
+    }
+
+    #[test]
+    fn test_add_extrema_compatibly() {
+        let mut font = Font::new();
+        let mut glyph = Glyph {
+            name: "D".into(),
+            ..Default::default()
+        };
+        for (id, lower) in [("m01", 200.0), ("m02", 100.0)] {
+            font.masters.push(Master::new(id, id, Default::default()));
+            let mut layer = Layer::new(500.0);
+            layer.master = LayerType::DefaultForMaster(id.into());
+            layer.shapes = vec![d_shape(lower)];
+            glyph.layers.push(layer);
+        }
+        font.glyphs.push(glyph);
+
+        AddExtrema::new().apply(&mut font).unwrap();
+        let glyph = font.glyphs.get("D").unwrap();
+        for layer in glyph.layers.iter() {
+            let path = layer.shapes[0].as_path().unwrap();
+            let curves = path
+                .nodes
+                .iter()
+                .filter(|n| n.nodetype == NodeType::Curve)
+                .count();
+            // Each master's extremum is added to both
+            assert_eq!(curves, 3);
+        }
+        let first = glyph.layers[0].shapes[0].as_path().unwrap();
+        assert!(first
+            .nodes
+            .iter()
+            .any(|n| (n.x - 150.0).abs() < 1e-6 && (n.y - 100.0).abs() < 1e-6));
+        assert!(glyph.compatibility_errors().is_empty());
+    }
+
+    #[test]
+    fn test_add_extrema_keeps_node_data() {
+        let mut path = match d_shape(200.0) {
+            Shape::Path(path) => path,
+            _ => unreachable!(),
+        };
+        path.nodes[3].smooth = true;
+        path.nodes[0]
+            .format_specific
+            .insert("name".into(), "corner".into());
+        let mut glyph = Glyph {
+            name: "D".into(),
+            ..Default::default()
+        };
+        let mut layer = Layer::new(500.0);
+        layer.shapes = vec![Shape::Path(path)];
+        glyph.layers.push(layer);
+
+        AddExtrema::new().add_extrema_to_glyph(&mut glyph).unwrap();
+        let path = glyph.layers[0].shapes[0].as_path().unwrap();
+        let types: Vec<NodeType> = path.nodes.iter().map(|n| n.nodetype).collect();
+        assert_eq!(
+            types,
+            vec![
+                NodeType::Line,
+                NodeType::OffCurve,
+                NodeType::OffCurve,
+                NodeType::Curve,
+                NodeType::OffCurve,
+                NodeType::OffCurve,
+                NodeType::Curve,
+            ]
+        );
+        assert_eq!(
+            path.nodes[0].format_specific.get("name"),
+            Some(&serde_json::Value::from("corner"))
+        );
+        // The new extremum is smooth, and the original curve point keeps its flag
+        assert!(path.nodes[3].smooth);
+        assert!((path.nodes[3].x - 150.0).abs() < 1e-6);
+        assert!(path.nodes[6].smooth);
+    }
+}
@@ -0,0 +1,27 @@
+use std::{collections::HashMap, sync::LazyLock};
+
+const AGL_STR: &str = include_str!("../../resources/agl.txt");
+
+/// Codepoints and their Adobe Glyph List names
+static AGL: LazyLock<HashMap<u32, &'static str>> = LazyLock::new(|| {
+    AGL_STR
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let (codepoint, name) = line.split_once(';')?;
+            Some((u32::from_str_radix(codepoint, 16).ok()?, name.trim()))
+        })
+        .collect()
+});
+
+/// The Adobe Glyph List name for a codepoint, if it has one
+pub(crate) fn name_for_codepoint(codepoint: u32) -> Option<&'static str> {
+    AGL.get(&codepoint).copied()
+}
+
+/// The codepoint of an Adobe Glyph List name, if it has one
+pub(crate) fn codepoint_for_name(name: &str) -> Option<u32> {
+    AGL.iter()
+        .find(|(_, agl_name)| **agl_name == name)
+        .map(|(codepoint, _)| *codepoint)
+}
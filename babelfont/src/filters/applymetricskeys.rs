@@ -0,0 +1,494 @@
+use std::collections::HashMap;
+
+use smol_str::SmolStr;
+
+use crate::{
+    convertors::glyphs3::{KEY_METRIC_LEFT, KEY_METRIC_RIGHT, KEY_METRIC_WIDTH},
+    filters::FontFilter,
+    BabelfontError, Font, Layer, LayerType, Master, Shape,
+};
+
+/// Which metric a key sets
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Side {
+    Left,
+    Right,
+    Width,
+}
+
+impl Side {
+    fn layer_key(self) -> &'static str {
+        match self {
+            Side::Left => KEY_METRIC_LEFT,
+            Side::Right => KEY_METRIC_RIGHT,
+            Side::Width => KEY_METRIC_WIDTH,
+        }
+    }
+
+    fn glyph_key(self) -> &'static str {
+        match self {
+            Side::Left => "metric_left",
+            Side::Right => "metric_right",
+            Side::Width => "metric_width",
+        }
+    }
+}
+
+/// The current spacing of a layer
+#[derive(Debug, Clone, Copy)]
+struct Metrics {
+    lsb: f64,
+    rsb: f64,
+    width: f64,
+    empty: bool,
+}
+
+impl Metrics {
+    /// The metric a reference picks up; `|` swaps the sides
+    fn get(&self, side: Side, opposite: bool) -> f64 {
+        match (side, opposite) {
+            (Side::Left, false) | (Side::Right, true) => self.lsb,
+            (Side::Right, false) | (Side::Left, true) => self.rsb,
+            (Side::Width, _) => self.width,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Visiting,
+    Done,
+}
+
+/// A filter that evaluates Glyphs metrics keys to space glyphs
+///
+/// Glyphs.app stores spacing as expressions on the glyph or on individual
+/// layers, such as `=H` (the same left side bearing as `H`), `=|n` (the left
+/// side bearing equals `n`'s right side bearing), `=o+10` or `=|` (the same as
+/// this glyph's other side). Layer keys override glyph keys. Keys are evaluated
+/// separately in each master, resolving any glyphs they refer to (and any glyphs
+/// used as components) first; reference cycles are reported and broken using
+/// the current metrics. Left side bearings are applied by shifting the layer's
+/// outlines and anchors, then the width key (or failing that, the right side
+/// bearing key) sets the advance width.
+#[derive(Debug, Clone, Default)]
+pub struct ApplyMetricsKeys;
+
+impl ApplyMetricsKeys {
+    /// Create a new ApplyMetricsKeys filter
+    pub fn new() -> Self {
+        ApplyMetricsKeys
+    }
+
+    fn resolve(
+        &self,
+        font: &mut Font,
+        master: &Master,
+        name: &SmolStr,
+        state: &mut HashMap<SmolStr, State>,
+    ) -> Result<(), BabelfontError> {
+        match state.get(name) {
+            Some(State::Done) => return Ok(()),
+            Some(State::Visiting) => {
+                log::warn!(
+                    "Metrics keys of glyph {} are part of a reference cycle",
+                    name
+                );
+                return Ok(());
+            }
+            None => {}
+        }
+        state.insert(name.clone(), State::Visiting);
+        let keys = metrics_keys(font, name, master);
+        let Some(layer) = font.master_layer_for(name, master) else {
+            state.insert(name.clone(), State::Done);
+            return Ok(());
+        };
+        let exists = |n: &str| font.glyphs.get(n).is_some();
+        let mut dependencies: Vec<SmolStr> =
+            layer.components().map(|c| c.reference.clone()).collect();
+        for (side, key) in keys.iter() {
+            // Evaluate with dummy values just to find the glyphs the key uses
+            let mut lookup = |reference: Option<&str>, _: bool| {
+                if let Some(reference) = reference {
+                    dependencies.push(reference.into());
+                }
+                Some(1.0)
+            };
+            if let Err(e) = evaluate(key, &exists, &mut lookup) {
+                log::warn!(
+                    "Bad metrics key {:?} in glyph {} ({:?}): {}",
+                    key,
+                    name,
+                    side,
+                    e
+                );
+            }
+        }
+        for dependency in dependencies {
+            if dependency != *name {
+                self.resolve(font, master, &dependency, state)?;
+            }
+        }
+
+        for (side, key) in keys.iter() {
+            let Some(own) = layer_metrics(font, name, master) else {
+                continue;
+            };
+            if own.empty && *side != Side::Width {
+                continue;
+            }
+            let exists = |n: &str| font.glyphs.get(n).is_some();
+            let mut lookup = |reference: Option<&str>, opposite: bool| match reference {
+                Some(reference) => {
+                    layer_metrics(font, reference, master).map(|m| m.get(*side, opposite))
+                }
+                None => Some(own.get(*side, opposite)),
+            };
+            let value = match evaluate(key, &exists, &mut lookup) {
+                Ok(value) => value,
+                Err(e) => {
+                    log::warn!(
+                        "Could not evaluate metrics key {:?} in glyph {}: {}",
+                        key,
+                        name,
+                        e
+                    );
+                    continue;
+                }
+            };
+            let Some(layer) = font.glyphs.get_mut(name).and_then(|glyph| {
+                glyph
+                    .layers
+                    .iter_mut()
+                    .find(|l| l.master == LayerType::DefaultForMaster(master.id.clone()))
+            }) else {
+                continue;
+            };
+            match side {
+                Side::Left => shift(layer, value - own.lsb),
+                Side::Right => layer.width = (own.width - own.rsb + value) as f32,
+                Side::Width => layer.width = value as f32,
+            }
+        }
+        state.insert(name.clone(), State::Done);
+        Ok(())
+    }
+}
+
+/// The metrics keys which apply to a glyph's master layer, in the order to apply them
+fn metrics_keys(font: &Font, name: &str, master: &Master) -> Vec<(Side, String)> {
+    let Some(glyph) = font.glyphs.get(name) else {
+        return vec![];
+    };
+    let layer = font.master_layer_for(name, master);
+    let key = |side: Side| {
+        layer
+            .and_then(|l| l.format_specific.get_optionstring(side.layer_key()))
+            .or_else(|| glyph.format_specific.get_optionstring(side.glyph_key()))
+            .filter(|k| !k.trim().is_empty())
+    };
+    let mut keys = vec![];
+    if let Some(left) = key(Side::Left) {
+        keys.push((Side::Left, left));
+    }
+    if let Some(width) = key(Side::Width) {
+        keys.push((Side::Width, width));
+    } else if let Some(right) = key(Side::Right) {
+        keys.push((Side::Right, right));
+    }
+    keys
+}
+
+/// The current metrics of a glyph's master layer, including its components
+fn layer_metrics(font: &Font, name: &str, master: &Master) -> Option<Metrics> {
+    let layer = font.master_layer_for(name, master)?;
+    let mut bounds: Option<kurbo::Rect> = None;
+    for path in layer
+        .paths()
+        .cloned()
+        .chain(layer.decomposed_components(font))
+    {
+        let Ok(bez) = path.to_kurbo() else {
+            continue;
+        };
+        if bez.elements().is_empty() {
+            continue;
+        }
+        let bbox = kurbo::Shape::bounding_box(&bez);
+        bounds = Some(bounds.map_or(bbox, |b| b.union(bbox)));
+    }
+    let width = layer.width as f64;
+    Some(match bounds {
+        Some(bounds) => Metrics {
+            lsb: bounds.min_x(),
+            rsb: width - bounds.max_x(),
+            width,
+            empty: false,
+        },
+        None => Metrics {
+            lsb: 0.0,
+            rsb: 0.0,
+            width,
+            empty: true,
+        },
+    })
+}
+
+/// Move everything in a layer horizontally, keeping the right side bearing
+fn shift(layer: &mut Layer, delta: f64) {
+    if delta == 0.0 {
+        return;
+    }
+    for shape in layer.shapes.iter_mut() {
+        match shape {
+            Shape::Path(path) => path.nodes.iter_mut().for_each(|node| node.x += delta),
+            Shape::Component(component) => component.transform.translation.0 += delta,
+        }
+    }
+    for anchor in layer.anchors.iter_mut() {
+        anchor.x += delta;
+    }
+    layer.width += delta as f32;
+}
+
+/// Evaluate a metrics key
+///
+/// `lookup` is called with the name of each glyph referred to (or `None` for
+/// the glyph itself) and whether the reference was prefixed with `|`.
+fn evaluate(
+    key: &str,
+    exists: &dyn Fn(&str) -> bool,
+    lookup: &mut dyn FnMut(Option<&str>, bool) -> Option<f64>,
+) -> Result<f64, String> {
+    let key = key.trim();
+    let key = key.strip_prefix('=').unwrap_or(key);
+    let mut parser = Parser {
+        input: key,
+        pos: 0,
+        exists,
+        lookup,
+    };
+    let value = parser.expression()?;
+    parser.skip_whitespace();
+    if parser.pos < parser.input.len() {
+        return Err(format!("unexpected {:?}", &parser.input[parser.pos..]));
+    }
+    Ok(value)
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+    exists: &'a dyn Fn(&str) -> bool,
+    lookup: &'a mut dyn FnMut(Option<&str>, bool) -> Option<f64>,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek().filter(|c| c.is_whitespace()) {
+            self.pos += c.len_utf8();
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expression(&mut self) -> Result<f64, String> {
+        let mut value = self.term()?;
+        loop {
+            if self.eat('+') {
+                value += self.term()?;
+            } else if self.eat('-') {
+                value -= self.term()?;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn term(&mut self) -> Result<f64, String> {
+        let mut value = self.factor()?;
+        loop {
+            if self.eat('*') {
+                value *= self.factor()?;
+            } else if self.eat('/') {
+                value /= self.factor()?;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn factor(&mut self) -> Result<f64, String> {
+        if self.eat('-') {
+            return Ok(-self.factor()?);
+        }
+        if self.eat('(') {
+            let value = self.expression()?;
+            if !self.eat(')') {
+                return Err("missing ')'".to_string());
+            }
+            return Ok(value);
+        }
+        let opposite = self.eat('|');
+        self.skip_whitespace();
+        let start = self.pos;
+        match self.peek() {
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                while let Some(c) = self.peek().filter(|c| c.is_ascii_digit() || *c == '.') {
+                    self.pos += c.len_utf8();
+                }
+                self.input[start..self.pos]
+                    .parse()
+                    .map_err(|_| format!("bad number {:?}", &self.input[start..self.pos]))
+            }
+            Some(c) if is_name_char(c) => {
+                while self.peek().is_some_and(is_name_char) {
+                    self.pos += self.peek().map_or(1, char::len_utf8);
+                }
+                // Glyph names may contain hyphens, so only treat them as
+                // subtraction if the longer name isn't a glyph
+                let mut name = &self.input[start..self.pos];
+                while !(self.exists)(name) {
+                    match name.rfind('-') {
+                        Some(hyphen) if hyphen > 0 => name = &name[..hyphen],
+                        _ => break,
+                    }
+                }
+                self.pos = start + name.len();
+                (self.lookup)(Some(name), opposite).ok_or_else(|| format!("unknown glyph {}", name))
+            }
+            _ if opposite => {
+                (self.lookup)(None, true).ok_or_else(|| "no metrics for this glyph".to_string())
+            }
+            _ => Err(format!("unexpected {:?}", &self.input[start..])),
+        }
+    }
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '.' || c == '_' || c == '-'
+}
+
+impl FontFilter for ApplyMetricsKeys {
+    fn apply(&self, font: &mut Font) -> Result<(), BabelfontError> {
+        log::info!("Applying metrics keys");
+        let names: Vec<SmolStr> = font.glyphs.iter().map(|g| g.name.clone()).collect();
+        for master in font.masters.clone() {
+            let mut state = HashMap::new();
+            for name in names.iter() {
+                self.resolve(font, &master, name, &mut state)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn from_str(_s: &str) -> Result<Self, BabelfontError>
+    where
+        Self: Sized,
+    {
+        Ok(ApplyMetricsKeys::new())
+    }
+
+    #[cfg(feature = "cli")]
+    fn arg() -> clap::Arg
+    where
+        Self: Sized,
+    {
+        clap::Arg::new("applymetricskeys")
+            .long("apply-metrics-keys")
+            .help("Space glyphs by evaluating their Glyphs metrics keys")
+            .action(clap::ArgAction::SetTrue)
+    }
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Glyph, Node, Path};
+
+    fn glyph(name: &str, x: f64, right: f64, width: f32) -> Glyph {
+        let mut layer = Layer::new(width);
+        layer.master = LayerType::DefaultForMaster("m01".into());
+        layer.id = Some("m01".into());
+        layer.shapes.push(Shape::Path(Path {
+            nodes: vec![
+                Node::new_line(x, 0.0),
+                Node::new_line(right, 0.0),
+                Node::new_line(right, 100.0),
+            ],
+            closed: true,
+            format_specific: Default::default(),
+        }));
+        Glyph {
+            name: name.into(),
+            layers: vec![layer],
+            ..Default::default()
+        }
+    }
+
+    fn set_key(font: &mut Font, name: &str, side: Side, key: &str) {
+        font.glyphs.get_mut(name).unwrap().layers[0]
+            .format_specific
+            .insert(side.layer_key().to_string(), key.into());
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let exists = |n: &str| n == "a-cy";
+        let mut lookup = |name: Option<&str>, opposite: bool| match (name, opposite) {
+            (Some("a-cy"), false) => Some(30.0),
+            (Some("a-cy"), true) => Some(20.0),
+            (None, true) => Some(5.0),
+            _ => None,
+        };
+        assert_eq!(evaluate("=a-cy+10", &exists, &mut lookup), Ok(40.0));
+        assert_eq!(evaluate("=|a-cy*2", &exists, &mut lookup), Ok(40.0));
+        assert_eq!(evaluate("=(a-cy-10)/2", &exists, &mut lookup), Ok(10.0));
+        assert_eq!(evaluate("=|", &exists, &mut lookup), Ok(5.0));
+        assert!(evaluate("=b", &exists, &mut lookup).is_err());
+        // Non-breaking spaces are whitespace too
+        assert_eq!(
+            evaluate("=a-cy\u{a0}+\u{a0}10\u{a0}", &exists, &mut lookup),
+            Ok(40.0)
+        );
+    }
+
+    #[test]
+    fn test_apply_metrics_keys() {
+        let mut font = Font::new();
+        font.masters
+            .push(Master::new("m01", "m01", Default::default()));
+        font.glyphs.push(glyph("H", 50.0, 450.0, 500.0));
+        font.glyphs.push(glyph("n", 10.0, 200.0, 300.0));
+        font.glyphs.push(glyph("o", 0.0, 100.0, 100.0));
+        // n takes its left side from H and its right side from its own left
+        set_key(&mut font, "n", Side::Left, "=H");
+        set_key(&mut font, "n", Side::Right, "=|");
+        // o refers to n, which must be spaced first
+        set_key(&mut font, "o", Side::Left, "=n+10");
+        set_key(&mut font, "o", Side::Width, "=300");
+        // A cycle is broken rather than looping forever
+        set_key(&mut font, "H", Side::Right, "=o");
+
+        ApplyMetricsKeys::new().apply(&mut font).unwrap();
+        let master = font.masters[0].clone();
+        let n = layer_metrics(&font, "n", &master).unwrap();
+        assert_eq!((n.lsb, n.rsb, n.width), (50.0, 50.0, 290.0));
+        let o = layer_metrics(&font, "o", &master).unwrap();
+        assert_eq!((o.lsb, o.width), (60.0, 300.0));
+    }
+}
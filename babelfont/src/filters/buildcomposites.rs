@@ -0,0 +1,437 @@
+use std::collections::HashMap;
+
+use smol_str::SmolStr;
+use unicode_normalization::UnicodeNormalization;
+
+use super::retaincodepoints::parse_range;
+use crate::{
+    common::decomposition::DecomposedAffine, filters::FontFilter, BabelfontError, Component, Font,
+    Glyph, GlyphCategory, Layer, LayerType, Shape,
+};
+
+/// How to build one composite glyph
+#[derive(Debug, Clone, PartialEq)]
+struct Recipe {
+    name: Option<SmolStr>,
+    codepoint: Option<u32>,
+    base: SmolStr,
+    /// Marks to attach, each with the anchor on the base to attach it to
+    marks: Vec<(SmolStr, Option<String>)>,
+}
+
+/// A filter that builds accented composite glyphs from a base and marks
+///
+/// Targets are either codepoints, which are decomposed with Unicode canonical
+/// decomposition and built from the glyphs encoding each part, or explicit
+/// Glyphs-style recipes such as `Aacute=A+acute@top`. In every master, each
+/// mark is positioned by aligning its `_anchor` with the matching anchor on the
+/// base (or on a previously attached mark, so marks can stack). If no anchor
+/// is given in a recipe, the first of the mark's attaching anchors which the
+/// base provides is used. The composite takes its advance width from the base.
+/// Glyphs whose marks can't be attached in every master are not built; sparse
+/// masters get a layer only where the base and marks have one.
+///
+/// New glyphs are named after GlyphsData where available, falling back to
+/// `uniXXXX` names. Glyphs which already exist are left untouched.
+#[derive(Debug, Clone, Default)]
+pub struct BuildComposites {
+    codepoints: Vec<u32>,
+    recipes: Vec<Recipe>,
+}
+
+impl BuildComposites {
+    /// Create a new filter building composites for the given codepoints
+    pub fn new(codepoints: impl IntoIterator<Item = u32>) -> Self {
+        BuildComposites {
+            codepoints: codepoints.into_iter().collect(),
+            recipes: vec![],
+        }
+    }
+
+    /// Also build a glyph from a recipe such as `Aacute=A+acute@top`
+    pub fn with_recipe(mut self, recipe: &str) -> Result<Self, BabelfontError> {
+        self.recipes.push(parse_recipe(recipe)?);
+        Ok(self)
+    }
+
+    /// Work out the recipe for a codepoint from its canonical decomposition
+    fn recipe_for_codepoint(font: &Font, codepoint: u32) -> Option<Recipe> {
+        let character = char::from_u32(codepoint)?;
+        let mut parts = std::iter::once(character).nfd();
+        let base = parts.next()?;
+        let marks: Vec<char> = parts.collect();
+        if marks.is_empty() {
+            return None;
+        }
+        let glyph_for = |c: char| {
+            font.glyphs
+                .iter()
+                .find(|g| g.codepoints.contains(&(c as u32)))
+                .map(|g| g.name.clone())
+        };
+        let Some(base) = glyph_for(base) else {
+            log::debug!("No base glyph for U+{:04X}", codepoint);
+            return None;
+        };
+        let mut mark_names = vec![];
+        for mark in marks {
+            let Some(name) = glyph_for(mark) else {
+                log::debug!(
+                    "No glyph for mark U+{:04X} in U+{:04X}",
+                    mark as u32,
+                    codepoint
+                );
+                return None;
+            };
+            mark_names.push((name, None));
+        }
+        Some(Recipe {
+            name: None,
+            codepoint: Some(codepoint),
+            base,
+            marks: mark_names,
+        })
+    }
+
+    /// Build one layer of a composite for the given master
+    fn build_layer(font: &Font, recipe: &Recipe, master_id: &str) -> Option<Layer> {
+        let master = font.masters.iter().find(|m| m.id == master_id)?;
+        let base_layer = font.master_layer_for(&recipe.base, master)?;
+        let mut layer = Layer::new(base_layer.width);
+        layer.master = LayerType::DefaultForMaster(master_id.to_string());
+        layer.id = Some(master_id.to_string());
+        layer
+            .shapes
+            .push(Shape::Component(component(&recipe.base, (0.0, 0.0))));
+
+        let mut available: HashMap<String, (f64, f64)> = base_layer
+            .anchors
+            .iter()
+            .filter(|a| !a.name.starts_with('_'))
+            .map(|a| (a.name.clone(), (a.x, a.y)))
+            .collect();
+        for (mark, anchor) in recipe.marks.iter() {
+            let Some(mark_layer) = font.master_layer_for(mark, master) else {
+                log::warn!("Mark {} has no layer in master {}", mark, master_id);
+                return None;
+            };
+            let attachment = mark_layer.anchors.iter().find(|a| match anchor {
+                Some(anchor) => a.name.strip_prefix('_') == Some(anchor.as_str()),
+                None => a
+                    .name
+                    .strip_prefix('_')
+                    .is_some_and(|name| available.contains_key(name)),
+            });
+            let offset = match attachment {
+                Some(attachment) => {
+                    let name = &attachment.name[1..];
+                    let Some(&(x, y)) = available.get(name) else {
+                        log::warn!(
+                            "Base of {} has no anchor {} in master {}",
+                            recipe.base,
+                            name,
+                            master_id
+                        );
+                        return None;
+                    };
+                    (x - attachment.x, y - attachment.y)
+                }
+                None => {
+                    log::warn!(
+                        "No anchor to attach {} to {} in master {}",
+                        mark,
+                        recipe.base,
+                        master_id
+                    );
+                    return None;
+                }
+            };
+            layer.shapes.push(Shape::Component(component(mark, offset)));
+            // The mark's own anchors are now available for stacking further marks
+            for a in mark_layer
+                .anchors
+                .iter()
+                .filter(|a| !a.name.starts_with('_'))
+            {
+                available.insert(a.name.clone(), (a.x + offset.0, a.y + offset.1));
+            }
+        }
+        Some(layer)
+    }
+}
+
+fn component(reference: &str, translation: (f64, f64)) -> Component {
+    Component {
+        reference: reference.into(),
+        transform: DecomposedAffine {
+            translation,
+            ..Default::default()
+        },
+        location: Default::default(),
+        format_specific: Default::default(),
+    }
+}
+
+/// The name to give a new glyph for a codepoint
+///
+/// This is the Glyphs.app name if the `glyphs` feature is enabled, or else
+/// the Adobe Glyph List name, falling back to a `uniXXXX` name.
+fn name_for_codepoint(codepoint: u32) -> SmolStr {
+    #[cfg(feature = "glyphs")]
+    if let Some(name) = super::glyphsdata::name_for_codepoint(codepoint) {
+        return name.into();
+    }
+    if let Some(name) = super::agl::name_for_codepoint(codepoint) {
+        return name.into();
+    }
+    if codepoint <= 0xFFFF {
+        format!("uni{:04X}", codepoint).into()
+    } else {
+        format!("u{:05X}", codepoint).into()
+    }
+}
+
+fn parse_recipe(s: &str) -> Result<Recipe, BabelfontError> {
+    let Some((name, parts)) = s.split_once('=') else {
+        return Err(BabelfontError::FilterError(format!(
+            "Invalid composite recipe: {}",
+            s
+        )));
+    };
+    let mut parts = parts.split('+').map(str::trim);
+    let base = parts.next().filter(|b| !b.is_empty()).ok_or_else(|| {
+        BabelfontError::FilterError(format!("Composite recipe has no base: {}", s))
+    })?;
+    let marks = parts
+        .map(|part| match part.split_once('@') {
+            Some((mark, anchor)) => (mark.into(), Some(anchor.to_string())),
+            None => (part.into(), None),
+        })
+        .collect();
+    #[cfg(feature = "glyphs")]
+    let codepoint = super::glyphsdata::codepoint_for_name(name.trim());
+    #[cfg(not(feature = "glyphs"))]
+    let codepoint = None;
+    let codepoint = codepoint.or_else(|| super::agl::codepoint_for_name(name.trim()));
+    Ok(Recipe {
+        name: Some(name.trim().into()),
+        codepoint,
+        base: base.into(),
+        marks,
+    })
+}
+
+impl FontFilter for BuildComposites {
+    fn apply(&self, font: &mut Font) -> Result<(), BabelfontError> {
+        log::info!("Building composite glyphs");
+        let mut recipes: Vec<Recipe> = self
+            .codepoints
+            .iter()
+            .filter(|&&cp| !font.glyphs.iter().any(|g| g.codepoints.contains(&cp)))
+            .filter_map(|&cp| Self::recipe_for_codepoint(font, cp))
+            .collect();
+        for recipe in self.recipes.iter() {
+            for glyph in std::iter::once(&recipe.base).chain(recipe.marks.iter().map(|(m, _)| m)) {
+                if font.glyphs.get(glyph).is_none() {
+                    return Err(BabelfontError::GlyphNotFound {
+                        glyph: glyph.to_string(),
+                    });
+                }
+            }
+            recipes.push(recipe.clone());
+        }
+
+        let masters: Vec<(String, bool)> = font
+            .masters
+            .iter()
+            .map(|m| (m.id.clone(), m.is_sparse(font)))
+            .collect();
+        for recipe in recipes {
+            let name = recipe
+                .name
+                .clone()
+                .or_else(|| recipe.codepoint.map(name_for_codepoint))
+                .unwrap_or_default();
+            if font.glyphs.get(&name).is_some() {
+                log::debug!("Glyph {} already exists, not building it", name);
+                continue;
+            }
+            // Every full master needs a layer; sparse masters only get one if
+            // the base and marks have layers there
+            let Some(layers) = masters
+                .iter()
+                .filter_map(|(id, sparse)| match Self::build_layer(font, &recipe, id) {
+                    Some(layer) => Some(Some(layer)),
+                    None if *sparse => None,
+                    None => Some(None),
+                })
+                .collect::<Option<Vec<_>>>()
+            else {
+                log::warn!("Could not build composite glyph {}", name);
+                continue;
+            };
+            log::debug!("Building composite glyph {}", name);
+            font.glyphs.push(Glyph {
+                name,
+                codepoints: recipe.codepoint.into_iter().collect(),
+                category: GlyphCategory::Base,
+                exported: true,
+                layers,
+                ..Default::default()
+            });
+        }
+        Ok(())
+    }
+
+    fn from_str(s: &str) -> Result<Self, BabelfontError>
+    where
+        Self: Sized,
+    {
+        let mut filter = BuildComposites::default();
+        for item in s.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            if item.contains('=') {
+                filter = filter.with_recipe(item)?;
+            } else {
+                filter.codepoints.extend(parse_range(item)?);
+            }
+        }
+        Ok(filter)
+    }
+
+    #[cfg(feature = "cli")]
+    fn arg() -> clap::Arg
+    where
+        Self: Sized,
+    {
+        clap::Arg::new("buildcomposites")
+            .long("build-composites")
+            .help("Build composite glyphs for the given codepoints, ranges (00C0-00FF) or recipes (Aacute=A+acute@top), comma-separated")
+            .value_name("TARGETS")
+            .action(clap::ArgAction::Append)
+    }
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Anchor, Master};
+
+    fn anchor(name: &str, x: f64, y: f64) -> Anchor {
+        Anchor {
+            x,
+            y,
+            name: name.to_string(),
+            format_specific: Default::default(),
+        }
+    }
+
+    fn glyph(name: &str, codepoint: u32, width: f32, anchors: Vec<Anchor>) -> Glyph {
+        let mut layer = Layer::new(width);
+        layer.master = LayerType::DefaultForMaster("m01".into());
+        layer.anchors = anchors;
+        Glyph {
+            name: name.into(),
+            codepoints: vec![codepoint],
+            layers: vec![layer],
+            exported: true,
+            ..Default::default()
+        }
+    }
+
+    fn font() -> Font {
+        let mut font = Font::new();
+        font.masters
+            .push(Master::new("m01", "m01", Default::default()));
+        font.glyphs.push(glyph("A", 0x41, 600.0, vec![anchor("top", 300.0, 700.0)]));
+        font.glyphs.push(glyph(
+            "acutecomb",
+            0x301,
+            0.0,
+            vec![anchor("_top", 100.0, 500.0), anchor("top", 100.0, 650.0)],
+        ));
+        font.glyphs.push(glyph(
+            "dotaccentcomb",
+            0x307,
+            0.0,
+            vec![anchor("_top", 50.0, 500.0)],
+        ));
+        font
+    }
+
+    #[test]
+    fn test_parse_recipe() {
+        let filter =
+            BuildComposites::from_str("00C1, A.alt=A+acutecomb@top+dotaccentcomb").unwrap();
+        assert_eq!(filter.codepoints, vec![0xC1]);
+        let recipe = &filter.recipes[0];
+        assert_eq!(recipe.name.as_deref(), Some("A.alt"));
+        assert_eq!(recipe.base, "A");
+        assert_eq!(
+            recipe.marks,
+            vec![
+                ("acutecomb".into(), Some("top".to_string())),
+                ("dotaccentcomb".into(), None)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_build_composites() {
+        let mut font = font();
+        BuildComposites::from_str("00C1, A.alt=A+acutecomb+dotaccentcomb")
+            .unwrap()
+            .apply(&mut font)
+            .unwrap();
+
+        let aacute = font
+            .glyphs
+            .iter()
+            .find(|g| g.codepoints == vec![0xC1])
+            .unwrap();
+        let layer = &aacute.layers[0];
+        assert_eq!(layer.width, 600.0);
+        let components: Vec<_> = layer.components().collect();
+        assert_eq!(components.len(), 2);
+        assert_eq!(components[1].reference, "acutecomb");
+        assert_eq!(components[1].transform.translation, (200.0, 200.0));
+
+        // The dot stacks on the acute's top anchor
+        let stacked = font.glyphs.get("A.alt").unwrap();
+        let components: Vec<_> = stacked.layers[0].components().collect();
+        assert_eq!(components[2].transform.translation, (250.0, 350.0));
+    }
+
+    #[test]
+    fn test_build_composites_skips() {
+        let mut font = font();
+        // A sparse master in which only the base has a layer
+        font.masters
+            .push(Master::new("sparse", "m02", Default::default()));
+        let mut layer = Layer::new(650.0);
+        layer.master = LayerType::DefaultForMaster("m02".into());
+        font.glyphs.get_mut("A").unwrap().layers.push(layer);
+        font.glyphs.push(glyph("B", 0x42, 600.0, vec![]));
+
+        BuildComposites::from_str("A.alt=A+acutecomb, B.alt=B+acutecomb")
+            .unwrap()
+            .apply(&mut font)
+            .unwrap();
+        let built = font.glyphs.get("A.alt").unwrap();
+        assert_eq!(built.layers.len(), 1);
+        // B has no top anchor, so the accent can't be placed
+        assert!(font.glyphs.get("B.alt").is_none());
+    }
+
+    #[test]
+    fn test_name_for_codepoint() {
+        use crate::filters::agl;
+        assert_eq!(name_for_codepoint(0xC1), "Aacute");
+        assert_eq!(name_for_codepoint(0xE000), "uniE000");
+        assert_eq!(name_for_codepoint(0xF0000), "uF0000");
+        // Named by the AGL when Glyphs.app data doesn't have (or isn't used for) it
+        assert_eq!(agl::name_for_codepoint(0x131), Some("dotlessi"));
+        assert_eq!(agl::codepoint_for_name("dotlessi"), Some(0x131));
+        assert_eq!(agl::name_for_codepoint(0xF0000), None);
+    }
+}
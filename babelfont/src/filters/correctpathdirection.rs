@@ -0,0 +1,218 @@
+use kurbo::{BezPath, ParamCurve, Shape as KurboShape};
+
+use crate::{filters::FontFilter, BabelfontError, Glyph, Shape};
+
+/// Which way outer contours should wind
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PathDirection {
+    /// Outer contours counter-clockwise, inner contours clockwise (CFF, UFO, Glyphs)
+    #[default]
+    PostScript,
+    /// Outer contours clockwise, inner contours counter-clockwise (TrueType)
+    TrueType,
+}
+
+impl std::str::FromStr for PathDirection {
+    type Err = BabelfontError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "postscript" | "ps" | "cff" => Ok(PathDirection::PostScript),
+            "truetype" | "tt" => Ok(PathDirection::TrueType),
+            other => Err(BabelfontError::FilterError(format!(
+                "Unknown path direction convention: {}",
+                other
+            ))),
+        }
+    }
+}
+
+/// A filter that reverses contours which wind the wrong way
+///
+/// Each closed contour is classified as outer or inner by counting how many
+/// other contours in the layer contain it, and its signed area is compared
+/// against the chosen [`PathDirection`]. For glyphs whose layers are
+/// interpolation-compatible, the decision is made on the first master layer and
+/// applied to the corresponding contours in every other layer, so that the
+/// layers stay compatible.
+#[derive(Debug, Clone, Default)]
+pub struct CorrectPathDirection(PathDirection);
+
+impl CorrectPathDirection {
+    /// Create a new filter for the given convention
+    pub fn new(direction: PathDirection) -> Self {
+        CorrectPathDirection(direction)
+    }
+
+    /// Work out which closed paths in a list of paths need to be reversed
+    fn paths_to_reverse(&self, paths: &[BezPath]) -> Vec<bool> {
+        paths
+            .iter()
+            .enumerate()
+            .map(|(index, path)| {
+                let Some(point) = path.segments().next().map(|seg| seg.eval(0.5)) else {
+                    return false;
+                };
+                let depth = paths
+                    .iter()
+                    .enumerate()
+                    .filter(|(other, other_path)| *other != index && other_path.winding(point) != 0)
+                    .count();
+                let outer = depth % 2 == 0;
+                let area = path.area();
+                let want_counter_clockwise = outer == (self.0 == PathDirection::PostScript);
+                area != 0.0 && (area > 0.0) != want_counter_clockwise
+            })
+            .collect()
+    }
+
+    pub(crate) fn correct_glyph(&self, glyph: &mut Glyph) -> Result<(), BabelfontError> {
+        let mut closed_paths = vec![];
+        for layer in glyph.layers.iter() {
+            closed_paths.push(
+                layer
+                    .paths()
+                    .filter(|p| p.closed)
+                    .map(|p| p.to_kurbo())
+                    .collect::<Result<Vec<_>, _>>()?,
+            );
+        }
+        let interpolating: Vec<usize> = glyph
+            .layers
+            .iter()
+            .enumerate()
+            .filter(|(_, layer)| layer.should_interpolate())
+            .map(|(index, _)| index)
+            .collect();
+        let compatible = interpolating.windows(2).all(|pair| {
+            let (a, b) = (&closed_paths[pair[0]], &closed_paths[pair[1]]);
+            a.len() == b.len()
+                && a.iter()
+                    .zip(b.iter())
+                    .all(|(a, b)| a.elements().len() == b.elements().len())
+        });
+        // Make the decision once for all compatible layers
+        let shared = if compatible {
+            interpolating
+                .first()
+                .map(|&index| self.paths_to_reverse(&closed_paths[index]))
+        } else {
+            if interpolating.len() > 1 {
+                log::warn!(
+                    "Layers of glyph {} are not compatible; correcting path direction separately",
+                    glyph.name
+                );
+            }
+            None
+        };
+
+        for (index, layer) in glyph.layers.iter_mut().enumerate() {
+            let reverse = match &shared {
+                Some(shared) if interpolating.contains(&index) => shared.clone(),
+                _ => self.paths_to_reverse(&closed_paths[index]),
+            };
+            let mut reverse = reverse.into_iter();
+            for shape in layer.shapes.iter_mut() {
+                if let Shape::Path(path) = shape {
+                    if path.closed && reverse.next().unwrap_or(false) {
+                        path.reverse();
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FontFilter for CorrectPathDirection {
+    fn apply(&self, font: &mut crate::Font) -> Result<(), BabelfontError> {
+        log::info!("Correcting path direction ({:?})", self.0);
+        for glyph in font.glyphs.iter_mut() {
+            self.correct_glyph(glyph)?;
+        }
+        Ok(())
+    }
+
+    fn from_str(s: &str) -> Result<Self, BabelfontError>
+    where
+        Self: Sized,
+    {
+        if s.trim().is_empty() {
+            return Ok(CorrectPathDirection::new(PathDirection::PostScript));
+        }
+        Ok(CorrectPathDirection::new(s.parse()?))
+    }
+
+    #[cfg(feature = "cli")]
+    fn arg() -> clap::Arg
+    where
+        Self: Sized,
+    {
+        clap::Arg::new("correctpathdirection")
+            .long("correct-path-direction")
+            .help("Correct contour direction (optionally 'truetype' or 'postscript', the default)")
+            .value_name("CONVENTION")
+            .action(clap::ArgAction::Append)
+            .default_missing_value("")
+            .required(false)
+    }
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Font, Layer, LayerType, Master, Node, Path};
+
+    fn square(x: f64, y: f64, size: f64, clockwise: bool) -> Shape {
+        let mut nodes = vec![
+            Node::new_line(x, y),
+            Node::new_line(x + size, y),
+            Node::new_line(x + size, y + size),
+            Node::new_line(x, y + size),
+        ];
+        if clockwise {
+            nodes.reverse();
+        }
+        Shape::Path(Path {
+            nodes,
+            closed: true,
+            format_specific: Default::default(),
+        })
+    }
+
+    fn is_counter_clockwise(shape: &Shape) -> bool {
+        shape.as_path().unwrap().to_kurbo().unwrap().area() > 0.0
+    }
+
+    #[test]
+    fn test_correct_path_direction() {
+        let mut font = Font::new();
+        let mut glyph = Glyph {
+            name: "o".into(),
+            ..Default::default()
+        };
+        // Both contours wind counter-clockwise in both masters
+        for (id, size) in [("m01", 300.0), ("m02", 400.0)] {
+            font.masters.push(Master::new(id, id, Default::default()));
+            let mut layer = Layer::new(500.0);
+            layer.master = LayerType::DefaultForMaster(id.into());
+            layer.shapes = vec![
+                square(0.0, 0.0, size, false),
+                square(100.0, 100.0, 100.0, false),
+            ];
+            glyph.layers.push(layer);
+        }
+        font.glyphs.push(glyph);
+
+        CorrectPathDirection::new(PathDirection::TrueType)
+            .apply(&mut font)
+            .unwrap();
+        let glyph = font.glyphs.get("o").unwrap();
+        for layer in glyph.layers.iter() {
+            assert!(!is_counter_clockwise(&layer.shapes[0]));
+            assert!(is_counter_clockwise(&layer.shapes[1]));
+        }
+        assert!(glyph.compatibility_errors().is_empty());
+    }
+}
@@ -1,14 +1,19 @@
 use kurbo::{cubics_to_quadratic_splines, BezPath, CubicBez, PathEl};
 
-use crate::{filters::FontFilter, Node, Path};
+use crate::{
+    filters::{CorrectPathDirection, FontFilter, PathDirection},
+    Node, Path,
+};
 
 use super::curve_filter_common::{
     apply_interpolatable_path_filter, mark_closed_and_normalize, path_el_kind,
 };
 
 /// A filter that converts cubic Bézier curves to quadratic Bézier curves in all glyphs of a font, attempting to keep corresponding paths across layers consistent for better interpolation results. This filter requires the `kurbo` feature to be enabled.
+///
+/// Optionally, contour directions can be corrected after conversion; see [`CubicToQuadratic::with_path_direction`].
 #[derive(Debug, Clone, Default)]
-pub struct CubicToQuadratic;
+pub struct CubicToQuadratic(Option<PathDirection>);
 
 const TOLERANCE: f64 = 0.5;
 
@@ -167,7 +172,14 @@ fn convert_bezpath_independently(path: &BezPath) -> Result<Path, crate::Babelfon
 impl CubicToQuadratic {
     /// Create a new CubicToQuadratic filter
     pub fn new() -> Self {
-        CubicToQuadratic
+        CubicToQuadratic(None)
+    }
+
+    /// Also correct the direction of contours to the given convention after conversion
+    ///
+    /// This is usually [`PathDirection::TrueType`] when producing quadratic outlines.
+    pub fn with_path_direction(direction: PathDirection) -> Self {
+        CubicToQuadratic(Some(direction))
     }
 }
 
@@ -178,14 +190,21 @@ impl FontFilter for CubicToQuadratic {
             "CubicToQuadratic",
             convert_bezpath_independently,
             convert_bezpaths_in_parallel,
-        )
+        )?;
+        if let Some(direction) = self.0 {
+            CorrectPathDirection::new(direction).apply(font)?;
+        }
+        Ok(())
     }
 
-    fn from_str(_s: &str) -> Result<Self, crate::BabelfontError>
+    fn from_str(s: &str) -> Result<Self, crate::BabelfontError>
     where
         Self: Sized,
     {
-        Ok(CubicToQuadratic::new())
+        match s.trim() {
+            "" | "true" => Ok(CubicToQuadratic::new()),
+            direction => Ok(CubicToQuadratic::with_path_direction(direction.parse()?)),
+        }
     }
 
     #[cfg(feature = "cli")]
@@ -195,8 +214,11 @@ impl FontFilter for CubicToQuadratic {
     {
         clap::Arg::new("cubic2quadratic")
             .long("cubic-to-quadratic")
-            .help("Convert cubic Bézier curves to quadratic Bézier curves")
-            .action(clap::ArgAction::SetTrue)
+            .help("Convert cubic Bézier curves to quadratic Bézier curves (optionally, 'truetype' or 'postscript' to also correct contour direction)")
+            .value_name("DIRECTION")
+            .num_args(0..=1)
+            .default_missing_value("")
+            .action(clap::ArgAction::Append)
     }
 }
 
@@ -222,4 +244,19 @@ mod tests {
             .iter()
             .any(|n| n.nodetype == crate::NodeType::Move));
     }
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!(CubicToQuadratic::from_str("").unwrap().0, None);
+        assert_eq!(CubicToQuadratic::from_str("true").unwrap().0, None);
+        assert_eq!(
+            CubicToQuadratic::from_str("truetype").unwrap().0,
+            Some(PathDirection::TrueType)
+        );
+        assert_eq!(
+            CubicToQuadratic::from_str("PS").unwrap().0,
+            Some(PathDirection::PostScript)
+        );
+        assert!(CubicToQuadratic::from_str("sideways").is_err());
+    }
 }
@@ -11,7 +11,8 @@ use indexmap::IndexSet;
 use smol_str::SmolStr;
 
 use crate::{
-    filters::FontFilter, interpolate::interpolate_layer, shape, Component, Glyph, Layer, Shape,
+    filters::FontFilter, interpolate::interpolate_layer, shape, Component, ComponentGraph, Glyph,
+    Layer, Shape,
 };
 
 /// A glyph with pre-computed effective locations for each layer
@@ -124,31 +125,11 @@ impl<'a> DecompositionManager<'a> {
     }
 
     /// If we have a component filter, expand it to include all transitive dependencies
-    fn expand_filter_transitively(&mut self, font: &mut crate::Font) {
+    fn expand_filter_transitively(&mut self, graph: &ComponentGraph) {
         self.components_filter = self.components_filter.as_ref().map(|filter| {
             let mut expanded = filter.clone();
-            let mut to_visit: Vec<SmolStr> = filter.iter().cloned().collect();
-            let mut visited = HashSet::new();
-
-            while let Some(component_name) = to_visit.pop() {
-                if visited.contains(&component_name) {
-                    continue;
-                }
-                visited.insert(component_name.clone());
-
-                // Find all components this component references
-                if let Some(glyph) = font.glyphs.get(&component_name) {
-                    for layer in &glyph.layers {
-                        for shape in &layer.shapes {
-                            if let Shape::Component(comp) = shape {
-                                if !expanded.contains(&comp.reference) {
-                                    expanded.insert(comp.reference.clone());
-                                    to_visit.push(comp.reference.clone());
-                                }
-                            }
-                        }
-                    }
-                }
+            for component_name in filter.iter() {
+                expanded.extend(graph.dependencies(component_name));
             }
             expanded
         });
@@ -300,29 +281,6 @@ impl DecomposeTask {
     }
 }
 
-/// Topologically visit glyphs based on component references
-fn visit_glyph(
-    g: &SmolStr,
-    references: &HashMap<SmolStr, HashSet<SmolStr>>,
-    all_glyphs: &HashSet<SmolStr>,
-    visited: &mut HashSet<SmolStr>,
-    result: &mut Vec<SmolStr>,
-) {
-    if visited.contains(g) {
-        return;
-    }
-    visited.insert(g.clone());
-
-    if let Some(refs) = references.get(g) {
-        for referenced in refs {
-            if all_glyphs.contains(referenced) {
-                visit_glyph(referenced, references, all_glyphs, visited, result);
-            }
-        }
-    }
-    result.push(g.clone());
-}
-
 impl FontFilter for DecomposeComponentReferences {
     fn apply(&self, font: &mut crate::Font) -> Result<(), crate::BabelfontError> {
         log::info!("Decomposing component references");
@@ -333,42 +291,22 @@ impl FontFilter for DecomposeComponentReferences {
             .collect::<Result<Vec<_>, _>>()?;
 
         let mut manager = DecompositionManager::new(self.0.clone(), &fontdrasil_axes);
-        manager.expand_filter_transitively(font);
-
-        let mut all_tasks = vec![];
-
-        // Collect all glyphs to scan for component references
-        // Build dependency graph for topological sorting
-        for glyph in font.glyphs.iter() {
-            manager.collect_tasks_recursive(&glyph.name, font)?;
-            all_tasks.append(&mut manager.tasks);
-            manager.visited.clear();
-        }
-
-        // Build dependency graph
-        let mut references: HashMap<SmolStr, HashSet<SmolStr>> = HashMap::new();
-        for task in &all_tasks {
-            references
-                .entry(task.glyph_name.clone())
-                .or_default()
-                .insert(task.referenced_glyph.clone());
-        }
-
-        // Topologically sort glyphs to determine processing order
-        let all_glyphs_set: HashSet<SmolStr> =
-            all_tasks.iter().map(|t| &t.glyph_name).cloned().collect();
-        let mut sorted_glyphs = Vec::new();
-        let mut visited_sort = HashSet::new();
-
-        for glyph in all_glyphs_set.iter() {
-            visit_glyph(
-                glyph,
-                &references,
-                &all_glyphs_set,
-                &mut visited_sort,
-                &mut sorted_glyphs,
-            );
-        }
+        let graph = font.component_graph();
+        manager.expand_filter_transitively(&graph);
+
+        // Glyphs with components to decompose, with components before the glyphs using them
+        let sorted_glyphs: Vec<SmolStr> = graph
+            .bottom_up()
+            .into_iter()
+            .filter(|name| {
+                graph.components_of(name).any(|component| {
+                    manager
+                        .components_filter
+                        .as_ref()
+                        .is_none_or(|filter| filter.contains(component))
+                })
+            })
+            .collect();
         log::info!("Decomposing {} glyphs", sorted_glyphs.len());
 
         // Process glyphs in dependency order, fully decomposing each before moving to the next
@@ -431,6 +369,32 @@ fn decompose_smart_component_with_glyph(
             .collect());
     }
 
+    let interpolated_layer = interpolate_smart_component(
+        component,
+        referenced_glyph,
+        referenced_glyph_layer_locations,
+        layer_location,
+        font_level_axes,
+    )?;
+    // I guess we should also think about the anchors of the component glyphs etc? Not sure.
+    Ok(interpolated_layer
+        .shapes
+        .iter()
+        .map(|s| s.apply_transform(component.transform))
+        .collect::<Vec<shape::Shape>>())
+}
+
+/// Interpolate a smart component's glyph at the location given by the component
+/// and the location of the layer containing it.
+///
+/// The result is untransformed; callers apply the component's transform.
+pub(crate) fn interpolate_smart_component(
+    component: &Component,
+    referenced_glyph: &Glyph,
+    referenced_glyph_layer_locations: &[Option<Location<DesignSpace>>],
+    layer_location: Option<&Location<DesignSpace>>,
+    font_level_axes: &[fontdrasil::types::Axis],
+) -> Result<Layer, crate::BabelfontError> {
     // We'd really like to use Location<Designspace> for the smart component location,
     // but we only have axis *names*, not tags. (We call them all VARC as a placeholder)
     // So we're going to make some fake tags.
@@ -532,19 +496,13 @@ fn decompose_smart_component_with_glyph(
     );
     let normalized_location = target_location.to_normalized(&axes);
     // Now we can hand the designspace off to our interpolation engine
-    let interpolated_layer = interpolate_layer(
+    interpolate_layer(
         referenced_glyph.name.as_str(),
         &layers_locations,
         &axes,
         &normalized_location?,
         false,
-    )?;
-    // I guess we should also think about the anchors of the component glyphs etc? Not sure.
-    Ok(interpolated_layer
-        .shapes
-        .iter()
-        .map(|s| s.apply_transform(component.transform))
-        .collect::<Vec<shape::Shape>>())
+    )
 }
 
 #[cfg(test)]
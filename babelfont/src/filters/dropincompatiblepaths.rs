@@ -82,7 +82,7 @@ impl FontFilter for DropIncompatiblePaths {
         });
         // Now process the todo-list
         for (glyph_name, path_index) in todo_list {
-            if let Some(glyph) = font.glyphs.iter_mut().find(|g| g.name == glyph_name) {
+            if let Some(glyph) = font.glyphs.get_mut(&glyph_name) {
                 for layer in glyph.layers.iter_mut() {
                     let mut path_counter = 0;
                     layer.shapes.retain(|shape| {
@@ -0,0 +1,73 @@
+use fea_rs_ast::{
+    AsFea, CursivePosStatement, GlyphContainer, GlyphName, LookupBlock, LookupFlagStatement,
+    Statement,
+};
+
+use super::{collect_anchors, FeatureWriter, GeneratedFeatures};
+use crate::{BabelfontError, Direction, Font};
+
+const RIGHT_TO_LEFT: u16 = 0x1;
+const IGNORE_MARKS: u16 = 0x8;
+
+/// Writes the `curs` feature from `entry` and `exit` anchors
+///
+/// Glyphs with a right-to-left direction go into a separate lookup with the
+/// `RightToLeft` flag set.
+pub struct CursFeatureWriter;
+
+impl FeatureWriter for CursFeatureWriter {
+    fn features(&self) -> &'static [&'static str] {
+        &["curs"]
+    }
+
+    fn write(&self, font: &Font) -> Result<GeneratedFeatures, BabelfontError> {
+        let anchors = collect_anchors(font)?;
+        let mut ltr = vec![];
+        let mut rtl = vec![];
+        for glyph in font.glyphs.iter() {
+            let Some(glyph_anchors) = anchors.get(&glyph.name) else {
+                continue;
+            };
+            let entry = glyph_anchors.get("entry").cloned();
+            let exit = glyph_anchors.get("exit").cloned();
+            if entry.is_none() && exit.is_none() {
+                continue;
+            }
+            let statement = Statement::CursivePos(CursivePosStatement::new(
+                GlyphContainer::GlyphName(GlyphName::new(&glyph.name)),
+                entry,
+                exit,
+                0..0,
+            ));
+            if glyph.direction == Some(Direction::RightToLeft) {
+                rtl.push(statement);
+            } else {
+                ltr.push(statement);
+            }
+        }
+
+        let mut lookups = vec![];
+        for (name, flags, statements) in [
+            ("curs_ltr", IGNORE_MARKS, ltr),
+            ("curs_rtl", IGNORE_MARKS | RIGHT_TO_LEFT, rtl),
+        ] {
+            if statements.is_empty() {
+                continue;
+            }
+            let mut lookup = vec![Statement::LookupFlag(LookupFlagStatement::new(
+                flags,
+                None,
+                None,
+                0..0,
+            ))];
+            lookup.extend(statements);
+            lookups.push(LookupBlock::new(name.into(), lookup, false, 0..0).as_fea(""));
+        }
+
+        let mut generated = GeneratedFeatures::default();
+        if !lookups.is_empty() {
+            generated.features.insert("curs".into(), lookups.join("\n"));
+        }
+        Ok(generated)
+    }
+}
@@ -0,0 +1,486 @@
+use std::collections::{HashMap, HashSet};
+
+use fea_rs_ast::{AsFea, FeatureFile, Metric, ToplevelItem, ValueRecord};
+use icu_properties::{props::Script, script::ScriptWithExtensions, PropertyParser};
+use indexmap::IndexMap;
+use smol_str::SmolStr;
+
+use super::{master_user_locations, variable_metric, FeatureWriter, GeneratedFeatures};
+use crate::{BabelfontError, Direction, Font};
+
+/// Writes the `kern` feature from the font's kerning and kern groups
+///
+/// Kern groups become `@kern1.*` and `@kern2.*` classes. As in ufo2ft, pairs
+/// are split by the scripts of their glyphs into one lookup per script
+/// declared with `languagesystem`, registered under that script; pairs of
+/// glyphs with no particular script (or of undeclared scripts) go into lookups
+/// registered for every language system. Pairs whose two sides belong to
+/// different scripts are dropped. Lookups for right-to-left scripts, and pairs
+/// involving right-to-left glyphs, adjust both placement and advance, as
+/// glyphsLib does.
+pub struct KernFeatureWriter;
+
+/// ISO 15924 codes of scripts written right to left
+const RTL_SCRIPTS: &[&str] = &[
+    "Adlm", "Arab", "Armi", "Avst", "Chrs", "Cprt", "Elym", "Hatr", "Hebr", "Hung", "Khar",
+    "Lydi", "Mand", "Mani", "Mend", "Merc", "Mero", "Narb", "Nbat", "Nkoo", "Orkh", "Ougr",
+    "Palm", "Phli", "Phlp", "Phnx", "Prti", "Rohg", "Samr", "Sarb", "Sogd", "Sogo", "Syrc",
+    "Thaa", "Yezi",
+];
+
+/// The ISO 15924 codes of the scripts covered by an OpenType script tag
+fn iso_scripts(ot_tag: &str) -> Vec<String> {
+    let fixed: &[&str] = match ot_tag.trim() {
+        "dev2" | "deva" => &["Deva"],
+        "bng2" | "beng" => &["Beng"],
+        "gjr2" | "gujr" => &["Gujr"],
+        "gur2" | "guru" => &["Guru"],
+        "knd2" | "knda" => &["Knda"],
+        "mlm2" | "mlym" => &["Mlym"],
+        "ory2" | "orya" => &["Orya"],
+        "tml2" | "taml" => &["Taml"],
+        "tel2" | "telu" => &["Telu"],
+        "mym2" | "mymr" => &["Mymr"],
+        "kana" => &["Kana", "Hira"],
+        "hang" | "jamo" => &["Hang"],
+        "nko" => &["Nkoo"],
+        "yi" => &["Yiii"],
+        "lao" => &["Laoo"],
+        "vai" => &["Vaii"],
+        _ => &[],
+    };
+    if !fixed.is_empty() {
+        return fixed.iter().map(|s| s.to_string()).collect();
+    }
+    let mut chars = ot_tag.trim().chars();
+    chars
+        .next()
+        .map(|first| vec![first.to_ascii_uppercase().to_string() + chars.as_str()])
+        .unwrap_or_default()
+}
+
+/// A script declared with `languagesystem`, and its non-default languages
+struct DeclaredScript {
+    tag: String,
+    scripts: Vec<Script>,
+    rtl: bool,
+    languages: Vec<String>,
+}
+
+/// The scripts declared by the font's `languagesystem` statements, other than `DFLT`
+fn declared_scripts(font: &Font) -> Vec<DeclaredScript> {
+    let glyph_names = font
+        .glyphs
+        .iter()
+        .map(|g| g.name.as_str())
+        .collect::<Vec<_>>();
+    let feature_file =
+        match FeatureFile::new_from_fea(&font.features.to_fea(), Some(&glyph_names), None) {
+            Ok(feature_file) => feature_file,
+            Err(e) => {
+                log::warn!(
+                    "Could not parse feature code to find language systems, not splitting kerning by script: {}",
+                    e
+                );
+                return vec![];
+            }
+        };
+    let mut declared: IndexMap<String, Vec<String>> = IndexMap::new();
+    for item in feature_file.statements.iter() {
+        if let ToplevelItem::LanguageSystem(lss) = item {
+            if lss.script.trim() == "DFLT" {
+                continue;
+            }
+            let languages = declared.entry(lss.script.trim().to_string()).or_default();
+            if lss.language.trim() != "dflt" {
+                languages.push(lss.language.trim().to_string());
+            }
+        }
+    }
+    let parser = PropertyParser::<Script>::new();
+    declared
+        .into_iter()
+        .map(|(tag, languages)| {
+            let codes = iso_scripts(&tag);
+            DeclaredScript {
+                scripts: codes.iter().filter_map(|c| parser.get_strict(c)).collect(),
+                rtl: codes.iter().any(|c| RTL_SCRIPTS.contains(&c.as_str())),
+                tag,
+                languages,
+            }
+        })
+        .collect()
+}
+
+/// The script of each glyph which has one
+///
+/// Encoded glyphs take the script of their codepoints, ignoring Common and
+/// Inherited; unencoded glyphs take the script of the glyph whose name is
+/// theirs up to the first `.` (so `alef-ar.fina` takes that of `alef-ar`).
+fn glyph_scripts(font: &Font) -> HashMap<SmolStr, Script> {
+    let script_data = ScriptWithExtensions::new();
+    let codepoint_script = |glyph: &crate::Glyph| {
+        glyph
+            .codepoints
+            .iter()
+            .map(|cp| script_data.get_script_val32(*cp))
+            .find(|script| *script != Script::Common && *script != Script::Inherited)
+    };
+    font.glyphs
+        .iter()
+        .filter_map(|glyph| {
+            let script = codepoint_script(glyph).or_else(|| {
+                let base = glyph.name.split('.').next()?;
+                font.glyphs.get(base).and_then(codepoint_script)
+            })?;
+            Some((glyph.name.clone(), script))
+        })
+        .collect()
+}
+
+/// The scripts of the glyphs on one side of a kerning pair
+fn side_scripts(
+    name: &str,
+    groups: &IndexMap<SmolStr, Vec<SmolStr>>,
+    scripts: &HashMap<SmolStr, Script>,
+) -> Vec<Script> {
+    let members = match name.strip_prefix('@') {
+        Some(group) => groups.get(group).cloned().unwrap_or_default(),
+        None => vec![SmolStr::from(name)],
+    };
+    let mut result = vec![];
+    for script in members.iter().filter_map(|m| scripts.get(m)) {
+        if !result.contains(script) {
+            result.push(*script);
+        }
+    }
+    result
+}
+
+/// The `pos` statement for a pair
+fn pair_statement(left: &str, right: &str, value: Metric, rtl: bool) -> String {
+    let enumerated = if specificity(&(left.into(), right.into())) == 1 {
+        "enum "
+    } else {
+        ""
+    };
+    let placement = rtl.then(|| value.clone());
+    let value_record = ValueRecord::new(
+        placement,
+        None,
+        Some(value),
+        None,
+        None,
+        None,
+        None,
+        None,
+        false,
+        0..0,
+        None,
+    );
+    format!(
+        "    {}pos {} {} {};",
+        enumerated,
+        side(left, true),
+        side(right, false),
+        value_record.as_fea("")
+    )
+}
+
+/// Format a kerning lookup
+fn lookup(name: &str, rtl: bool, statements: &[String]) -> String {
+    format!(
+        "lookup {} {{\n    lookupflag {};\n{}\n}} {};",
+        name,
+        if rtl {
+            "RightToLeft IgnoreMarks"
+        } else {
+            "IgnoreMarks"
+        },
+        statements.join("\n"),
+        name
+    )
+}
+
+/// Turn one side of a kerning pair into feature code
+fn side(name: &str, first: bool) -> String {
+    match name.strip_prefix('@') {
+        Some(group) if first => format!("@kern1.{}", group),
+        Some(group) => format!("@kern2.{}", group),
+        None => name.to_string(),
+    }
+}
+
+/// Whether a side of a kerning pair involves a right-to-left glyph
+fn is_rtl(
+    name: &str,
+    groups: &IndexMap<SmolStr, Vec<SmolStr>>,
+    rtl_glyphs: &HashSet<SmolStr>,
+) -> bool {
+    match name.strip_prefix('@') {
+        Some(group) => groups
+            .get(group)
+            .is_some_and(|members| members.iter().any(|m| rtl_glyphs.contains(m))),
+        None => rtl_glyphs.contains(name),
+    }
+}
+
+/// Order pairs so that more specific pairs come before less specific ones
+fn specificity(pair: &(SmolStr, SmolStr)) -> u8 {
+    match (pair.0.starts_with('@'), pair.1.starts_with('@')) {
+        (false, false) => 0,
+        (false, true) | (true, false) => 1,
+        (true, true) => 2,
+    }
+}
+
+impl FeatureWriter for KernFeatureWriter {
+    fn features(&self) -> &'static [&'static str] {
+        &["kern"]
+    }
+
+    fn write(&self, font: &Font) -> Result<GeneratedFeatures, BabelfontError> {
+        let masters: Vec<_> = master_user_locations(font)?
+            .into_iter()
+            .filter(|(master, _)| !master.is_sparse(font))
+            .collect();
+
+        let mut pairs: IndexMap<(SmolStr, SmolStr), Vec<_>> = IndexMap::new();
+        for (index, (master, location)) in masters.iter().enumerate() {
+            for (pair, value) in font.merged_kerning_for_master(master) {
+                let values = pairs.entry(pair).or_insert_with(|| {
+                    // Pairs not kerned in earlier masters are zero there
+                    masters[..index]
+                        .iter()
+                        .map(|(_, location)| (location.clone(), 0))
+                        .collect()
+                });
+                values.push((location.clone(), value));
+            }
+            for values in pairs.values_mut() {
+                if values.len() == index {
+                    values.push((location.clone(), 0));
+                }
+            }
+        }
+        if pairs.is_empty() {
+            return Ok(GeneratedFeatures::default());
+        }
+        pairs.sort_by(|a, _, b, _| specificity(a).cmp(&specificity(b)));
+
+        let (first_groups, second_groups) = font.kern_groups_with_rtl_swaps();
+        let mut rtl_glyphs = font.rtl_kerning_glyphs();
+        rtl_glyphs.extend(
+            font.glyphs
+                .iter()
+                .filter(|g| g.direction == Some(Direction::RightToLeft))
+                .map(|g| g.name.clone()),
+        );
+
+        let mut generated = GeneratedFeatures::default();
+        let used: HashSet<&SmolStr> = pairs.keys().flat_map(|(l, r)| [l, r]).collect();
+        for (prefix, groups) in [("kern1", &first_groups), ("kern2", &second_groups)] {
+            for (name, members) in groups.iter() {
+                if members.is_empty() {
+                    continue;
+                }
+                let group = SmolStr::from(format!("@{}", name));
+                if !used.contains(&group) {
+                    continue;
+                }
+                generated.definitions.push(format!(
+                    "@{}.{} = [{}];",
+                    prefix,
+                    name,
+                    members.join(" ")
+                ));
+            }
+        }
+
+        let declared = declared_scripts(font);
+        let scripts = glyph_scripts(font);
+        // Lookups for every language system, split by direction
+        let mut ltr = vec![];
+        let mut rtl = vec![];
+        // Lookups for each declared script, in the same order
+        let mut per_script: Vec<Vec<String>> = vec![vec![]; declared.len()];
+        for ((left, right), values) in pairs {
+            let value = variable_metric(values);
+            // Zero pairs involving a glyph may be exceptions to group kerning,
+            // but zero group pairs do nothing
+            if matches!(value, Metric::Scalar(0))
+                && specificity(&(left.clone(), right.clone())) == 2
+            {
+                continue;
+            }
+            let left_scripts = side_scripts(&left, &first_groups, &scripts);
+            let right_scripts = side_scripts(&right, &second_groups, &scripts);
+            let pair_scripts: Vec<Script> = if left_scripts.is_empty() {
+                right_scripts
+            } else if right_scripts.is_empty() {
+                left_scripts
+            } else {
+                let common: Vec<Script> = left_scripts
+                    .iter()
+                    .filter(|s| right_scripts.contains(s))
+                    .copied()
+                    .collect();
+                if common.is_empty() {
+                    log::info!(
+                        "Dropping kerning pair {} {}, whose sides are in different scripts",
+                        left,
+                        right
+                    );
+                    continue;
+                }
+                common
+            };
+            let targets: Vec<usize> = declared
+                .iter()
+                .enumerate()
+                .filter(|(_, d)| d.scripts.iter().any(|s| pair_scripts.contains(s)))
+                .map(|(index, _)| index)
+                .collect();
+            if targets.is_empty() {
+                let rtl_pair = is_rtl(&left, &first_groups, &rtl_glyphs)
+                    || is_rtl(&right, &second_groups, &rtl_glyphs);
+                let statement = pair_statement(&left, &right, value, rtl_pair);
+                if rtl_pair {
+                    rtl.push(statement);
+                } else {
+                    ltr.push(statement);
+                }
+                continue;
+            }
+            for index in targets {
+                per_script[index].push(pair_statement(
+                    &left,
+                    &right,
+                    value.clone(),
+                    declared[index].rtl,
+                ));
+            }
+        }
+
+        let mut code = vec![];
+        for (name, rtl_lookup, statements) in [("kern_ltr", false, ltr), ("kern_rtl", true, rtl)] {
+            if !statements.is_empty() {
+                code.push(lookup(name, rtl_lookup, &statements));
+            }
+        }
+        for (script, statements) in declared.iter().zip(per_script) {
+            if statements.is_empty() {
+                continue;
+            }
+            // Lookups before the first script statement apply to all scripts;
+            // languages inherit the lookups of their script's default language
+            code.push(format!("script {};", script.tag));
+            code.push(lookup(
+                &format!("kern_{}", script.tag.trim()),
+                script.rtl,
+                &statements,
+            ));
+            for language in script.languages.iter() {
+                code.push(format!("language {};", language));
+            }
+        }
+        if !code.is_empty() {
+            generated.features.insert("kern".into(), code.join("\n"));
+        }
+        Ok(generated)
+    }
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{features::PossiblyAutomaticCode, Glyph, Master};
+
+    #[test]
+    fn test_kern_feature_writer() {
+        let mut font = Font::new();
+        let mut master = Master::new("Regular", "m01", Default::default());
+        master.kerning.insert(("A".into(), "V".into()), -50);
+        master.kerning.insert(("@O".into(), "@A".into()), -20);
+        master.kerning.insert(("T".into(), "@A".into()), -30);
+        master.kerning.insert(("alef".into(), "bet".into()), -10);
+        font.masters.push(master);
+        for name in ["A", "V", "O", "T", "alef", "bet"] {
+            font.glyphs.push(Glyph {
+                name: name.into(),
+                exported: true,
+                direction: if name == "alef" || name == "bet" {
+                    Some(Direction::RightToLeft)
+                } else {
+                    None
+                },
+                ..Default::default()
+            });
+        }
+        font.first_kern_groups.insert("O".into(), vec!["O".into()]);
+        font.second_kern_groups.insert("A".into(), vec!["A".into()]);
+
+        let generated = KernFeatureWriter.write(&font).unwrap();
+        assert_eq!(
+            generated.definitions,
+            vec!["@kern1.O = [O];", "@kern2.A = [A];"]
+        );
+        let kern = generated.features.get("kern").unwrap();
+        let a_v = kern.find("pos A V -50;").unwrap();
+        let t_a = kern.find("enum pos T @kern2.A -30;").unwrap();
+        let o_a = kern.find("pos @kern1.O @kern2.A -20;").unwrap();
+        assert!(a_v < t_a && t_a < o_a);
+        assert!(kern.contains("lookup kern_rtl"));
+        assert!(kern.contains("pos alef bet <-10 0 -10 0>;"));
+    }
+
+    #[test]
+    fn test_kern_split_by_script() {
+        let mut font = Font::new();
+        font.features.prefixes.insert(
+            "languagesystems".into(),
+            PossiblyAutomaticCode::new(
+                "languagesystem DFLT dflt;\nlanguagesystem latn dflt;\nlanguagesystem latn TRK;\nlanguagesystem arab dflt;",
+            ),
+        );
+        let mut master = Master::new("Regular", "m01", Default::default());
+        master.kerning.insert(("A".into(), "V".into()), -50);
+        master.kerning.insert(("alef-ar".into(), "beh-ar".into()), -10);
+        master.kerning.insert(("A".into(), "beh-ar".into()), -20);
+        master.kerning.insert(("period".into(), "period".into()), -5);
+        master.kerning.insert(("alef-ar.fina".into(), "period".into()), 15);
+        font.masters.push(master);
+        for (name, codepoint) in [
+            ("A", Some(0x41)),
+            ("V", Some(0x56)),
+            ("alef-ar", Some(0x627)),
+            ("alef-ar.fina", None),
+            ("beh-ar", Some(0x628)),
+            ("period", Some(0x2E)),
+        ] {
+            font.glyphs.push(Glyph {
+                name: name.into(),
+                exported: true,
+                codepoints: codepoint.into_iter().collect(),
+                ..Default::default()
+            });
+        }
+        let generated = KernFeatureWriter.write(&font).unwrap();
+        let kern = generated.features.get("kern").unwrap();
+
+        // Script-neutral pairs apply everywhere, before any script statement
+        let period = kern.find("pos period period -5;").unwrap();
+        let latn = kern.find("script latn;").unwrap();
+        let arab = kern.find("script arab;").unwrap();
+        assert!(period < latn && latn < arab);
+        assert!(kern.contains("lookup kern_latn {\n    lookupflag IgnoreMarks;\n    pos A V -50;"));
+        assert!(kern.find("language TRK;").unwrap() > latn);
+        assert!(kern.contains("lookup kern_arab {\n    lookupflag RightToLeft IgnoreMarks;"));
+        assert!(kern.contains("pos alef-ar beh-ar <-10 0 -10 0>;"));
+        // Unencoded glyphs take the script of their base glyph
+        assert!(kern.find("pos alef-ar.fina period <15 0 15 0>;").unwrap() > arab);
+        // Pairs across scripts are dropped
+        assert!(!kern.contains("pos A beh-ar"));
+    }
+}
@@ -0,0 +1,283 @@
+use std::collections::HashSet;
+
+use fea_rs_ast::{
+    Anchor as FeaAnchor, AsFea, GlyphClass, GlyphContainer, GlyphName, LookupBlock,
+    LookupFlagStatement, MarkBasePosStatement, MarkClass, MarkClassDefinition, MarkLigPosStatement,
+    MarkMarkPosStatement, Statement,
+};
+use indexmap::IndexMap;
+use smol_str::SmolStr;
+
+use super::{collect_anchors, FeatureWriter, GeneratedFeatures};
+use crate::{BabelfontError, Font, GlyphCategory};
+
+const USE_MARK_FILTERING_SET: u16 = 0x10;
+
+/// Writes `mark` and `mkmk` features from the anchors in the font's master layers
+///
+/// Mark glyphs carry `_name` anchors; base glyphs carry `name`; ligatures carry
+/// `name_1`, `name_2`, ... for each of their components; and marks carrying `name`
+/// take other marks in the `mkmk` feature.
+pub struct MarkFeatureWriter;
+
+fn glyph(name: &str) -> GlyphContainer {
+    GlyphContainer::GlyphName(GlyphName::new(name))
+}
+
+/// Make an anchor name safe to use in a class or lookup name
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Split `top_2` into (`top`, 2)
+fn ligature_anchor(name: &str) -> Option<(&str, usize)> {
+    let (base, index) = name.rsplit_once('_')?;
+    let index = index.parse::<usize>().ok()?;
+    if base.is_empty() || index == 0 {
+        return None;
+    }
+    Some((base, index))
+}
+
+impl FeatureWriter for MarkFeatureWriter {
+    fn features(&self) -> &'static [&'static str] {
+        &["mark", "mkmk"]
+    }
+
+    fn write(&self, font: &Font) -> Result<GeneratedFeatures, BabelfontError> {
+        let anchors = collect_anchors(font)?;
+        let marks: HashSet<&SmolStr> = font
+            .glyphs
+            .iter()
+            .filter(|g| g.category == GlyphCategory::Mark)
+            .map(|g| &g.name)
+            .collect();
+
+        // Anchor name -> (mark glyph, anchor)
+        let mut mark_classes: IndexMap<&str, Vec<(&SmolStr, &FeaAnchor)>> = IndexMap::new();
+        for (glyph_name, glyph_anchors) in anchors.iter() {
+            for (anchor_name, anchor) in glyph_anchors.iter() {
+                if let Some(base) = anchor_name.strip_prefix('_').filter(|b| !b.is_empty()) {
+                    mark_classes
+                        .entry(base)
+                        .or_default()
+                        .push((glyph_name, anchor));
+                }
+            }
+        }
+
+        let mut bases: IndexMap<&str, Vec<(&SmolStr, &FeaAnchor)>> = IndexMap::new();
+        let mut mark_bases: IndexMap<&str, Vec<(&SmolStr, &FeaAnchor)>> = IndexMap::new();
+        // Anchor name -> ligature glyph -> component index -> anchor
+        let mut ligatures: IndexMap<&str, IndexMap<&SmolStr, IndexMap<usize, &FeaAnchor>>> =
+            IndexMap::new();
+        for (glyph_name, glyph_anchors) in anchors.iter() {
+            for (anchor_name, anchor) in glyph_anchors.iter() {
+                if let Some((class, _)) = mark_classes.get_key_value(anchor_name.as_str()) {
+                    if marks.contains(glyph_name) {
+                        mark_bases
+                            .entry(*class)
+                            .or_default()
+                            .push((glyph_name, anchor));
+                    } else {
+                        bases.entry(*class).or_default().push((glyph_name, anchor));
+                    }
+                } else if let Some((base, index)) = ligature_anchor(anchor_name) {
+                    if let Some((class, _)) = mark_classes.get_key_value(base) {
+                        if !marks.contains(glyph_name) {
+                            ligatures
+                                .entry(*class)
+                                .or_default()
+                                .entry(glyph_name)
+                                .or_default()
+                                .insert(index, anchor);
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut generated = GeneratedFeatures::default();
+        let class_name = |anchor_name: &str| SmolStr::from(format!("MC_{}", sanitize(anchor_name)));
+        for (anchor_name, members) in mark_classes.iter() {
+            for (glyph_name, anchor) in members {
+                generated.definitions.push(
+                    MarkClassDefinition::new(
+                        MarkClass::new(&class_name(anchor_name)),
+                        (*anchor).clone(),
+                        glyph(glyph_name),
+                    )
+                    .as_fea(""),
+                );
+            }
+        }
+
+        let mut mark_lookups = vec![];
+        for (anchor_name, glyphs) in bases.iter() {
+            let statements = glyphs
+                .iter()
+                .map(|(glyph_name, anchor)| {
+                    Statement::MarkBasePos(MarkBasePosStatement::new(
+                        glyph(glyph_name),
+                        vec![((*anchor).clone(), MarkClass::new(&class_name(anchor_name)))],
+                        0..0,
+                    ))
+                })
+                .collect();
+            mark_lookups.push(LookupBlock::new(
+                format!("mark2base_{}", sanitize(anchor_name)).into(),
+                statements,
+                false,
+                0..0,
+            ));
+        }
+        for (anchor_name, glyphs) in ligatures.iter() {
+            let statements = glyphs
+                .iter()
+                .map(|(glyph_name, components)| {
+                    let count = components.keys().max().copied().unwrap_or_default();
+                    let components = (1..=count)
+                        .map(|index| {
+                            components
+                                .get(&index)
+                                .map(|anchor| {
+                                    vec![(
+                                        (*anchor).clone(),
+                                        MarkClass::new(&class_name(anchor_name)),
+                                    )]
+                                })
+                                .unwrap_or_default()
+                        })
+                        .collect();
+                    Statement::MarkLigPos(MarkLigPosStatement::new(
+                        glyph(glyph_name),
+                        components,
+                        0..0,
+                    ))
+                })
+                .collect();
+            mark_lookups.push(LookupBlock::new(
+                format!("mark2liga_{}", sanitize(anchor_name)).into(),
+                statements,
+                false,
+                0..0,
+            ));
+        }
+
+        let mut mkmk_lookups = vec![];
+        for (anchor_name, glyphs) in mark_bases.iter() {
+            let filtering_set = mark_classes
+                .get(anchor_name)
+                .map(|members| {
+                    members
+                        .iter()
+                        .map(|(glyph_name, _)| glyph(glyph_name))
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+            let mut statements = vec![Statement::LookupFlag(LookupFlagStatement::new(
+                USE_MARK_FILTERING_SET,
+                None,
+                Some(GlyphContainer::GlyphClass(GlyphClass::new(
+                    filtering_set,
+                    0..0,
+                ))),
+                0..0,
+            ))];
+            statements.extend(glyphs.iter().map(|(glyph_name, anchor)| {
+                Statement::MarkMarkPos(MarkMarkPosStatement::new(
+                    glyph(glyph_name),
+                    vec![((*anchor).clone(), MarkClass::new(&class_name(anchor_name)))],
+                    0..0,
+                ))
+            }));
+            mkmk_lookups.push(LookupBlock::new(
+                format!("mark2mark_{}", sanitize(anchor_name)).into(),
+                statements,
+                false,
+                0..0,
+            ));
+        }
+
+        for (tag, lookups) in [("mark", mark_lookups), ("mkmk", mkmk_lookups)] {
+            if lookups.is_empty() {
+                continue;
+            }
+            generated.features.insert(
+                tag.into(),
+                lookups
+                    .iter()
+                    .map(|l| l.as_fea(""))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            );
+        }
+        Ok(generated)
+    }
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Anchor, Glyph, Layer, LayerType, Master};
+
+    fn glyph_with_anchors(name: &str, category: GlyphCategory, anchors: &[(&str, f64)]) -> Glyph {
+        let mut layer = Layer::new(500.0);
+        layer.master = LayerType::DefaultForMaster("m01".into());
+        for (anchor, y) in anchors {
+            layer.anchors.push(Anchor {
+                name: anchor.to_string(),
+                x: 100.0,
+                y: *y,
+                ..Default::default()
+            });
+        }
+        Glyph {
+            name: name.into(),
+            category,
+            exported: true,
+            layers: vec![layer],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_mark_feature_writer() {
+        let mut font = Font::new();
+        font.masters
+            .push(Master::new("Regular", "m01", Default::default()));
+        font.glyphs.push(glyph_with_anchors(
+            "a",
+            GlyphCategory::Base,
+            &[("top", 500.0)],
+        ));
+        font.glyphs.push(glyph_with_anchors(
+            "f_i",
+            GlyphCategory::Ligature,
+            &[("top_1", 500.0), ("top_2", 600.0)],
+        ));
+        font.glyphs.push(glyph_with_anchors(
+            "acutecomb",
+            GlyphCategory::Mark,
+            &[("_top", 450.0), ("top", 700.0)],
+        ));
+        let generated = MarkFeatureWriter.write(&font).unwrap();
+        assert_eq!(generated.definitions.len(), 1);
+        assert!(generated.definitions[0].contains("@MC_top"));
+        let mark = generated.features.get("mark").unwrap();
+        assert!(mark.contains("mark2base_top"));
+        assert!(mark.contains("mark2liga_top"));
+        let mkmk = generated.features.get("mkmk").unwrap();
+        assert!(mkmk.contains("mark2mark_top"));
+        assert!(mkmk.contains("UseMarkFilteringSet"));
+    }
+}
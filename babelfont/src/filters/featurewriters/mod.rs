@@ -0,0 +1,430 @@
+use fea_rs_ast::{Anchor as FeaAnchor, Metric};
+use indexmap::IndexMap;
+use smol_str::SmolStr;
+
+use crate::{
+    features::PossiblyAutomaticCode, filters::FontFilter, BabelfontError, Font, LayerType, Master,
+};
+
+mod curs;
+mod kern;
+mod mark;
+
+pub use curs::CursFeatureWriter;
+pub use kern::KernFeatureWriter;
+pub use mark::MarkFeatureWriter;
+
+/// A location in user space, in the form used by variable metrics in feature code
+type SimpleUserLocation = IndexMap<SmolStr, i16>;
+
+/// Something which generates OpenType feature code from the font's data
+pub trait FeatureWriter {
+    /// The feature tags which this writer can generate
+    fn features(&self) -> &'static [&'static str];
+
+    /// Generate feature code from the font
+    fn write(&self, font: &Font) -> Result<GeneratedFeatures, BabelfontError>;
+}
+
+/// Feature code generated by a [FeatureWriter]
+#[derive(Debug, Default)]
+pub struct GeneratedFeatures {
+    /// Definitions (classes, mark classes) shared by the generated features
+    ///
+    /// These are written to an automatic prefix placed before all other feature
+    /// code, so that they are defined before they are used.
+    pub definitions: Vec<String>,
+    /// Generated code for each feature, keyed by feature tag
+    pub features: IndexMap<SmolStr, String>,
+}
+
+/// A filter that generates mark, mkmk, curs and kern features from anchors and kerning
+///
+/// Generated code is stored as automatic code. Existing automatic code for a feature
+/// is replaced; manual code is left alone unless it contains an `# Automatic Code`
+/// marker, in which case the generated code is inserted at the marker. Manual feature
+/// blocks inside prefixes (as found in UFO sources) are handled the same way.
+///
+/// Note that the fontir compiler generates these features itself from the font's
+/// anchors and kerning; this filter is for exporting to tools and formats which
+/// expect explicit feature code.
+pub struct WriteFeatures {
+    features: Vec<SmolStr>,
+}
+
+impl Default for WriteFeatures {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+impl WriteFeatures {
+    /// Create a new WriteFeatures filter which writes the given features
+    pub fn new<S: Into<SmolStr>>(features: impl IntoIterator<Item = S>) -> Self {
+        WriteFeatures {
+            features: features.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Create a new WriteFeatures filter which writes all the features it knows about
+    pub fn all() -> Self {
+        Self::new(
+            Self::writers()
+                .iter()
+                .flat_map(|w| w.features().iter().copied()),
+        )
+    }
+
+    fn writers() -> Vec<Box<dyn FeatureWriter>> {
+        vec![
+            Box::new(MarkFeatureWriter),
+            Box::new(CursFeatureWriter),
+            Box::new(KernFeatureWriter),
+        ]
+    }
+}
+
+impl FontFilter for WriteFeatures {
+    fn apply(&self, font: &mut crate::Font) -> Result<(), crate::BabelfontError> {
+        log::info!("Writing features: {}", self.features.join(", "));
+        // Definitions go before all other code, in the order of the writers
+        let mut definitions_index = 0;
+        for writer in Self::writers() {
+            if !writer
+                .features()
+                .iter()
+                .any(|f| self.features.iter().any(|wanted| wanted == f))
+            {
+                continue;
+            }
+            let generated = writer.write(font)?;
+            let mut written = false;
+            for (tag, code) in generated.features {
+                if !self.features.contains(&tag) || code.is_empty() {
+                    continue;
+                }
+                written |= insert_generated_code(font, &tag, &code);
+            }
+            let prefix_name = SmolStr::from(format!(
+                "Definitions for {}",
+                writer.features().join(", ")
+            ));
+            if font
+                .features
+                .prefixes
+                .get(&prefix_name)
+                .is_some_and(|prefix| !prefix.automatic)
+            {
+                continue;
+            }
+            if written && !generated.definitions.is_empty() {
+                let code = PossiblyAutomaticCode::new_automatic(generated.definitions.join("\n"));
+                match font.features.prefixes.get_mut(&prefix_name) {
+                    Some(prefix) => *prefix = code,
+                    None => {
+                        font.features
+                            .prefixes
+                            .shift_insert(definitions_index, prefix_name, code);
+                    }
+                }
+                definitions_index += 1;
+            } else {
+                font.features.prefixes.shift_remove(&prefix_name);
+            }
+        }
+        Ok(())
+    }
+
+    fn from_str(s: &str) -> Result<Self, crate::BabelfontError>
+    where
+        Self: Sized,
+    {
+        if s.is_empty() || s == "true" {
+            return Ok(WriteFeatures::all());
+        }
+        let known: Vec<&str> = Self::writers()
+            .iter()
+            .flat_map(|w| w.features().iter().copied())
+            .collect();
+        let features: Vec<&str> = s.split(',').map(|f| f.trim()).collect();
+        if let Some(unknown) = features.iter().find(|f| !known.contains(*f)) {
+            return Err(BabelfontError::FilterError(format!(
+                "Don't know how to write feature '{}'",
+                unknown
+            )));
+        }
+        Ok(WriteFeatures::new(features))
+    }
+
+    #[cfg(feature = "cli")]
+    fn arg() -> clap::Arg
+    where
+        Self: Sized,
+    {
+        clap::Arg::new("writefeatures")
+            .long("write-features")
+            .help("Generate mark, mkmk, curs and kern features from anchors and kerning (optionally, a comma-separated list of features to write)")
+            .value_name("FEATURES")
+            .num_args(0..=1)
+            .default_missing_value("true")
+            .action(clap::ArgAction::Append)
+    }
+}
+
+/// Whether a line of feature code is an `# Automatic Code` insertion marker
+fn is_marker(line: &str) -> bool {
+    let comment = line.trim_start();
+    let Some(comment) = comment.strip_prefix('#') else {
+        return false;
+    };
+    let comment = comment.trim().to_lowercase();
+    comment.starts_with("automatic code") && !is_end_marker(line)
+}
+
+/// Whether a line of feature code ends a block of previously inserted automatic code
+fn is_end_marker(line: &str) -> bool {
+    let comment = line.trim_start();
+    let Some(comment) = comment.strip_prefix('#') else {
+        return false;
+    };
+    comment.trim().to_lowercase() == "automatic code end"
+}
+
+/// Insert generated code at the `# Automatic Code` marker in `lines[start..end]`
+///
+/// Any code previously inserted at the marker (up to an `# Automatic Code End`
+/// line) is replaced. Returns false if there is no marker.
+fn insert_at_marker(lines: &mut Vec<String>, start: usize, end: usize, generated: &str) -> bool {
+    let Some(marker) = (start..end).find(|&i| is_marker(&lines[i])) else {
+        return false;
+    };
+    let previous_end = (marker + 1..end).find(|&i| is_end_marker(&lines[i]));
+    let replace_until = previous_end.map(|i| i + 1).unwrap_or(marker + 1);
+    let mut replacement: Vec<String> = generated.lines().map(|l| l.to_string()).collect();
+    replacement.push("# Automatic Code End".to_string());
+    lines.splice(marker + 1..replace_until, replacement);
+    true
+}
+
+/// Find the lines of a `feature <tag> { ... } <tag>;` block in some feature code
+fn find_feature_block(lines: &[String], tag: &str) -> Option<(usize, usize)> {
+    let start = lines.iter().position(|line| {
+        line.trim_start()
+            .strip_prefix("feature")
+            .filter(|rest| rest.starts_with(char::is_whitespace))
+            .and_then(|rest| rest.trim_start().strip_prefix(tag))
+            .is_some_and(|rest| rest.trim_start().is_empty() || rest.trim_start().starts_with('{'))
+    })?;
+    let end = (start..lines.len()).find(|&i| {
+        lines[i]
+            .trim_start()
+            .strip_prefix('}')
+            .is_some_and(|rest| rest.trim_start().starts_with(tag))
+    })?;
+    Some((start, end))
+}
+
+/// Put generated code for a feature into the font, respecting manual code
+///
+/// Returns true if the code was written.
+fn insert_generated_code(font: &mut Font, tag: &str, generated: &str) -> bool {
+    if let Some((_, existing)) = font.features.features.iter_mut().find(|(t, _)| t == tag) {
+        if existing.automatic {
+            existing.code = generated.to_string();
+            return true;
+        }
+        let mut lines: Vec<String> = existing.code.lines().map(|l| l.to_string()).collect();
+        let len = lines.len();
+        if insert_at_marker(&mut lines, 0, len, generated) {
+            existing.code = lines.join("\n");
+            return true;
+        }
+        log::info!(
+            "Not writing {} feature; the font has manual code for it",
+            tag
+        );
+        return false;
+    }
+    for (name, prefix) in font.features.prefixes.iter_mut() {
+        let mut lines: Vec<String> = prefix.code.lines().map(|l| l.to_string()).collect();
+        let Some((start, end)) = find_feature_block(&lines, tag) else {
+            continue;
+        };
+        if insert_at_marker(&mut lines, start, end, generated) {
+            prefix.code = lines.join("\n");
+            return true;
+        }
+        log::info!(
+            "Not writing {} feature; prefix {} has manual code for it",
+            tag,
+            name
+        );
+        return false;
+    }
+    font.features
+        .features
+        .push((tag.into(), PossiblyAutomaticCode::new_automatic(generated)));
+    true
+}
+
+/// The user-space location of each master, in the form used by variable metrics
+pub(crate) fn master_user_locations(
+    font: &Font,
+) -> Result<Vec<(&Master, SimpleUserLocation)>, BabelfontError> {
+    let axes = font.fontdrasil_axes()?;
+    font.masters
+        .iter()
+        .map(|master| {
+            let location = master
+                .location
+                .to_user(&axes)?
+                .iter()
+                .map(|(tag, coord)| (SmolStr::from(tag.to_string()), coord.to_f64() as i16))
+                .collect();
+            Ok((master, location))
+        })
+        .collect()
+}
+
+/// The anchors of each exported glyph, with positions across all masters
+///
+/// Returns glyph name -> anchor name -> anchor, in glyph order. The set of anchors
+/// for a glyph is taken from its default master layer.
+pub(crate) fn collect_anchors(
+    font: &Font,
+) -> Result<IndexMap<SmolStr, IndexMap<String, FeaAnchor>>, BabelfontError> {
+    let masters = master_user_locations(font)?;
+    let default_master = font
+        .default_master()
+        .ok_or(BabelfontError::NoDefaultMaster)?;
+    let mut result = IndexMap::new();
+    for glyph in font.glyphs.iter().filter(|g| g.exported) {
+        let layer_for = |master: &Master| {
+            glyph
+                .layers
+                .iter()
+                .find(|layer| layer.master == LayerType::DefaultForMaster(master.id.clone()))
+        };
+        let Some(default_layer) = layer_for(default_master) else {
+            continue;
+        };
+        let mut anchors = IndexMap::new();
+        for anchor in default_layer.anchors.iter() {
+            let mut xs = vec![];
+            let mut ys = vec![];
+            for (master, location) in masters.iter() {
+                let Some(master_anchor) = layer_for(master)
+                    .and_then(|layer| layer.anchors.iter().find(|a| a.name == anchor.name))
+                else {
+                    continue;
+                };
+                xs.push((location.clone(), master_anchor.x.round() as i16));
+                ys.push((location.clone(), master_anchor.y.round() as i16));
+            }
+            anchors.insert(
+                anchor.name.clone(),
+                FeaAnchor::new(
+                    variable_metric(xs),
+                    variable_metric(ys),
+                    None,
+                    None,
+                    None,
+                    None,
+                    0..0,
+                ),
+            );
+        }
+        if !anchors.is_empty() {
+            result.insert(glyph.name.clone(), anchors);
+        }
+    }
+    Ok(result)
+}
+
+/// A metric which is a scalar if it has the same value everywhere, and variable otherwise
+pub(crate) fn variable_metric(values: Vec<(SimpleUserLocation, i16)>) -> Metric {
+    match values.first() {
+        Some((_, first)) if values.iter().all(|(_, v)| v == first) => Metric::Scalar(*first),
+        None => Metric::Scalar(0),
+        _ => Metric::Variable(values),
+    }
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_at_marker_is_idempotent() {
+        let mut lines: Vec<String> = vec!["sub a by b;".into(), "# Automatic Code".into()];
+        assert!(insert_at_marker(&mut lines, 0, 2, "pos a b -10;"));
+        let len = lines.len();
+        assert!(insert_at_marker(&mut lines, 0, len, "pos a b -20;"));
+        assert_eq!(
+            lines,
+            vec![
+                "sub a by b;",
+                "# Automatic Code",
+                "pos a b -20;",
+                "# Automatic Code End"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_respects_manual_code() {
+        let mut font = Font::new();
+        font.features
+            .features
+            .push(("kern".into(), PossiblyAutomaticCode::new("pos a b -5;")));
+        assert!(!insert_generated_code(&mut font, "kern", "pos a b -10;"));
+        assert_eq!(font.features.features[0].1.code, "pos a b -5;");
+
+        font.features.prefixes.insert(
+            "anonymous".into(),
+            PossiblyAutomaticCode::new("feature mark {\n    # Automatic Code\n} mark;"),
+        );
+        assert!(insert_generated_code(
+            &mut font,
+            "mark",
+            "pos base a <anchor 0 0> mark @MC_top;"
+        ));
+        assert_eq!(
+            font.features.prefixes["anonymous"].code,
+            "feature mark {\n    # Automatic Code\npos base a <anchor 0 0> mark @MC_top;\n# Automatic Code End\n} mark;"
+        );
+
+        assert!(insert_generated_code(
+            &mut font,
+            "mkmk",
+            "pos mark a <anchor 0 0> mark @MC_top;"
+        ));
+        assert!(font.features.features[1].1.automatic);
+    }
+
+    #[test]
+    fn test_definitions_go_in_prefix() {
+        let mut font = Font::new();
+        let mut master = Master::new("Regular", "m01", Default::default());
+        master.kerning.insert(("@O".into(), "A".into()), -20);
+        font.masters.push(master);
+        font.first_kern_groups.insert("O".into(), vec!["O".into()]);
+        font.features.prefixes.insert(
+            "anonymous".into(),
+            PossiblyAutomaticCode::new("feature kern {\n    # Automatic Code\n} kern;"),
+        );
+        WriteFeatures::new(["kern"]).apply(&mut font).unwrap();
+        let (name, definitions) = font.features.prefixes.get_index(0).unwrap();
+        assert_eq!(name, "Definitions for kern");
+        assert!(definitions.automatic);
+        assert_eq!(definitions.code, "@kern1.O = [O];");
+        assert!(!font.features.prefixes["anonymous"].code.contains("@kern1.O ="));
+
+        // Writing again replaces the definitions rather than adding to them
+        WriteFeatures::new(["kern"]).apply(&mut font).unwrap();
+        assert_eq!(font.features.prefixes.len(), 2);
+        let fea = font.features.to_fea();
+        assert!(fea.find("@kern1.O = [O];").unwrap() < fea.find("feature kern").unwrap());
+    }
+}
@@ -0,0 +1,346 @@
+use crate::{filters::FontFilter, Glyph, LayerType, Path, Shape};
+
+/// Node positions scaled into the path's bounding box, so that paths from
+/// different masters can be compared regardless of weight or width
+fn normalized(path: &Path) -> Vec<(f64, f64)> {
+    let (mut min_x, mut min_y) = (f64::INFINITY, f64::INFINITY);
+    let (mut max_x, mut max_y) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+    for node in &path.nodes {
+        min_x = min_x.min(node.x);
+        min_y = min_y.min(node.y);
+        max_x = max_x.max(node.x);
+        max_y = max_y.max(node.y);
+    }
+    let width = if max_x > min_x { max_x - min_x } else { 1.0 };
+    let height = if max_y > min_y { max_y - min_y } else { 1.0 };
+    path.nodes
+        .iter()
+        .map(|node| ((node.x - min_x) / width, (node.y - min_y) / height))
+        .collect()
+}
+
+fn distance(a: &[(f64, f64)], b: &[(f64, f64)]) -> f64 {
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| (a.0 - b.0).powi(2) + (a.1 - b.1).powi(2))
+        .sum()
+}
+
+fn centre(path: &Path) -> (f64, f64) {
+    let count = path.nodes.len().max(1) as f64;
+    let (x, y) = path
+        .nodes
+        .iter()
+        .fold((0.0, 0.0), |(x, y), node| (x + node.x, y + node.y));
+    (x / count, y / count)
+}
+
+/// How far the nodes of `path` need to be rotated so that its start point
+/// corresponds to the start point of `reference`
+///
+/// Returns `None` if no rotation makes the two paths structurally compatible,
+/// and `Some(0)` if the path is compatible as it stands and no other rotation
+/// is a clearly better fit. Among the rotations which keep the node types
+/// compatible, the one whose nodes lie closest to the reference's (relative
+/// to each path's bounding box) wins.
+pub(crate) fn start_point_offset(reference: &Path, path: &Path) -> Option<usize> {
+    if reference.nodes.len() != path.nodes.len() || reference.closed != path.closed {
+        return None;
+    }
+    let target_signature = reference.signature();
+    let signature = path.signature();
+    if !path.closed || path.nodes.is_empty() {
+        return (signature == target_signature).then_some(0);
+    }
+    let target = normalized(reference);
+    let mut nodes = normalized(path);
+    let mut signature = signature;
+    let mut identity = None;
+    let mut best: Option<(usize, f64)> = None;
+    for offset in 0..path.nodes.len() {
+        // Matching the reference's node types also keeps any off-curve
+        // points in the same place relative to the start of the list
+        if signature == target_signature {
+            let d = distance(&target, &nodes);
+            if offset == 0 {
+                identity = Some(d);
+            }
+            if best.is_none_or(|(_, best_d)| d < best_d) {
+                best = Some((offset, d));
+            }
+        }
+        nodes.rotate_left(1);
+        signature.rotate_left(1);
+    }
+    let (offset, d) = best?;
+    match identity {
+        // Only move the start point if doing so is a clear improvement
+        Some(identity) if d >= identity * 0.5 => Some(0),
+        _ => Some(offset),
+    }
+}
+
+fn signed_area(path: &Path) -> f64 {
+    let len = path.nodes.len();
+    (0..len)
+        .map(|ix| {
+            let (a, b) = (&path.nodes[ix], &path.nodes[(ix + 1) % len]);
+            a.x * b.y - b.x * a.y
+        })
+        .sum::<f64>()
+        / 2.0
+}
+
+/// Whether two closed paths wind in opposite directions
+pub(crate) fn winds_opposite(reference: &Path, path: &Path) -> bool {
+    reference.closed && path.closed && signed_area(reference) * signed_area(path) < 0.0
+}
+
+/// A copy of `path`, reversed and rotated as needed to line up with `reference`
+///
+/// Returns `None` if the two paths cannot be made structurally compatible
+/// this way.
+pub(crate) fn align_path(reference: &Path, path: &Path) -> Option<Path> {
+    let mut path = path.clone();
+    if winds_opposite(reference, &path) {
+        path.reverse();
+    }
+    let offset = start_point_offset(reference, &path)?;
+    path.nodes.rotate_left(offset);
+    Some(path)
+}
+
+/// For each of the `reference` paths, the index of the corresponding path
+/// in `paths`
+///
+/// Each reference path is paired with the nearest (relative to the layer's
+/// bounds) unclaimed path that can be aligned with it. If the paths already
+/// correspond one-to-one (up to direction and start point), the identity is kept
+/// unless the pairing is a clearly better fit. Returns `None` if no pairing
+/// exists.
+pub(crate) fn match_contours(reference: &[&Path], paths: &[&Path]) -> Option<Vec<usize>> {
+    if reference.len() != paths.len() {
+        return None;
+    }
+    let identity_is_compatible = reference
+        .iter()
+        .zip(paths)
+        .all(|(a, b)| align_path(a, b).is_some());
+    let relative_centres = |paths: &[&Path]| -> Vec<(f64, f64)> {
+        let centres: Vec<(f64, f64)> = paths.iter().map(|p| centre(p)).collect();
+        let (mut min_x, mut min_y) = (f64::INFINITY, f64::INFINITY);
+        let (mut max_x, mut max_y) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for node in paths.iter().flat_map(|p| p.nodes.iter()) {
+            min_x = min_x.min(node.x);
+            min_y = min_y.min(node.y);
+            max_x = max_x.max(node.x);
+            max_y = max_y.max(node.y);
+        }
+        let width = if max_x > min_x { max_x - min_x } else { 1.0 };
+        let height = if max_y > min_y { max_y - min_y } else { 1.0 };
+        centres
+            .into_iter()
+            .map(|(x, y)| ((x - min_x) / width, (y - min_y) / height))
+            .collect()
+    };
+    let reference_centres = relative_centres(reference);
+    let centres = relative_centres(paths);
+    let cost = |order: &[usize]| -> f64 {
+        order
+            .iter()
+            .zip(&reference_centres)
+            .map(|(&ix, reference_centre)| distance(&[*reference_centre], &[centres[ix]]))
+            .sum()
+    };
+    let identity: Vec<usize> = (0..paths.len()).collect();
+    let mut order: Vec<usize> = vec![];
+    for (reference_path, reference_centre) in reference.iter().zip(&reference_centres) {
+        let closest = (0..paths.len())
+            .filter(|ix| !order.contains(ix))
+            .filter(|&ix| align_path(reference_path, paths[ix]).is_some())
+            .min_by(|&a, &b| {
+                distance(&[*reference_centre], &[centres[a]])
+                    .total_cmp(&distance(&[*reference_centre], &[centres[b]]))
+            });
+        match closest {
+            Some(closest) => order.push(closest),
+            None if identity_is_compatible => return Some(identity),
+            None => return None,
+        }
+    }
+    if identity_is_compatible && cost(&order) >= cost(&identity) * 0.5 {
+        return Some(identity);
+    }
+    Some(order)
+}
+
+/// The index of the layer which other layers of a glyph are compared against:
+/// the default master's layer if there is one, otherwise the first
+/// interpolating layer
+pub(crate) fn reference_layer(
+    glyph: &Glyph,
+    default_master: Option<&str>,
+    interpolating: &[usize],
+) -> Option<usize> {
+    interpolating
+        .iter()
+        .copied()
+        .find(|&ix| match &glyph.layers[ix].master {
+            LayerType::DefaultForMaster(id) => Some(id.as_str()) == default_master,
+            _ => false,
+        })
+        .or_else(|| interpolating.first().copied())
+}
+
+/// A filter that fixes contour order, direction and start points to match the default master
+///
+/// For each glyph, every interpolating layer is compared against the default
+/// master's layer. If the contours can be paired up with the default layer's
+/// contours in a different order, they are reordered; then each closed
+/// contour which winds the other way is reversed, and its start point is moved
+/// to the node which best corresponds to the start point of the matching
+/// contour in the default layer. Layers whose contours cannot be paired up are
+/// left alone.
+#[derive(Debug, Clone, Default)]
+pub struct FixStartPoints;
+
+impl FixStartPoints {
+    /// Create a new FixStartPoints filter
+    pub fn new() -> Self {
+        FixStartPoints
+    }
+}
+
+impl FontFilter for FixStartPoints {
+    fn apply(&self, font: &mut crate::Font) -> Result<(), crate::BabelfontError> {
+        log::info!("Fixing contour order, direction and start points");
+        let default_master = font.default_master().map(|m| m.id.clone());
+        for glyph in font.glyphs.iter_mut() {
+            let interpolating: Vec<usize> = glyph
+                .layers
+                .iter()
+                .enumerate()
+                .filter(|(_, layer)| layer.should_interpolate())
+                .map(|(ix, _)| ix)
+                .collect();
+            let Some(reference_ix) =
+                reference_layer(glyph, default_master.as_deref(), &interpolating)
+            else {
+                continue;
+            };
+            let reference_paths: Vec<Path> = glyph.layers[reference_ix].paths().cloned().collect();
+            let reference: Vec<&Path> = reference_paths.iter().collect();
+            for &layer_ix in interpolating.iter().filter(|&&ix| ix != reference_ix) {
+                let layer = &mut glyph.layers[layer_ix];
+                let paths: Vec<Path> = layer.paths().cloned().collect();
+                let Some(order) = match_contours(&reference, &paths.iter().collect::<Vec<_>>())
+                else {
+                    continue;
+                };
+                if order.iter().enumerate().any(|(ix, &other)| ix != other) {
+                    log::debug!(
+                        "  Reordering contours of glyph '{}' in layer {}",
+                        glyph.name,
+                        layer.debug_name()
+                    );
+                }
+                let mut fixed = order.iter().zip(&reference).map(|(&ix, reference)| {
+                    align_path(reference, &paths[ix]).unwrap_or_else(|| paths[ix].clone())
+                });
+                for shape in layer.shapes.iter_mut() {
+                    if let Shape::Path(path) = shape {
+                        if let Some(fixed) = fixed.next() {
+                            *path = fixed;
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn from_str(_s: &str) -> Result<Self, crate::BabelfontError>
+    where
+        Self: Sized,
+    {
+        Ok(FixStartPoints::new())
+    }
+
+    #[cfg(feature = "cli")]
+    fn arg() -> clap::Arg
+    where
+        Self: Sized,
+    {
+        clap::Arg::new("fixstartpoints")
+            .long("fix-start-points")
+            .help("Reorder, reverse and rotate contours to match the default master")
+            .action(clap::ArgAction::SetTrue)
+    }
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Font, Layer, Master, Node, NodeType};
+
+    fn square(x: f64, y: f64, size: f64) -> Path {
+        let mut path = Path {
+            closed: true,
+            ..Default::default()
+        };
+        for (dx, dy) in [(0.0, 0.0), (size, 0.0), (size, size), (0.0, size)] {
+            path.nodes.push(Node {
+                x: x + dx,
+                y: y + dy,
+                nodetype: NodeType::Line,
+                smooth: false,
+                format_specific: Default::default(),
+            });
+        }
+        path
+    }
+
+    fn layer(master: &str, paths: Vec<Path>) -> Layer {
+        let mut layer = Layer::new(500.0);
+        layer.id = Some(master.into());
+        layer.master = LayerType::DefaultForMaster(master.into());
+        layer.shapes = paths.into_iter().map(Shape::Path).collect();
+        layer
+    }
+
+    #[test]
+    fn test_start_point_offset() {
+        let reference = square(0.0, 0.0, 100.0);
+        let mut path = square(0.0, 0.0, 200.0);
+        assert_eq!(start_point_offset(&reference, &path), Some(0));
+        path.nodes.rotate_left(1);
+        assert_eq!(start_point_offset(&reference, &path), Some(3));
+    }
+
+    #[test]
+    fn test_fix_start_points() {
+        let mut font = Font::new();
+        font.masters
+            .push(Master::new("Regular", "m01", Default::default()));
+        font.masters
+            .push(Master::new("Bold", "m02", Default::default()));
+        let mut rotated = square(0.0, 0.0, 200.0);
+        rotated.nodes.rotate_left(2);
+        font.glyphs.push(crate::Glyph {
+            name: "two".into(),
+            layers: vec![
+                layer(
+                    "m01",
+                    vec![square(0.0, 0.0, 100.0), square(300.0, 0.0, 100.0)],
+                ),
+                layer("m02", vec![square(300.0, 0.0, 200.0), rotated]),
+            ],
+            ..Default::default()
+        });
+        FixStartPoints::new().apply(&mut font).unwrap();
+        let bold: Vec<&Path> = font.glyphs[0].layers[1].paths().collect();
+        assert_eq!(bold[0], &square(0.0, 0.0, 200.0));
+        assert_eq!(bold[1], &square(300.0, 0.0, 200.0));
+    }
+}
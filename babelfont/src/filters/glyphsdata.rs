@@ -6,7 +6,8 @@ use crate::filters::FontFilter;
 
 #[derive(Debug, Deserialize)]
 struct GlyphInfo {
-    // unicode: Option<u32>,
+    #[serde(default)]
+    unicode: Option<String>,
     // unicode_legacy: Option<String>,
     name: String,
     category: String,
@@ -23,6 +24,28 @@ const GLYPHS_DATA_STR: &str = include_str!(concat!(env!("OUT_DIR"), "/glyphsdata
 static GLYPHS_DATA: LazyLock<Vec<GlyphInfo>> =
     LazyLock::new(|| serde_json::from_str(GLYPHS_DATA_STR).expect("Failed to parse glyphs data"));
 
+/// The Glyphs.app name for a codepoint, if it has one
+pub(crate) fn name_for_codepoint(codepoint: u32) -> Option<&'static str> {
+    GLYPHS_DATA
+        .iter()
+        .find(|info| {
+            info.unicode
+                .as_deref()
+                .and_then(|u| u32::from_str_radix(u, 16).ok())
+                == Some(codepoint)
+        })
+        .map(|info| info.name.as_str())
+}
+
+/// The codepoint of a Glyphs.app glyph name, if it has one
+pub(crate) fn codepoint_for_name(name: &str) -> Option<u32> {
+    GLYPHS_DATA
+        .iter()
+        .find(|info| info.name == name)
+        .and_then(|info| info.unicode.as_deref())
+        .and_then(|u| u32::from_str_radix(u, 16).ok())
+}
+
 /// A filter that adds Glyphs.app glyph metadata to the font
 pub struct GlyphsData;
 
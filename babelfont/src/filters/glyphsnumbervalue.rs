@@ -29,8 +29,12 @@ impl FontFilter for GlyphsNumberValue {
             .map(|g| g.name.as_str())
             .collect::<Vec<_>>();
         let mut feature_file: FeatureFile =
-            FeatureFile::new_from_fea(&features, Some(&glyph_names), font.source.clone())
-                .map_err(|e| crate::BabelfontError::FilterError(e.to_string()))?;
+            FeatureFile::new_from_fea(&features, Some(&glyph_names), font.source.clone()).map_err(
+                |e| {
+                    crate::BabelfontError::from(e)
+                        .with_feature_sources(&font.features, font.source.as_deref())
+                },
+            )?;
 
         let number_names = font.format_specific.get(KEY_NUMBER_NAMES).map(|v| {
             v.as_array()
@@ -3,6 +3,9 @@ use crate::{filters::FontFilter, GlyphCategory};
 /// A filter that classifies uncategorized glyphs whose anchors are exclusively
 /// mark-side (underscore-prefixed) as Nonspacing marks.
 ///
+/// Glyphs carrying ligature caret anchors (`caret_N` or `vcaret_N`) are
+/// classified as ligatures instead.
+///
 /// Some sources carry no glyph-class information at all, so their mark
 /// glyphs arrive with category Unknown and filters keyed on `Mark` (such as
 /// `--set-subcategory`) never fire, and compilers with feature writers do
@@ -13,11 +16,19 @@ fn has_mark_anchors(layer: &crate::Layer) -> bool {
     layer.anchors.iter().any(|a| a.name.starts_with('_'))
 }
 
+fn has_caret_anchors(layer: &crate::Layer) -> bool {
+    layer.anchors.iter().any(|a| a.ligature_caret().is_some())
+}
+
 impl FontFilter for InferMarkCategory {
     fn apply(&self, font: &mut crate::Font) -> Result<(), crate::BabelfontError> {
         for glyph in font.glyphs.iter_mut() {
-            if glyph.category == GlyphCategory::Unknown && glyph.layers.iter().any(has_mark_anchors)
-            {
+            if glyph.category != GlyphCategory::Unknown {
+                continue;
+            }
+            if glyph.layers.iter().any(has_caret_anchors) {
+                glyph.category = GlyphCategory::Ligature;
+            } else if glyph.layers.iter().any(has_mark_anchors) {
                 glyph.category = GlyphCategory::Mark;
             }
         }
@@ -69,17 +80,13 @@ mod tests {
     #[test]
     fn test_infer_mark_category() {
         let mut font = Font::new();
-        font.glyphs
-            .0
-            .push(glyph_with_anchors("anusvara", vec!["_top"]));
-        font.glyphs
-            .0
-            .push(glyph_with_anchors("ka", vec!["top", "bottom"]));
+        font.glyphs.push(glyph_with_anchors("anusvara", vec!["_top"]));
+        font.glyphs.push(glyph_with_anchors("ka", vec!["top", "bottom"]));
         // A mark that can also carry other marks (mkmk) still counts as a base
         // carrier, so it is left alone for explicit classification.
-        font.glyphs
-            .0
-            .push(glyph_with_anchors("candra", vec!["_top", "top"]));
+        font.glyphs.push(glyph_with_anchors("candra", vec!["_top", "top"]));
+        // Caret anchors mark ligatures, whatever else they carry
+        font.glyphs.push(glyph_with_anchors("f_i", vec!["caret_1", "top_1", "top_2"]));
         InferMarkCategory.apply(&mut font).expect("filter failed");
         assert_eq!(
             font.glyphs.get("anusvara").expect("anusvara").category,
@@ -93,5 +100,9 @@ mod tests {
             font.glyphs.get("candra").expect("candra").category,
             GlyphCategory::Mark
         );
+        assert_eq!(
+            font.glyphs.get("f_i").expect("f_i").category,
+            GlyphCategory::Ligature
+        );
     }
 }
@@ -0,0 +1,612 @@
+use std::collections::{HashMap, HashSet};
+
+use fontdrasil::{
+    coords::{DesignCoord, DesignLocation, NormalizedLocation, UserCoord},
+    variations::VariationModel,
+};
+use indexmap::{IndexMap, IndexSet};
+use smol_str::SmolStr;
+
+use crate::{
+    filters::{DropAxis, FontFilter},
+    BabelfontError, CustomOTValues, Font, Layer, LayerType, Master, MasterKerning, MetricType,
+    Position, Tag,
+};
+
+/// How to restrict an axis in [`InstantiateSubspace`], in userspace coordinates
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AxisLimit {
+    /// Fix the axis at a single value and remove it from the font
+    Pin(UserCoord),
+    /// Limit the axis to a range (minimum, maximum)
+    Range(UserCoord, UserCoord),
+}
+
+/// A filter that restricts the font's design space to a part of it
+///
+/// Each limited axis is either pinned to a value, in which case it is removed
+/// from the font, or limited to a smaller range. Where masters fall outside the
+/// new range, new masters are interpolated at the range boundaries - glyphs
+/// (with their anchors), kerning, metrics, guides and numeric OpenType values
+/// alike - and the masters outside the
+/// range are dropped. Axis bounds and mappings, instances and cross-axis
+/// mappings are updated to match.
+pub struct InstantiateSubspace(Vec<(Tag, AxisLimit)>);
+
+impl InstantiateSubspace {
+    /// Create a new InstantiateSubspace filter with the given axis limits
+    pub fn new(limits: Vec<(Tag, AxisLimit)>) -> Self {
+        InstantiateSubspace(limits)
+    }
+
+    fn limit_axis(
+        &self,
+        font: &mut Font,
+        tag: Tag,
+        limit: AxisLimit,
+    ) -> Result<(), BabelfontError> {
+        let Some(axis) = font.axes.iter().find(|axis| axis.tag == tag) else {
+            log::warn!("Axis {} not found in font axes", tag);
+            return Ok(());
+        };
+        let (user_min, user_max) = match limit {
+            AxisLimit::Pin(value) => (value, value),
+            AxisLimit::Range(min, max) if min.to_f64() > max.to_f64() => (max, min),
+            AxisLimit::Range(min, max) => (min, max),
+        };
+        let design_min = axis.userspace_to_designspace(user_min)?;
+        let design_max = axis.userspace_to_designspace(user_max)?;
+        let design_default = axis
+            .default
+            .map(|default| axis.userspace_to_designspace(default))
+            .transpose()?
+            .unwrap_or(design_min);
+        let clamp = |coord: DesignCoord| {
+            if coord.to_f64() < design_min.to_f64() {
+                design_min
+            } else if coord.to_f64() > design_max.to_f64() {
+                design_max
+            } else {
+                coord
+            }
+        };
+        let in_range =
+            |location: &DesignLocation| location.get(tag).is_none_or(|coord| clamp(coord) == coord);
+        log::info!(
+            "Limiting axis {} to {}..{}",
+            tag,
+            user_min.to_f64(),
+            user_max.to_f64()
+        );
+
+        // Make master locations explicit on this axis
+        for master in font.masters.iter_mut() {
+            if master.location.get(tag).is_none() {
+                master.location.insert(tag, design_default);
+            }
+        }
+
+        // Interpolate new masters at the boundaries of the new range
+        let mut new_locations: Vec<(usize, DesignLocation)> = vec![];
+        for (index, master) in font.masters.iter().enumerate() {
+            if in_range(&master.location) || master.is_sparse(font) {
+                continue;
+            }
+            let mut location = master.location.clone();
+            if let Some(coord) = location.get(tag) {
+                location.insert(tag, clamp(coord));
+            }
+            if font.masters.iter().any(|m| m.location == location)
+                || new_locations.iter().any(|(_, l)| *l == location)
+            {
+                continue;
+            }
+            new_locations.push((index, location));
+        }
+        let mut new_masters = vec![];
+        for (index, location) in new_locations {
+            new_masters.push(interpolate_master(font, &font.masters[index], location)?);
+        }
+        for (master, layers, rtl_kerning) in new_masters {
+            for (glyph_name, layer) in layers {
+                if let Some(glyph) = font.glyphs.get_mut(&glyph_name) {
+                    glyph.layers.push(layer);
+                }
+            }
+            if !rtl_kerning.is_empty() {
+                font.set_rtl_kerning(&master.id, rtl_kerning);
+            }
+            font.masters.push(master);
+        }
+
+        // Drop everything outside the new range
+        let dropped_masters: Vec<String> = font
+            .masters
+            .iter()
+            .filter(|master| !in_range(&master.location))
+            .map(|master| master.id.clone())
+            .collect();
+        for glyph in font.glyphs.iter_mut() {
+            glyph.layers.retain(|layer| {
+                let master_dropped = match &layer.master {
+                    LayerType::DefaultForMaster(id) | LayerType::AssociatedWithMaster(id) => {
+                        dropped_masters.contains(id)
+                    }
+                    LayerType::FreeFloating => false,
+                };
+                !master_dropped && layer.location.as_ref().is_none_or(in_range)
+            });
+        }
+        for master_id in dropped_masters.iter() {
+            font.set_rtl_kerning(master_id, std::iter::empty());
+        }
+        font.masters
+            .retain(|master| !dropped_masters.contains(&master.id));
+        font.instances
+            .retain(|instance| in_range(&instance.location));
+        font.cross_axis_mappings
+            .retain(|mapping| in_range(&mapping.input) && in_range(&mapping.output));
+
+        // Update the axis itself
+        let Some(axis) = font.axes.iter_mut().find(|axis| axis.tag == tag) else {
+            return Ok(());
+        };
+        let default = axis.default.unwrap_or(user_min);
+        let default = if default.to_f64() < user_min.to_f64() {
+            user_min
+        } else if default.to_f64() > user_max.to_f64() {
+            user_max
+        } else {
+            default
+        };
+        axis.default = Some(default);
+        if let AxisLimit::Pin(_) = limit {
+            return DropAxis::new(tag).apply(font);
+        }
+        axis.min = Some(user_min);
+        axis.max = Some(user_max);
+        if let Some(map) = axis.map.as_mut() {
+            map.retain(|(user, _)| {
+                user.to_f64() >= user_min.to_f64() && user.to_f64() <= user_max.to_f64()
+            });
+            for (user, design) in [(user_min, design_min), (user_max, design_max)] {
+                if !map.iter().any(|(u, _)| *u == user) {
+                    map.push((user, design));
+                }
+            }
+            map.sort_by(|a, b| a.0.to_f64().total_cmp(&b.0.to_f64()));
+        }
+        axis.values.retain(|value| {
+            value.to_f64() >= user_min.to_f64() && value.to_f64() <= user_max.to_f64()
+        });
+        Ok(())
+    }
+}
+
+/// Create a new master at the given location, along with its glyph layers and RTL kerning
+///
+/// Properties which cannot be interpolated, such as guide names or a vendor ID,
+/// are taken from `nearest`; so are guides if the masters have different numbers
+/// of them.
+#[allow(clippy::type_complexity)]
+fn interpolate_master(
+    font: &Font,
+    nearest: &Master,
+    location: DesignLocation,
+) -> Result<
+    (
+        Master,
+        Vec<(SmolStr, Layer)>,
+        IndexMap<(SmolStr, SmolStr), i16>,
+    ),
+    BabelfontError,
+> {
+    let mut master = nearest.clone();
+    master.id = uuid::Uuid::new_v4().to_string();
+    master.name = format!(
+        "{} {}",
+        nearest
+            .name
+            .get_default()
+            .map(|s| s.as_str())
+            .unwrap_or("Master"),
+        location
+            .iter()
+            .map(|(tag, coord)| format!("{}={}", tag, coord.to_f64()))
+            .collect::<Vec<_>>()
+            .join(" ")
+    )
+    .into();
+    master.location = location.clone();
+    let values = interpolate_master_values(font, &location)?;
+    master.kerning = values.kerning;
+    master.metrics.extend(values.metrics);
+    if let Some(positions) = values.guides {
+        for (guide, pos) in master.guides.iter_mut().zip(positions) {
+            guide.pos = pos;
+        }
+    }
+    for (field, value) in values.ot_values {
+        set_ot_value(&mut master.custom_ot_values, field, value);
+    }
+
+    let mut layers = vec![];
+    for glyph in font.glyphs.iter() {
+        let mut layer = match font.interpolate_glyph(&glyph.name, &location) {
+            Ok(layer) => layer,
+            Err(e) => {
+                let Some(layer) = font.master_layer_for(&glyph.name, nearest) else {
+                    continue;
+                };
+                log::warn!(
+                    "Could not interpolate {} at new master location, copying nearest master: {}",
+                    glyph.name,
+                    e
+                );
+                layer.clone()
+            }
+        };
+        layer.master = LayerType::DefaultForMaster(master.id.clone());
+        layer.id = Some(master.id.clone());
+        layer.location = None;
+        layers.push((glyph.name.clone(), layer));
+    }
+    Ok((master, layers, values.rtl_kerning))
+}
+
+/// Kerning, metrics, guides and OpenType values interpolated for a new master
+struct MasterValues {
+    /// Left-to-right kerning
+    kerning: IndexMap<(SmolStr, SmolStr), i16>,
+    /// Right-to-left kerning, stored by Glyphs apart from the master's kerning
+    rtl_kerning: IndexMap<(SmolStr, SmolStr), i16>,
+    metrics: IndexMap<MetricType, i32>,
+    /// Guide positions, if every master has the same number of guides
+    guides: Option<Vec<Position>>,
+    /// Values of the fields listed in [OT_FIELDS] which every master defines
+    ot_values: Vec<(usize, Vec<f64>)>,
+}
+
+/// Numeric per-master OpenType values which can be interpolated
+const OT_FIELDS: usize = 8;
+
+/// Get one of the interpolatable OpenType values, as a list of numbers
+fn ot_value(values: &CustomOTValues, field: usize) -> Option<Vec<f64>> {
+    match field {
+        0 => values.os2_us_weight_class.map(|v| vec![v as f64]),
+        1 => values.os2_us_width_class.map(|v| vec![v as f64]),
+        2 => values.cff_blue_values.clone(),
+        3 => values.cff_other_blues.clone(),
+        4 => values.cff_family_blues.clone(),
+        5 => values.cff_family_other_blues.clone(),
+        6 => values.cff_stem_snap_h.clone(),
+        7 => values.cff_stem_snap_v.clone(),
+        _ => None,
+    }
+}
+
+/// Set one of the interpolatable OpenType values from a list of numbers
+fn set_ot_value(values: &mut CustomOTValues, field: usize, value: Vec<f64>) {
+    let class = value.first().map(|v| v.round().clamp(1.0, u16::MAX as f64) as u16);
+    match field {
+        0 => values.os2_us_weight_class = class,
+        1 => values.os2_us_width_class = class,
+        2 => values.cff_blue_values = Some(value),
+        3 => values.cff_other_blues = Some(value),
+        4 => values.cff_family_blues = Some(value),
+        5 => values.cff_family_other_blues = Some(value),
+        6 => values.cff_stem_snap_h = Some(value),
+        7 => values.cff_stem_snap_v = Some(value),
+        _ => {}
+    }
+}
+
+/// Interpolate the kerning, guides and those metrics and OpenType values
+/// defined in every master, at a location
+///
+/// Masters lacking a kerning pair use the group kerning which applies to it,
+/// as given by [Font::master_kerning]; RTL kerning is interpolated the same way.
+/// Guides are matched up by their order in each master.
+fn interpolate_master_values(
+    font: &Font,
+    location: &DesignLocation,
+) -> Result<MasterValues, BabelfontError> {
+    let axes = font.fontdrasil_axes()?;
+    let masters: Vec<&Master> = font.masters.iter().filter(|m| !m.is_sparse(font)).collect();
+    let kernings: Vec<MasterKerning> = masters.iter().map(|m| font.master_kerning(m)).collect();
+    let pairs: IndexSet<(SmolStr, SmolStr)> = kernings
+        .iter()
+        .flat_map(|kerning| kerning.pairs().map(|(pair, _)| pair.clone()))
+        .collect();
+    // Pairs which only appear in the separately-stored RTL kerning
+    let rtl_pairs: HashSet<(SmolStr, SmolStr)> = masters
+        .iter()
+        .zip(kernings.iter())
+        .flat_map(|(master, kerning)| {
+            kerning
+                .pairs()
+                .filter(|(pair, _)| !master.kerning.contains_key(*pair))
+                .map(|(pair, _)| pair.clone())
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    let metrics: Vec<MetricType> = masters
+        .first()
+        .map(|first| {
+            first
+                .metrics
+                .keys()
+                .filter(|metric| masters.iter().all(|m| m.metrics.contains_key(*metric)))
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default();
+    let guide_count = masters.first().map(|m| m.guides.len()).unwrap_or(0);
+    let guides_compatible = masters.iter().all(|m| m.guides.len() == guide_count);
+    // Fields defined, with the same number of values, in every master
+    let ot_fields: Vec<(usize, usize)> = (0..OT_FIELDS)
+        .filter_map(|field| {
+            let lengths: Vec<Option<usize>> = masters
+                .iter()
+                .map(|m| ot_value(&m.custom_ot_values, field).map(|v| v.len()))
+                .collect();
+            let first = (*lengths.first()?)?;
+            lengths
+                .iter()
+                .all(|len| *len == Some(first))
+                .then_some((field, first))
+        })
+        .collect();
+
+    let mut values: HashMap<NormalizedLocation, Vec<f64>> = HashMap::new();
+    for (master, kerning) in masters.iter().zip(kernings.iter()) {
+        let mut master_values: Vec<f64> = pairs
+            .iter()
+            .map(|(left, right)| kerning.value(left, right) as f64)
+            .collect();
+        master_values.extend(metrics.iter().map(|metric| master.metrics[metric] as f64));
+        if guides_compatible {
+            master_values.extend(master.guides.iter().flat_map(|guide| {
+                [guide.pos.x as f64, guide.pos.y as f64, guide.pos.angle as f64]
+            }));
+        }
+        for (field, _) in ot_fields.iter() {
+            master_values.extend(ot_value(&master.custom_ot_values, *field).unwrap_or_default());
+        }
+        values.insert(master.location.to_normalized(&axes)?, master_values);
+    }
+    let mut result = MasterValues {
+        kerning: IndexMap::new(),
+        rtl_kerning: IndexMap::new(),
+        metrics: IndexMap::new(),
+        guides: None,
+        ot_values: vec![],
+    };
+    if values.is_empty() {
+        return Ok(result);
+    }
+    let model = VariationModel::new(values.keys().cloned().collect(), axes.axis_order());
+    let deltas = model.deltas(&values)?;
+    let interpolated = model.interpolate_from_deltas(&location.to_normalized(&axes)?, &deltas);
+
+    let pair_count = pairs.len();
+    for (pair, value) in pairs.into_iter().zip(interpolated.iter()) {
+        let value = value.round() as i16;
+        // Zero pairs involving a glyph may be exceptions to group kerning
+        if value == 0 && pair.0.starts_with('@') && pair.1.starts_with('@') {
+            continue;
+        }
+        if rtl_pairs.contains(&pair) {
+            result.rtl_kerning.insert(pair, value);
+        } else {
+            result.kerning.insert(pair, value);
+        }
+    }
+    let mut rest = interpolated.iter().skip(pair_count).copied();
+    result.metrics = metrics
+        .into_iter()
+        .zip(rest.by_ref())
+        .map(|(metric, value)| (metric, value.round() as i32))
+        .collect();
+    if guides_compatible {
+        result.guides = Some(
+            (0..guide_count)
+                .map(|_| Position {
+                    x: rest.next().unwrap_or_default() as f32,
+                    y: rest.next().unwrap_or_default() as f32,
+                    angle: rest.next().unwrap_or_default() as f32,
+                })
+                .collect(),
+        );
+    }
+    result.ot_values = ot_fields
+        .into_iter()
+        .map(|(field, len)| (field, rest.by_ref().take(len).collect()))
+        .collect();
+    Ok(result)
+}
+
+impl FontFilter for InstantiateSubspace {
+    fn apply(&self, font: &mut Font) -> Result<(), BabelfontError> {
+        for (tag, limit) in self.0.iter() {
+            self.limit_axis(font, *tag, *limit)?;
+        }
+        Ok(())
+    }
+
+    fn from_str(s: &str) -> Result<Self, BabelfontError>
+    where
+        Self: Sized,
+    {
+        let mut limits = vec![];
+        for pair in s.split(',') {
+            let (axis, value) = pair.split_once('=').ok_or_else(|| {
+                BabelfontError::FilterError(format!("Invalid axis limit: {}", pair))
+            })?;
+            let tag: Tag = axis
+                .trim()
+                .parse()
+                .map_err(|_| BabelfontError::FilterError(format!("Invalid axis tag: {}", axis)))?;
+            let parse = |v: &str| {
+                v.trim().parse::<f64>().map(UserCoord::new).map_err(|_| {
+                    BabelfontError::FilterError(format!("Invalid value for axis '{}': {}", axis, v))
+                })
+            };
+            let limit = match value.split_once(':') {
+                Some((min, max)) => AxisLimit::Range(parse(min)?, parse(max)?),
+                None => AxisLimit::Pin(parse(value)?),
+            };
+            limits.push((tag, limit));
+        }
+        Ok(InstantiateSubspace::new(limits))
+    }
+
+    #[cfg(feature = "cli")]
+    fn arg() -> clap::Arg
+    where
+        Self: Sized,
+    {
+        clap::Arg::new("instantiatesubspace")
+            .long("instantiate-subspace")
+            .help("Pin axes to a value or limit them to a range, in userspace coordinates (e.g. wght=300:700,wdth=100)")
+            .value_name("LIMITS")
+            .action(clap::ArgAction::Append)
+    }
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Axis, Glyph, Instance, Node, Path, Shape};
+
+    fn weight_font() -> Font {
+        let mut font = Font::new();
+        let wght = Tag::new(b"wght");
+        let mut axis = Axis::new("Weight", wght);
+        axis.min = Some(UserCoord::new(100.0));
+        axis.default = Some(UserCoord::new(100.0));
+        axis.max = Some(UserCoord::new(900.0));
+        font.axes.push(axis);
+        let mut glyph = Glyph {
+            name: "A".into(),
+            ..Default::default()
+        };
+        for (id, weight, x, kern) in [("m01", 100.0, 100.0, -100), ("m02", 900.0, 900.0, -20)] {
+            let mut master = Master::new(
+                id,
+                id,
+                DesignLocation::from(vec![(wght, DesignCoord::new(weight))]),
+            );
+            master.kerning.insert(("A".into(), "A".into()), kern);
+            master.metrics.insert(MetricType::XHeight, weight as i32);
+            font.masters.push(master);
+            let mut layer = Layer::new(500.0);
+            layer.master = LayerType::DefaultForMaster(id.into());
+            layer.shapes.push(Shape::Path(Path {
+                nodes: vec![Node::new_line(0.0, 0.0), Node::new_line(x, 0.0)],
+                closed: false,
+                format_specific: Default::default(),
+            }));
+            glyph.layers.push(layer);
+            font.instances.push(Instance {
+                id: id.into(),
+                name: id.into(),
+                location: DesignLocation::from(vec![(wght, DesignCoord::new(weight))]),
+                ..Default::default()
+            });
+        }
+        font.glyphs.push(glyph);
+        font
+    }
+
+    #[test]
+    fn test_limit_range() {
+        let mut font = weight_font();
+        InstantiateSubspace::from_str("wght=300:700")
+            .unwrap()
+            .apply(&mut font)
+            .unwrap();
+        let axis = &font.axes[0];
+        assert_eq!(axis.min, Some(UserCoord::new(300.0)));
+        assert_eq!(axis.max, Some(UserCoord::new(700.0)));
+        assert_eq!(axis.default, Some(UserCoord::new(300.0)));
+        assert_eq!(font.masters.len(), 2);
+        assert!(font.instances.is_empty());
+        let light = &font.masters[0];
+        assert_eq!(light.kerning[&("A".into(), "A".into())], -80);
+        assert_eq!(light.metrics[&MetricType::XHeight], 300);
+        let layer = font.master_layer_for("A", light).unwrap().shapes[0]
+            .as_path()
+            .unwrap()
+            .clone();
+        assert_eq!(layer.nodes[1].x, 300.0);
+    }
+
+    #[test]
+    fn test_group_kerning_interpolation() {
+        let mut font = weight_font();
+        font.first_kern_groups.insert("A".into(), vec!["A".into()]);
+        font.second_kern_groups.insert("V".into(), vec!["V".into()]);
+        // Kerned by its group in the light master, and as an exception in the bold one
+        font.masters[0]
+            .kerning
+            .insert(("@A".into(), "@V".into()), -100);
+        font.masters[1].kerning.insert(("A".into(), "V".into()), -20);
+        InstantiateSubspace::from_str("wght=500")
+            .unwrap()
+            .apply(&mut font)
+            .unwrap();
+        let master = &font.masters[0];
+        assert_eq!(master.kerning[&("A".into(), "V".into())], -60);
+        assert_eq!(master.kerning[&("@A".into(), "@V".into())], -50);
+    }
+
+    #[test]
+    fn test_pin_axis() {
+        let mut font = weight_font();
+        InstantiateSubspace::from_str("wght=500")
+            .unwrap()
+            .apply(&mut font)
+            .unwrap();
+        assert!(font.axes.is_empty());
+        assert_eq!(font.masters.len(), 1);
+        let glyph = font.glyphs.get("A").unwrap();
+        assert_eq!(glyph.layers.len(), 1);
+        assert_eq!(
+            glyph.layers[0].shapes[0].as_path().unwrap().nodes[1].x,
+            500.0
+        );
+    }
+
+    #[test]
+    fn test_guide_and_ot_value_interpolation() {
+        let mut font = weight_font();
+        for (master, (y, weight_class, stems)) in font
+            .masters
+            .iter_mut()
+            .zip([(100.0, 100, vec![40.0, 50.0]), (500.0, 900, vec![120.0, 150.0])])
+        {
+            let mut guide = crate::Guide::new();
+            guide.pos.y = y;
+            guide.name = Some("overshoot".into());
+            master.guides.push(guide);
+            master.custom_ot_values.os2_us_weight_class = Some(weight_class);
+            master.custom_ot_values.cff_stem_snap_h = Some(stems);
+        }
+        // Only defined in one master, so not interpolated
+        font.masters[1].custom_ot_values.os2_us_width_class = Some(7);
+        InstantiateSubspace::from_str("wght=500")
+            .unwrap()
+            .apply(&mut font)
+            .unwrap();
+        let master = &font.masters[0];
+        assert_eq!(master.guides.len(), 1);
+        assert_eq!(master.guides[0].pos.y, 300.0);
+        assert_eq!(master.guides[0].name.as_deref(), Some("overshoot"));
+        let ot = &master.custom_ot_values;
+        assert_eq!(ot.os2_us_weight_class, Some(500));
+        assert_eq!(ot.cff_stem_snap_h, Some(vec![80.0, 100.0]));
+        assert_eq!(ot.os2_us_width_class, None);
+    }
+}
@@ -1,7 +1,12 @@
 use crate::Tag;
 use fontdrasil::coords::{DesignCoord, DesignLocation};
 
+mod agl;
 mod curve_filter_common;
+mod path_union;
+
+pub(crate) use featurewriters::{master_user_locations, variable_metric};
+pub(crate) use fixstartpoints::{match_contours, reference_layer, start_point_offset};
 
 /// Macro to declare filters with less boilerplate, organized into named groups.
 ///
@@ -82,17 +87,24 @@ declare_filters! {
         DropInstances(dropinstances) => "dropinstances",
         DropVariations(dropvariations) => "dropvariations",
         DropAxis(dropaxis) => "dropaxis",
+        InstantiateSubspace(instantiatesubspace) => "instantiatesubspace",
         DropSparseMasters(dropsparsemasters) => "dropsparsemasters",
         DropIncompatiblePaths(dropincompatiblepaths) => "dropincompatiblepaths",
         RetainGlyphs(retainglyphs) => "retainglyphs",
+        RetainCodepoints(retaincodepoints) => "retaincodepoints",
     }
     group "Filters for manipulating outlines" {
         DecomposeComponentReferences(decomposecomponentreferences) => "decomposecomponents",
         CubicToQuadratic(cubic2quadratic) => "cubic2quadratic",
         QuadraticToCubic(quadratic2cubic) => "quadratic2cubic",
         CleanupPaths(cleanuppaths) => "cleanuppaths",
+        CorrectPathDirection(correctpathdirection) => "correctpathdirection",
+        FixStartPoints(fixstartpoints) => "fixstartpoints",
+        AddExtrema(addextrema) => "addextrema",
         MakeCompatible(makecompatible) => "makecompatible",
         Recompose(recomposition) => "recompose",
+        BuildComposites(buildcomposites) => "buildcomposites",
+        RemoveOverlaps(removeoverlaps) => "removeoverlaps",
     }
     group "Filters for Glyphs font sources" {
         CorrectConjunctCategory(correctconjunctcategory) => "correctconjunctcategory",
@@ -103,11 +115,14 @@ declare_filters! {
         GlyphsStylisticSetLabel(glyphsstylisticsetlabel) => "glyphsstylisticsetlabel",
         GlyphsBracketLayers(glyphsbracketlayers) => "glyphsbracketlayers",
         SetSubcategory(setsubcategory) => "setsubcategory",
+        PropagateAnchors(propagateanchors) => "propagateanchors",
+        ApplyMetricsKeys(applymetricskeys) => "applymetricskeys",
     }
     group "Filters for manipulating feature code" {
         ResolveIncludes(resolveincludes) => "resolveincludes",
         SubsetLayout(subsetlayout) => "subsetlayout",
         MoveKerningFromFeatures(movekerningfromfeatures) => "movekerningfromfeatures",
+        WriteFeatures(featurewriters) => "writefeatures",
     }
     group "General font filters" {
         RewriteSmartAxes(rewritesmartaxes) => "rewritesmartaxes",
@@ -117,9 +132,15 @@ declare_filters! {
         AddMaster(addmaster) => "addmaster",
         RemoveExtraneousLayers(removeextraneouslayers) => "removeextraneouslayers",
         AddInstancePerMaster(addinstancepermaster) => "addinstancepermaster",
+        Slant(slant) => "slant",
     }
 }
 
+pub use correctpathdirection::PathDirection;
+pub use instantiatesubspace::AxisLimit;
+pub use removeoverlaps::OverlapCompatibility;
+pub use slant::SlantOrigin;
+
 /// A trait for font filters that can be applied to a font
 pub trait FontFilter {
     /// Apply the filter to the given font
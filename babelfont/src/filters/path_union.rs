@@ -0,0 +1,328 @@
+//! Union of closed contours, for removing overlaps
+//!
+//! Every segment is split wherever it meets another, and the pieces which
+//! have the filled area on exactly one side are kept and joined back up into
+//! contours.
+use std::collections::HashMap;
+
+use kurbo::{
+    BezPath, Line, ParamCurve, ParamCurveArea, ParamCurveNearest, PathEl, PathSeg, Point, Rect,
+    Shape, Vec2,
+};
+
+/// Points closer than this (in font units) are treated as the same point
+const SAME_POINT: f64 = 1e-3;
+/// Candidate intersections closer together than this are the same one
+const SAME_RUN: f64 = 5e-2;
+/// How far either side of a piece of outline to look to see if it is filled
+///
+/// This needs to be small so as not to reach past anything else nearby, such as
+/// where a curve only just touches a line.
+const PROBE: f64 = 1e-6;
+/// Where along a piece of outline to look either side of it
+///
+/// Winding numbers can be miscounted when the horizontal ray from a point passes
+/// exactly through the end of a segment, which is likely for axis-aligned outlines
+/// with integer coordinates; looking away from the middle makes that unlikely.
+const PROBE_AT: f64 = 0.4783;
+/// Curves are subdivided until they are this small when finding intersections
+const CURVE_PRECISION: f64 = 1e-5;
+/// Give up subdividing curves after this many halvings
+const MAX_DEPTH: usize = 64;
+
+/// The union of a set of closed contours, using the nonzero fill rule
+///
+/// Contours are returned in the same direction as the majority of the input,
+/// with holes going the other way. Returns `None` if the outline could not be
+/// put back together, which should only happen for degenerate input.
+pub(crate) fn union(contours: &[BezPath]) -> Option<Vec<BezPath>> {
+    let contours: Vec<Vec<PathSeg>> = contours.iter().map(closed_segments).collect();
+    let segments: Vec<PathSeg> = contours.iter().flatten().copied().collect();
+    if segments.is_empty() {
+        return Some(vec![]);
+    }
+    let mut outline = BezPath::new();
+    for contour in contours.iter().filter(|c| !c.is_empty()) {
+        outline.move_to(contour[0].start());
+        for segment in contour {
+            push_segment(&mut outline, segment);
+        }
+        outline.close_path();
+    }
+    let clockwise = segments.iter().map(|s| s.signed_area()).sum::<f64>() < 0.0;
+
+    // Split every segment where it touches another
+    let mut splits: Vec<Vec<f64>> = vec![vec![]; segments.len()];
+    for (i, a) in segments.iter().enumerate() {
+        for (j, b) in segments.iter().enumerate().skip(i + 1) {
+            for (ta, tb) in intersections(a, b) {
+                splits[i].push(ta);
+                splits[j].push(tb);
+            }
+        }
+    }
+
+    // Keep the pieces with the filled area on their left (and only their left)
+    let mut vertices: Vec<Point> = vec![];
+    let mut edges: Vec<(usize, usize, PathSeg)> = vec![];
+    for (segment, mut ts) in segments.iter().zip(splits) {
+        ts.retain(|t| *t > 0.0 && *t < 1.0);
+        ts.sort_by(f64::total_cmp);
+        ts.insert(0, 0.0);
+        ts.push(1.0);
+        for range in ts.windows(2) {
+            let piece = segment.subsegment(range[0]..range[1]);
+            if piece.start().distance(piece.end()) < SAME_POINT
+                && piece.bounding_box().size().max_side() < SAME_POINT
+            {
+                continue;
+            }
+            let point = piece.eval(PROBE_AT);
+            let tangent = piece.eval(PROBE_AT + 1e-3) - piece.eval(PROBE_AT - 1e-3);
+            if tangent.hypot() < f64::EPSILON {
+                continue;
+            }
+            let normal = Vec2::new(-tangent.y, tangent.x).normalize() * PROBE;
+            let left = outline.winding(point + normal) != 0;
+            let right = outline.winding(point - normal) != 0;
+            let piece = match (left, right) {
+                (true, false) => piece,
+                (false, true) => piece.reverse(),
+                _ => continue,
+            };
+            let start = vertex(&mut vertices, piece.start());
+            let end = vertex(&mut vertices, piece.end());
+            let piece = with_ends(piece, vertices[start], vertices[end]);
+            // Coincident edges of different contours are only drawn once
+            if edges.iter().any(|(s, e, other)| {
+                *s == start && *e == end && other.eval(0.5).distance(piece.eval(0.5)) < SAME_POINT
+            }) {
+                continue;
+            }
+            edges.push((start, end, piece));
+        }
+    }
+
+    // Join the pieces back up into contours
+    let mut outgoing: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (index, (start, _, _)) in edges.iter().enumerate() {
+        outgoing.entry(*start).or_default().push(index);
+    }
+    let mut used = vec![false; edges.len()];
+    let mut result = vec![];
+    while let Some(first) = used.iter().position(|used| !used) {
+        let mut contour = vec![];
+        let mut current = first;
+        loop {
+            used[current] = true;
+            let (_, end, piece) = edges[current];
+            contour.push(piece);
+            if end == edges[first].0 {
+                break;
+            }
+            current = *outgoing.get(&end)?.iter().find(|&&next| !used[next])?;
+        }
+        let contour = merge_lines(contour);
+        let mut path = BezPath::new();
+        path.move_to(contour[0].start());
+        for piece in contour.iter() {
+            push_segment(&mut path, piece);
+        }
+        path.close_path();
+        result.push(if clockwise {
+            path.reverse_subpaths()
+        } else {
+            path
+        });
+    }
+    Some(result)
+}
+
+/// The segments of a contour, including the closing line
+fn closed_segments(contour: &BezPath) -> Vec<PathSeg> {
+    let mut segments: Vec<PathSeg> = contour.segments().collect();
+    if let (Some(first), Some(last)) = (segments.first(), segments.last()) {
+        if first.start().distance(last.end()) >= SAME_POINT {
+            segments.push(PathSeg::Line(Line::new(last.end(), first.start())));
+        }
+    }
+    segments.retain(|segment| {
+        segment.start().distance(segment.end()) >= SAME_POINT
+            || segment.bounding_box().size().max_side() >= SAME_POINT
+    });
+    segments
+}
+
+fn push_segment(path: &mut BezPath, segment: &PathSeg) {
+    path.push(match *segment {
+        PathSeg::Line(l) => PathEl::LineTo(l.p1),
+        PathSeg::Quad(q) => PathEl::QuadTo(q.p1, q.p2),
+        PathSeg::Cubic(c) => PathEl::CurveTo(c.p1, c.p2, c.p3),
+    });
+}
+
+/// Find (or add) the vertex at a point
+fn vertex(vertices: &mut Vec<Point>, point: Point) -> usize {
+    if let Some(index) = vertices.iter().position(|v| v.distance(point) < SAME_POINT) {
+        return index;
+    }
+    vertices.push(point);
+    vertices.len() - 1
+}
+
+/// Move the end points of a segment onto the vertices it joins
+fn with_ends(segment: PathSeg, start: Point, end: Point) -> PathSeg {
+    match segment {
+        PathSeg::Line(mut l) => {
+            (l.p0, l.p1) = (start, end);
+            PathSeg::Line(l)
+        }
+        PathSeg::Quad(mut q) => {
+            (q.p0, q.p2) = (start, end);
+            PathSeg::Quad(q)
+        }
+        PathSeg::Cubic(mut c) => {
+            (c.p0, c.p3) = (start, end);
+            PathSeg::Cubic(c)
+        }
+    }
+}
+
+/// Join consecutive lines which go in the same direction
+fn merge_lines(contour: Vec<PathSeg>) -> Vec<PathSeg> {
+    let collinear = |a: &PathSeg, b: &PathSeg| match (a, b) {
+        (PathSeg::Line(a), PathSeg::Line(b)) => {
+            let (da, db) = (a.p1 - a.p0, b.p1 - b.p0);
+            da.cross(db).abs() / da.hypot() < SAME_POINT && da.dot(db) > 0.0
+        }
+        _ => false,
+    };
+    let mut merged: Vec<PathSeg> = vec![];
+    for piece in contour {
+        match merged.last_mut() {
+            Some(PathSeg::Line(last)) if collinear(&PathSeg::Line(*last), &piece) => {
+                last.p1 = piece.end();
+            }
+            _ => merged.push(piece),
+        }
+    }
+    if merged.len() > 2 && collinear(&merged[merged.len() - 1], &merged[0]) {
+        if let Some(PathSeg::Line(last)) = merged.pop() {
+            merged[0] = PathSeg::Line(Line::new(last.p0, merged[0].end()));
+        }
+    }
+    merged
+}
+
+/// Parameters at which two segments meet
+fn intersections(a: &PathSeg, b: &PathSeg) -> Vec<(f64, f64)> {
+    if !overlap(a.bounding_box(), b.bounding_box(), SAME_POINT) {
+        return vec![];
+    }
+    let mut found = vec![];
+    // End points which lie on the other segment; this also catches
+    // T-junctions and lines which overlap along their length
+    for (t, point) in [(0.0, a.start()), (1.0, a.end())] {
+        let nearest = b.nearest(point, 1e-9);
+        if nearest.distance_sq < SAME_POINT * SAME_POINT {
+            found.push((t, nearest.t));
+        }
+    }
+    for (t, point) in [(0.0, b.start()), (1.0, b.end())] {
+        let nearest = a.nearest(point, 1e-9);
+        if nearest.distance_sq < SAME_POINT * SAME_POINT {
+            found.push((nearest.t, t));
+        }
+    }
+    match (a, b) {
+        (_, PathSeg::Line(line)) => found.extend(
+            a.intersect_line(*line)
+                .into_iter()
+                .map(|i| (i.segment_t, i.line_t)),
+        ),
+        (PathSeg::Line(line), _) => found.extend(
+            b.intersect_line(*line)
+                .into_iter()
+                .map(|i| (i.line_t, i.segment_t)),
+        ),
+        _ if same_curve(a, b) => {}
+        _ => curve_intersections(a, 0.0..1.0, b, 0.0..1.0, 0, &mut found),
+    }
+    // Where segments touch rather than cross, or are found more than once,
+    // there is a run of nearby candidates; keep the closest of each run
+    found.sort_by(|x, y| x.0.total_cmp(&y.0));
+    let gap = |(ta, tb): (f64, f64)| a.eval(ta).distance(b.eval(tb));
+    let mut runs: Vec<(f64, f64)> = vec![];
+    let mut previous: Option<(f64, f64)> = None;
+    for candidate in found {
+        let joins_run = previous.is_some_and(|(pa, pb)| {
+            a.eval(pa).distance(a.eval(candidate.0)) < SAME_RUN
+                && b.eval(pb).distance(b.eval(candidate.1)) < SAME_RUN
+        });
+        match runs.last_mut() {
+            Some(best) if joins_run => {
+                if gap(candidate) < gap(*best) {
+                    *best = candidate;
+                }
+            }
+            _ => runs.push(candidate),
+        }
+        previous = Some(candidate);
+    }
+    runs
+}
+
+/// Whether two rectangles come within a distance of each other
+fn overlap(a: Rect, b: Rect, distance: f64) -> bool {
+    a.x0 - distance <= b.x1
+        && b.x0 - distance <= a.x1
+        && a.y0 - distance <= b.y1
+        && b.y0 - distance <= a.y1
+}
+
+/// Whether two curves are the same, perhaps drawn in opposite directions
+fn same_curve(a: &PathSeg, b: &PathSeg) -> bool {
+    let points = |segment: &PathSeg| -> Vec<Point> {
+        match *segment {
+            PathSeg::Line(l) => vec![l.p0, l.p1],
+            PathSeg::Quad(q) => vec![q.p0, q.p1, q.p2],
+            PathSeg::Cubic(c) => vec![c.p0, c.p1, c.p2, c.p3],
+        }
+    };
+    let (a, b) = (points(a), points(b));
+    let matches = |b: &mut dyn Iterator<Item = Point>| {
+        a.iter().zip(b).all(|(p, q)| p.distance(q) < SAME_POINT)
+    };
+    a.len() == b.len() && (matches(&mut b.iter().copied()) || matches(&mut b.iter().rev().copied()))
+}
+
+/// Find where two curves cross by repeatedly splitting them in half
+fn curve_intersections(
+    a: &PathSeg,
+    a_range: std::ops::Range<f64>,
+    b: &PathSeg,
+    b_range: std::ops::Range<f64>,
+    depth: usize,
+    found: &mut Vec<(f64, f64)>,
+) {
+    let a_box = a.subsegment(a_range.clone()).bounding_box();
+    let b_box = b.subsegment(b_range.clone()).bounding_box();
+    if !overlap(a_box, b_box, CURVE_PRECISION) {
+        return;
+    }
+    let small = |rect: Rect| rect.size().max_side() < CURVE_PRECISION;
+    if depth >= MAX_DEPTH || (small(a_box) && small(b_box)) {
+        found.push((
+            (a_range.start + a_range.end) / 2.0,
+            (b_range.start + b_range.end) / 2.0,
+        ));
+        return;
+    }
+    let a_mid = (a_range.start + a_range.end) / 2.0;
+    let b_mid = (b_range.start + b_range.end) / 2.0;
+    for a_half in [a_range.start..a_mid, a_mid..a_range.end] {
+        for b_half in [b_range.start..b_mid, b_mid..b_range.end] {
+            curve_intersections(a, a_half.clone(), b, b_half, depth + 1, found);
+        }
+    }
+}
@@ -0,0 +1,613 @@
+use std::collections::HashMap;
+
+use fontdrasil::coords::{DesignSpace, Location};
+use indexmap::IndexMap;
+use smol_str::SmolStr;
+
+use crate::{
+    filters::{decomposecomponentreferences::interpolate_smart_component, FontFilter},
+    Anchor, Component, Font, Glyph, GlyphCategory, Layer, LayerType,
+};
+
+/// A filter that copies anchors from components onto composite glyphs, as Glyphs.app does
+///
+/// For example, `Aacute` takes `top` from `acutecomb` and `bottom` and `ogonek`
+/// from `A`. Anchors are transformed along with their components (and renamed if
+/// the component is flipped), and in ligatures they are numbered after the
+/// component they come from, so `f_i` gets `top_1` and `top_2`. Anchors defined
+/// explicitly on a composite take precedence over propagated ones.
+///
+/// This follows glyphsLib's `propagate_anchors`, which in turn follows Glyphs.app.
+pub struct PropagateAnchors;
+
+/// Find the layer of a component glyph that corresponds to a layer of the composite
+///
+/// Layers with the same ID (as with Glyphs brace and bracket layers) are preferred,
+/// then layers at the same location, then the default layer of the same master.
+fn component_layer<'a>(component_glyph: &'a Glyph, layer: &Layer) -> Option<(usize, &'a Layer)> {
+    let layers = || component_glyph.layers.iter().enumerate();
+    if let Some(id) = &layer.id {
+        if let Some(found) = layers().find(|(_, l)| l.id.as_ref() == Some(id)) {
+            return Some(found);
+        }
+    }
+    if let Some(location) = &layer.location {
+        if let Some(found) =
+            layers().find(|(_, l)| !l.is_background && l.location.as_ref() == Some(location))
+        {
+            return Some(found);
+        }
+    }
+    let master_id = match &layer.master {
+        LayerType::DefaultForMaster(id) | LayerType::AssociatedWithMaster(id) => id,
+        LayerType::FreeFloating => return None,
+    };
+    layers().find(|(_, l)| l.master == LayerType::DefaultForMaster(master_id.clone()))
+}
+
+/// Anchors of a layer, made relative to its `*origin` anchor if it has one
+fn origin_adjusted_anchors(anchors: &[Anchor]) -> impl Iterator<Item = Anchor> + '_ {
+    let origin = anchors
+        .iter()
+        .find(|a| a.name == "*origin")
+        .map(|a| (a.x, a.y))
+        .unwrap_or_default();
+    anchors
+        .iter()
+        .filter(|a| a.name != "*origin")
+        .map(move |a| Anchor {
+            x: a.x - origin.0,
+            y: a.y - origin.1,
+            ..a.clone()
+        })
+}
+
+/// The x and y scale of a transform once any rotation is removed
+///
+/// A negative value means the component is flipped along that axis.
+fn xy_scale(transform: kurbo::Affine) -> (f64, f64) {
+    let [xx, xy, ..] = transform.as_coeffs();
+    let angle = xy.atan2(xx);
+    let unrotated = (kurbo::Affine::rotate(-angle) * transform).as_coeffs();
+    let mut scale = (unrotated[0], unrotated[3]);
+    // Glyphs treats a 180° rotation as flipping on both axes
+    if (angle.to_degrees() - 180.0).abs() < 0.001 {
+        scale = (-scale.0, -scale.1);
+    }
+    scale
+}
+
+/// Rename an anchor taken from a flipped component, so `top` becomes `bottom` etc.
+fn rename_for_scale(name: &str, scale: (f64, f64)) -> String {
+    fn swap(name: String, one: &str, two: &str) -> String {
+        if name.contains(one) {
+            name.replace(one, two)
+        } else if name.contains(two) {
+            name.replace(two, one)
+        } else {
+            name
+        }
+    }
+    let mut name = name.to_string();
+    if scale.1 < 0.0 {
+        name = swap(name, "bottom", "top");
+    }
+    if scale.0 < 0.0 {
+        name = swap(name, "left", "right");
+        name = swap(name, "exit", "entry");
+    }
+    name
+}
+
+/// Number an anchor for the component of a ligature it comes from
+///
+/// `top` on the third component becomes `top_3`; `top_2` on a component which
+/// follows two others becomes `top_4`.
+fn ligature_anchor_name(name: &str, base_number: usize) -> String {
+    match name.split_once('_') {
+        Some((name, suffix)) => {
+            let suffix = base_number + suffix.parse::<usize>().unwrap_or(1);
+            format!("{}_{}", name, suffix)
+        }
+        None => format!("{}_{}", name, base_number + 1),
+    }
+}
+
+struct Propagator<'a> {
+    font: &'a Font,
+    axes: &'a [fontdrasil::types::Axis],
+    /// How many base glyphs each (glyph, layer index) is made from
+    base_glyph_counts: &'a mut HashMap<(SmolStr, usize), usize>,
+}
+
+impl Propagator<'_> {
+    /// The anchors of a component in a layer, before transformation
+    fn component_anchors(
+        &self,
+        component: &Component,
+        layer: &Layer,
+    ) -> Option<(Vec<Anchor>, usize)> {
+        let Some(component_glyph) = self.font.glyphs.get(&component.reference) else {
+            log::warn!("Component {} not found", component.reference);
+            return None;
+        };
+        let found = component_layer(component_glyph, layer);
+        let base_glyphs = found
+            .and_then(|(index, _)| {
+                self.base_glyph_counts
+                    .get(&(component.reference.clone(), index))
+                    .copied()
+            })
+            .unwrap_or(0);
+        if !component.location.is_empty() && !component_glyph.component_axes.is_empty() {
+            let layer_locations: Vec<Option<Location<DesignSpace>>> = component_glyph
+                .layers
+                .iter()
+                .map(|l| l.effective_location(self.font))
+                .collect();
+            match interpolate_smart_component(
+                component,
+                component_glyph,
+                &layer_locations,
+                layer.effective_location(self.font).as_ref(),
+                self.axes,
+            ) {
+                Ok(interpolated) => return Some((interpolated.anchors, base_glyphs)),
+                Err(e) => log::warn!(
+                    "Could not interpolate anchors of smart component {}: {}",
+                    component.reference,
+                    e
+                ),
+            }
+        }
+        found.map(|(_, l)| (l.anchors.clone(), base_glyphs))
+    }
+
+    /// Compute the anchors of a layer, including those of its components
+    ///
+    /// Components must already have been processed.
+    fn anchors_for_layer(&mut self, glyph: &Glyph, layer_index: usize) -> Vec<Anchor> {
+        let layer = &glyph.layers[layer_index];
+        if layer.anchors.is_empty() && !layer.has_components() {
+            return vec![];
+        }
+        // Marks with their own anchors are left as they are
+        if !layer.anchors.is_empty() && glyph.category == GlyphCategory::Mark {
+            return origin_adjusted_anchors(&layer.anchors).collect();
+        }
+        let is_ligature = glyph.category == GlyphCategory::Ligature;
+        let mut has_underscore = layer.anchors.iter().any(|a| a.name.starts_with('_'));
+        let mut number_of_base_glyphs = 0;
+        let mut all_anchors: IndexMap<String, Anchor> = IndexMap::new();
+
+        for (component_index, component) in layer.components().enumerate() {
+            let Some((anchors, component_base_glyphs)) = self.component_anchors(component, layer)
+            else {
+                continue;
+            };
+            let component_has_underscore = anchors
+                .iter()
+                .any(|a| a.name.len() >= 2 && a.name.starts_with('_'));
+            let component_has_exit = anchors.iter().any(|a| a.name.ends_with("exit"));
+            if !(component_has_underscore || component_has_exit) {
+                // A glyph has only one exit, and it belongs to the last component
+                all_anchors.retain(|name, _| !name.ends_with("exit"));
+            }
+
+            let transform = component.transform.as_affine();
+            let scale = xy_scale(transform);
+            for anchor in anchors {
+                let new_has_underscore = anchor.name.starts_with('_');
+                if (component_index > 0 || has_underscore) && new_has_underscore {
+                    continue;
+                }
+                if component_index > 0 && anchor.name.ends_with("entry") {
+                    continue;
+                }
+                let mut name = rename_for_scale(&anchor.name, scale);
+                if is_ligature
+                    && component_base_glyphs > 0
+                    && !new_has_underscore
+                    && !(name.ends_with("exit") || name.ends_with("entry"))
+                {
+                    name = ligature_anchor_name(&name, number_of_base_glyphs);
+                }
+                let position = transform * kurbo::Point::new(anchor.x, anchor.y);
+                all_anchors.insert(
+                    name.clone(),
+                    Anchor {
+                        name,
+                        x: position.x,
+                        y: position.y,
+                        ..anchor
+                    },
+                );
+                has_underscore |= new_has_underscore;
+            }
+            number_of_base_glyphs += component_base_glyphs;
+        }
+
+        // Anchors defined on the layer itself win over propagated ones
+        for anchor in origin_adjusted_anchors(&layer.anchors) {
+            all_anchors.insert(anchor.name.clone(), anchor);
+        }
+
+        // Count how many base glyphs we are made of, judging by our anchors
+        let mut has_underscore_anchor = false;
+        let mut has_mark_anchor = false;
+        let mut component_count_from_anchors = 0;
+        for name in all_anchors.keys() {
+            has_underscore_anchor |= name.starts_with('_');
+            has_mark_anchor |= name.starts_with(|c: char| c.is_ascii_alphabetic());
+            if !is_ligature
+                && number_of_base_glyphs == 0
+                && !name.starts_with('_')
+                && !(name.ends_with("entry") || name.ends_with("exit"))
+            {
+                if let Some((_, suffix)) = name.split_once('_') {
+                    // Carets sit between components, so the last one is one fewer
+                    let extra = if name.starts_with("caret") { 1 } else { 0 };
+                    let index = suffix.parse::<usize>().unwrap_or(0) + extra;
+                    component_count_from_anchors = component_count_from_anchors.max(index);
+                }
+            }
+        }
+        if !has_underscore_anchor && number_of_base_glyphs == 0 && has_mark_anchor {
+            number_of_base_glyphs += 1;
+        }
+        number_of_base_glyphs = number_of_base_glyphs.max(component_count_from_anchors);
+
+        if layer.anchors.iter().any(|a| a.name == "_bottom") {
+            all_anchors.shift_remove("top");
+            all_anchors.shift_remove("_top");
+        }
+        if layer.anchors.iter().any(|a| a.name == "_top") {
+            all_anchors.shift_remove("bottom");
+            all_anchors.shift_remove("_bottom");
+        }
+        self.base_glyph_counts
+            .insert((glyph.name.clone(), layer_index), number_of_base_glyphs);
+        all_anchors.into_values().collect()
+    }
+}
+
+impl FontFilter for PropagateAnchors {
+    fn apply(&self, font: &mut crate::Font) -> Result<(), crate::BabelfontError> {
+        log::info!("Propagating anchors");
+        let axes = font
+            .axes
+            .iter()
+            .map(|ax| ax.clone().try_into())
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut base_glyph_counts = HashMap::new();
+        // Components come first, so composites see their propagated anchors
+        for name in font.component_graph().bottom_up() {
+            let Some(glyph) = font.glyphs.get(&name) else {
+                continue;
+            };
+            let mut propagator = Propagator {
+                font,
+                axes: &axes,
+                base_glyph_counts: &mut base_glyph_counts,
+            };
+            let mut layers = vec![];
+            for (layer_index, layer) in glyph.layers.iter().enumerate() {
+                if layer.is_background {
+                    layers.push(None);
+                } else {
+                    layers.push(Some(propagator.anchors_for_layer(glyph, layer_index)));
+                }
+            }
+            if let Some(glyph) = font.glyphs.get_mut(&name) {
+                for (layer, anchors) in glyph.layers.iter_mut().zip(layers) {
+                    if let Some(anchors) = anchors {
+                        layer.anchors = anchors;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn from_str(_s: &str) -> Result<Self, crate::BabelfontError>
+    where
+        Self: Sized,
+    {
+        Ok(PropagateAnchors)
+    }
+
+    #[cfg(feature = "cli")]
+    fn arg() -> clap::Arg
+    where
+        Self: Sized,
+    {
+        clap::Arg::new("propagateanchors")
+            .long("propagate-anchors")
+            .help("Copy anchors from components onto composite glyphs, as Glyphs.app does")
+            .action(clap::ArgAction::SetTrue)
+    }
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{common::decomposition::DecomposedAffine, Master, Shape};
+
+    fn layer(anchors: &[(&str, f64, f64)], components: &[(&str, f64, f64)]) -> Layer {
+        master_layer("m01", anchors, components)
+    }
+
+    fn master_layer(
+        master: &str,
+        anchors: &[(&str, f64, f64)],
+        components: &[(&str, f64, f64)],
+    ) -> Layer {
+        let mut layer = Layer::new(500.0);
+        layer.master = LayerType::DefaultForMaster(master.into());
+        for (name, x, y) in anchors {
+            layer.anchors.push(Anchor {
+                name: name.to_string(),
+                x: *x,
+                y: *y,
+                ..Default::default()
+            });
+        }
+        for (reference, dx, dy) in components {
+            layer.shapes.push(Shape::Component(Component {
+                reference: (*reference).into(),
+                transform: DecomposedAffine {
+                    translation: (*dx, *dy),
+                    ..Default::default()
+                },
+                location: IndexMap::new(),
+                format_specific: Default::default(),
+            }));
+        }
+        layer
+    }
+
+    fn glyph(name: &str, category: GlyphCategory, layer: Layer) -> Glyph {
+        Glyph {
+            name: name.into(),
+            category,
+            exported: true,
+            layers: vec![layer],
+            ..Default::default()
+        }
+    }
+
+    fn anchor(font: &Font, glyph: &str, name: &str) -> Option<(f64, f64)> {
+        layer_anchor(font, glyph, 0, name)
+    }
+
+    fn layer_anchor(font: &Font, glyph: &str, layer: usize, name: &str) -> Option<(f64, f64)> {
+        font.glyphs.get(glyph).unwrap().layers[layer]
+            .anchors
+            .iter()
+            .find(|a| a.name == name)
+            .map(|a| (a.x, a.y))
+    }
+
+    #[test]
+    fn test_propagate_anchors() {
+        let mut font = Font::new();
+        font.masters
+            .push(Master::new("Regular", "m01", Default::default()));
+        // Composites come before their components, to check the ordering
+        font.glyphs.push(glyph(
+            "Aacute",
+            GlyphCategory::Base,
+            layer(&[], &[("A", 0.0, 0.0), ("acutecomb", 50.0, 200.0)]),
+        ));
+        font.glyphs.push(glyph(
+            "f_i",
+            GlyphCategory::Ligature,
+            layer(&[], &[("f", 0.0, 0.0), ("i", 300.0, 0.0)]),
+        ));
+        font.glyphs.push(glyph(
+            "A",
+            GlyphCategory::Base,
+            layer(
+                &[
+                    ("top", 250.0, 700.0),
+                    ("bottom", 250.0, 0.0),
+                    ("ogonek", 400.0, 0.0),
+                ],
+                &[],
+            ),
+        ));
+        font.glyphs.push(glyph(
+            "acutecomb",
+            GlyphCategory::Mark,
+            layer(&[("_top", 200.0, 500.0), ("top", 200.0, 700.0)], &[]),
+        ));
+        font.glyphs.push(glyph(
+            "f",
+            GlyphCategory::Base,
+            layer(&[("top", 150.0, 700.0)], &[]),
+        ));
+        font.glyphs.push(glyph(
+            "i",
+            GlyphCategory::Base,
+            layer(&[("top", 100.0, 600.0)], &[]),
+        ));
+
+        PropagateAnchors.apply(&mut font).unwrap();
+
+        // top comes from the acute, moved by its offset; the rest from A
+        assert_eq!(anchor(&font, "Aacute", "top"), Some((250.0, 900.0)));
+        assert_eq!(anchor(&font, "Aacute", "bottom"), Some((250.0, 0.0)));
+        assert_eq!(anchor(&font, "Aacute", "ogonek"), Some((400.0, 0.0)));
+        // The mark's attachment anchor is not propagated to the composite
+        assert_eq!(anchor(&font, "Aacute", "_top"), None);
+
+        assert_eq!(anchor(&font, "f_i", "top_1"), Some((150.0, 700.0)));
+        assert_eq!(anchor(&font, "f_i", "top_2"), Some((400.0, 600.0)));
+        assert_eq!(anchor(&font, "f_i", "top"), None);
+    }
+
+    #[test]
+    fn test_flipped_component_renames_anchors() {
+        assert_eq!(
+            rename_for_scale("top", xy_scale(kurbo::Affine::scale_non_uniform(1.0, -1.0))),
+            "bottom"
+        );
+        assert_eq!(
+            rename_for_scale(
+                "exit",
+                xy_scale(kurbo::Affine::scale_non_uniform(-1.0, 1.0))
+            ),
+            "entry"
+        );
+        assert_eq!(
+            rename_for_scale("top", xy_scale(kurbo::Affine::rotate(0.5))),
+            "top"
+        );
+    }
+
+    /// A font with `A` and `acutecomb`, listed after the given composites
+    fn font_with_a_and_acute(composites: Vec<Glyph>) -> Font {
+        let mut font = Font::new();
+        font.masters
+            .push(Master::new("Regular", "m01", Default::default()));
+        for composite in composites {
+            font.glyphs.push(composite);
+        }
+        font.glyphs.push(glyph(
+            "A",
+            GlyphCategory::Base,
+            layer(
+                &[
+                    ("top", 250.0, 700.0),
+                    ("bottom", 250.0, 0.0),
+                    ("ogonek", 400.0, 0.0),
+                ],
+                &[],
+            ),
+        ));
+        font.glyphs.push(glyph(
+            "acutecomb",
+            GlyphCategory::Mark,
+            layer(&[("_top", 200.0, 500.0), ("top", 200.0, 700.0)], &[]),
+        ));
+        font
+    }
+
+    #[test]
+    fn test_nested_components() {
+        // Two levels of nesting, listed before the glyphs they are made from
+        let mut font = font_with_a_and_acute(vec![
+            glyph(
+                "Aacuteacute",
+                GlyphCategory::Base,
+                layer(&[], &[("Aacute", 10.0, 0.0), ("acutecomb", 60.0, 400.0)]),
+            ),
+            glyph(
+                "Aacute",
+                GlyphCategory::Base,
+                layer(&[], &[("A", 0.0, 0.0), ("acutecomb", 50.0, 200.0)]),
+            ),
+        ]);
+
+        PropagateAnchors.apply(&mut font).unwrap();
+
+        assert_eq!(anchor(&font, "Aacute", "top"), Some((250.0, 900.0)));
+        // bottom comes from A through Aacute, top from the outer acute
+        assert_eq!(anchor(&font, "Aacuteacute", "bottom"), Some((260.0, 0.0)));
+        assert_eq!(anchor(&font, "Aacuteacute", "ogonek"), Some((410.0, 0.0)));
+        assert_eq!(anchor(&font, "Aacuteacute", "top"), Some((260.0, 1100.0)));
+        assert_eq!(anchor(&font, "Aacuteacute", "_top"), None);
+    }
+
+    #[test]
+    fn test_transformed_components() {
+        let mut flipped = layer(&[], &[]);
+        flipped.shapes.push(Shape::Component(Component {
+            reference: "A".into(),
+            transform: DecomposedAffine {
+                translation: (0.0, 700.0),
+                scale: (1.0, -1.0),
+                ..Default::default()
+            },
+            location: IndexMap::new(),
+            format_specific: Default::default(),
+        }));
+        let mut scaled = layer(&[], &[]);
+        scaled.shapes.push(Shape::Component(Component {
+            reference: "A".into(),
+            transform: DecomposedAffine {
+                translation: (100.0, 0.0),
+                scale: (0.5, 0.5),
+                ..Default::default()
+            },
+            location: IndexMap::new(),
+            format_specific: Default::default(),
+        }));
+        let mut font = font_with_a_and_acute(vec![
+            glyph("A.flipped", GlyphCategory::Base, flipped),
+            glyph("A.small", GlyphCategory::Base, scaled),
+        ]);
+
+        PropagateAnchors.apply(&mut font).unwrap();
+
+        // Flipping vertically swaps top and bottom, and moves them with the outline
+        assert_eq!(anchor(&font, "A.flipped", "top"), Some((250.0, 700.0)));
+        assert_eq!(anchor(&font, "A.flipped", "bottom"), Some((250.0, 0.0)));
+        assert_eq!(anchor(&font, "A.flipped", "ogonek"), Some((400.0, 700.0)));
+
+        assert_eq!(anchor(&font, "A.small", "top"), Some((225.0, 350.0)));
+        assert_eq!(anchor(&font, "A.small", "bottom"), Some((225.0, 0.0)));
+        assert_eq!(anchor(&font, "A.small", "ogonek"), Some((300.0, 0.0)));
+    }
+
+    #[test]
+    fn test_multiple_masters() {
+        let mut font = Font::new();
+        font.masters
+            .push(Master::new("Regular", "m01", Default::default()));
+        font.masters
+            .push(Master::new("Bold", "m02", Default::default()));
+        let mut aacute = glyph(
+            "Aacute",
+            GlyphCategory::Base,
+            master_layer("m01", &[], &[("A", 0.0, 0.0), ("acutecomb", 50.0, 200.0)]),
+        );
+        aacute.layers.push(master_layer(
+            "m02",
+            &[],
+            &[("A", 0.0, 0.0), ("acutecomb", 80.0, 230.0)],
+        ));
+        font.glyphs.push(aacute);
+        // The bold master of A comes first, to check layers are matched by master
+        let mut a = glyph(
+            "A",
+            GlyphCategory::Base,
+            master_layer("m02", &[("top", 300.0, 750.0), ("bottom", 300.0, 0.0)], &[]),
+        );
+        a.layers.push(master_layer(
+            "m01",
+            &[("top", 250.0, 700.0), ("bottom", 250.0, 0.0)],
+            &[],
+        ));
+        font.glyphs.push(a);
+        let mut acutecomb = glyph(
+            "acutecomb",
+            GlyphCategory::Mark,
+            master_layer("m01", &[("_top", 200.0, 500.0), ("top", 200.0, 700.0)], &[]),
+        );
+        acutecomb.layers.push(master_layer(
+            "m02",
+            &[("_top", 220.0, 520.0), ("top", 220.0, 760.0)],
+            &[],
+        ));
+        font.glyphs.push(acutecomb);
+
+        PropagateAnchors.apply(&mut font).unwrap();
+
+        assert_eq!(layer_anchor(&font, "Aacute", 0, "top"), Some((250.0, 900.0)));
+        assert_eq!(layer_anchor(&font, "Aacute", 0, "bottom"), Some((250.0, 0.0)));
+        assert_eq!(layer_anchor(&font, "Aacute", 1, "top"), Some((300.0, 990.0)));
+        assert_eq!(layer_anchor(&font, "Aacute", 1, "bottom"), Some((300.0, 0.0)));
+    }
+}
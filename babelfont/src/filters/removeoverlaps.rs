@@ -0,0 +1,385 @@
+use indexmap::IndexSet;
+use smol_str::SmolStr;
+
+use super::{curve_filter_common::signature, path_union};
+use crate::{filters::FontFilter, BabelfontError, Font, Layer, Path, Shape};
+
+/// What to do when removing overlaps would break interpolation compatibility
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverlapCompatibility {
+    /// Remove overlaps regardless; suitable for static fonts
+    #[default]
+    Ignore,
+    /// Leave glyphs untouched if removing overlaps would make their layers incompatible
+    Skip,
+    /// Remove overlaps, but warn about glyphs whose layers become incompatible
+    Flag,
+}
+
+/// A filter that removes overlapping contours by taking the union of each layer's paths
+///
+/// Paths are combined using the nonzero fill rule, so contours drawn in opposite
+/// directions cut holes rather than being merged. Curves keep their type, so
+/// quadratic outlines stay quadratic, and the new contours go in the same
+/// direction as most of the original ones. Open paths are left alone.
+///
+/// Removing overlaps typically changes the structure of a glyph differently in
+/// each master, so variable fonts should use [`OverlapCompatibility::Skip`] or
+/// [`OverlapCompatibility::Flag`] to avoid (or at least be told about) glyphs
+/// which no longer interpolate.
+#[derive(Debug, Clone, Default)]
+pub struct RemoveOverlaps {
+    glyphs: Option<IndexSet<SmolStr>>,
+    decompose: bool,
+    compatibility: OverlapCompatibility,
+}
+
+impl RemoveOverlaps {
+    /// Create a new filter which removes overlaps in all glyphs
+    pub fn new() -> Self {
+        RemoveOverlaps::default()
+    }
+
+    /// Only remove overlaps in the given glyphs
+    pub fn glyphs<I, S>(mut self, glyphs: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<SmolStr>,
+    {
+        self.glyphs = Some(glyphs.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Decompose components before removing overlaps
+    pub fn decompose(mut self, decompose: bool) -> Self {
+        self.decompose = decompose;
+        self
+    }
+
+    /// Set how glyphs which would become incompatible are treated
+    pub fn compatibility(mut self, compatibility: OverlapCompatibility) -> Self {
+        self.compatibility = compatibility;
+        self
+    }
+
+    fn new_shapes(&self, font: &Font, layer: &Layer) -> Result<Vec<Shape>, BabelfontError> {
+        let source = if self.decompose && layer.has_components() {
+            layer.decomposed(font)
+        } else {
+            layer.clone()
+        };
+        let mut shapes: Vec<Shape> = source
+            .shapes
+            .iter()
+            .filter(|shape| match shape {
+                Shape::Component(_) => true,
+                Shape::Path(path) => !path.closed,
+            })
+            .cloned()
+            .collect();
+        let closed: Vec<&Path> = source.paths().filter(|p| p.closed).collect();
+        shapes.extend(union(&closed)?.into_iter().map(Shape::Path));
+        Ok(shapes)
+    }
+}
+
+/// The union of a set of closed paths
+fn union(paths: &[&Path]) -> Result<Vec<Path>, BabelfontError> {
+    let contours = paths
+        .iter()
+        .map(|path| path.to_kurbo())
+        .collect::<Result<Vec<_>, _>>()?;
+    let union = path_union::union(&contours).ok_or_else(|| {
+        BabelfontError::FilterError("Could not remove overlaps: outline did not close".to_string())
+    })?;
+    Ok(union.into_iter().map(Path::from).collect())
+}
+
+/// The structure of a layer's paths, for checking interpolation compatibility
+fn path_signatures(shapes: &[Shape]) -> Result<Vec<String>, BabelfontError> {
+    shapes
+        .iter()
+        .filter_map(|shape| shape.as_path())
+        .map(|path| path.to_kurbo().map(|bez| signature(&bez)))
+        .collect()
+}
+
+fn compatible<'a>(mut signatures: impl Iterator<Item = &'a Vec<String>>) -> bool {
+    let Some(first) = signatures.next() else {
+        return true;
+    };
+    signatures.all(|other| other == first)
+}
+
+impl FontFilter for RemoveOverlaps {
+    fn apply(&self, font: &mut Font) -> Result<(), BabelfontError> {
+        log::info!("Removing overlaps");
+        let mut changes = vec![];
+        for glyph in font.glyphs.iter() {
+            if self
+                .glyphs
+                .as_ref()
+                .is_some_and(|glyphs| !glyphs.contains(&glyph.name))
+            {
+                continue;
+            }
+            let mut new_layers = vec![];
+            for (index, layer) in glyph.layers.iter().enumerate() {
+                if layer.is_background || !(layer.has_paths() || self.decompose) {
+                    continue;
+                }
+                new_layers.push((index, self.new_shapes(font, layer)?));
+            }
+            if self.compatibility != OverlapCompatibility::Ignore {
+                let interpolating: Vec<usize> = glyph
+                    .layers
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, layer)| layer.should_interpolate())
+                    .map(|(index, _)| index)
+                    .collect();
+                let before = interpolating
+                    .iter()
+                    .map(|&index| path_signatures(&glyph.layers[index].shapes))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let after = new_layers
+                    .iter()
+                    .filter(|(index, _)| interpolating.contains(index))
+                    .map(|(_, shapes)| path_signatures(shapes))
+                    .collect::<Result<Vec<_>, _>>()?;
+                // Only worry about glyphs that were compatible to begin with
+                if compatible(before.iter()) && !compatible(after.iter()) {
+                    if self.compatibility == OverlapCompatibility::Skip {
+                        log::info!(
+                            "Not removing overlaps in {}; its layers would become incompatible",
+                            glyph.name
+                        );
+                        continue;
+                    }
+                    log::warn!(
+                        "Removing overlaps made the layers of {} incompatible",
+                        glyph.name
+                    );
+                }
+            }
+            changes.push((glyph.name.clone(), new_layers));
+        }
+        for (name, new_layers) in changes {
+            let Some(glyph) = font.glyphs.get_mut(&name) else {
+                continue;
+            };
+            for (index, shapes) in new_layers {
+                glyph.layers[index].shapes = shapes;
+            }
+        }
+        Ok(())
+    }
+
+    fn from_str(s: &str) -> Result<Self, BabelfontError>
+    where
+        Self: Sized,
+    {
+        let mut filter = RemoveOverlaps::new();
+        let mut glyphs = vec![];
+        for item in s.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            match item {
+                "decompose" => filter = filter.decompose(true),
+                "skip-incompatible" => filter = filter.compatibility(OverlapCompatibility::Skip),
+                "flag-incompatible" => filter = filter.compatibility(OverlapCompatibility::Flag),
+                glyph => glyphs.push(glyph),
+            }
+        }
+        if !glyphs.is_empty() {
+            filter = filter.glyphs(glyphs);
+        }
+        Ok(filter)
+    }
+
+    #[cfg(feature = "cli")]
+    fn arg() -> clap::Arg
+    where
+        Self: Sized,
+    {
+        clap::Arg::new("removeoverlaps")
+            .long("remove-overlaps")
+            .help("Remove overlapping contours (optionally list glyphs, plus 'decompose', 'skip-incompatible' or 'flag-incompatible')")
+            .value_name("OPTIONS")
+            .action(clap::ArgAction::Append)
+            .default_missing_value("")
+            .required(false)
+    }
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Glyph, LayerType, Master, Node};
+
+    fn square(x: f64, y: f64, size: f64) -> Shape {
+        rectangle(x, y, x + size, y + size)
+    }
+
+    fn rectangle(x0: f64, y0: f64, x1: f64, y1: f64) -> Shape {
+        Shape::Path(Path {
+            nodes: vec![
+                Node::new_line(x0, y0),
+                Node::new_line(x1, y0),
+                Node::new_line(x1, y1),
+                Node::new_line(x0, y1),
+            ],
+            closed: true,
+            format_specific: Default::default(),
+        })
+    }
+
+    fn font_with_layers(layers: Vec<Vec<Shape>>) -> Font {
+        let mut font = Font::new();
+        let mut glyph = Glyph {
+            name: "A".into(),
+            ..Default::default()
+        };
+        for (index, shapes) in layers.into_iter().enumerate() {
+            let id = format!("m{:02}", index);
+            font.masters
+                .push(Master::new(id.clone(), id.clone(), Default::default()));
+            let mut layer = Layer::new(500.0);
+            layer.master = LayerType::DefaultForMaster(id);
+            layer.shapes = shapes;
+            glyph.layers.push(layer);
+        }
+        font.glyphs.push(glyph);
+        font
+    }
+
+    #[test]
+    fn test_remove_overlaps() {
+        let mut font = font_with_layers(vec![vec![
+            square(0.0, 0.0, 100.0),
+            square(50.0, 50.0, 100.0),
+        ]]);
+        RemoveOverlaps::new().apply(&mut font).unwrap();
+        let layer = &font.glyphs.get("A").unwrap().layers[0];
+        assert_eq!(layer.shapes.len(), 1);
+        let path = layer.shapes[0].as_path().unwrap();
+        assert_eq!(path.nodes.len(), 8);
+        assert!(path
+            .nodes
+            .iter()
+            .all(|n| n.nodetype == crate::NodeType::Line));
+        let mut corners: Vec<(f64, f64)> = path.nodes.iter().map(|n| (n.x, n.y)).collect();
+        corners.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(
+            corners,
+            vec![
+                (0.0, 0.0),
+                (0.0, 100.0),
+                (50.0, 100.0),
+                (50.0, 150.0),
+                (100.0, 0.0),
+                (100.0, 50.0),
+                (150.0, 50.0),
+                (150.0, 150.0)
+            ]
+        );
+        // Same direction as the squares it came from
+        assert!((area(path) - 17500.0).abs() < 1e-6);
+    }
+
+    fn circle(x: f64, y: f64, radius: f64) -> Path {
+        Path::from(kurbo::Shape::to_path(
+            &kurbo::Circle::new((x, y), radius),
+            0.1,
+        ))
+    }
+
+    fn area(path: &Path) -> f64 {
+        kurbo::Shape::area(&path.to_kurbo().unwrap())
+    }
+
+    #[test]
+    fn test_remove_overlaps_keeps_curves_and_counters() {
+        // An "o" with a bar through it
+        let mut counter = circle(0.0, 0.0, 50.0);
+        counter.reverse();
+        let mut font = font_with_layers(vec![vec![
+            Shape::Path(circle(0.0, 0.0, 100.0)),
+            Shape::Path(counter),
+            rectangle(-10.0, -150.0, 10.0, 150.0),
+        ]]);
+        RemoveOverlaps::new().apply(&mut font).unwrap();
+        let layer = &font.glyphs.get("A").unwrap().layers[0];
+        // The outside, and the two halves of the counter
+        assert_eq!(layer.shapes.len(), 3);
+        let mut areas: Vec<f64> = layer
+            .shapes
+            .iter()
+            .map(|shape| area(shape.as_path().unwrap()))
+            .collect();
+        areas.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert!(areas[0] < 0.0 && areas[1] < 0.0 && areas[2] > 0.0);
+        assert!((areas[0] - areas[1]).abs() < 1e-3);
+        // Each half of the counter is a half-disc less a strip of the bar
+        let half_counter = std::f64::consts::PI * 50.0 * 50.0 / 2.0 - 10.0 * 100.0;
+        assert!((areas[0] + half_counter).abs() < 50.0);
+        assert!(layer
+            .shapes
+            .iter()
+            .flat_map(|shape| shape.as_path().unwrap().nodes.iter())
+            .any(|node| node.nodetype == crate::NodeType::Curve));
+    }
+
+    #[test]
+    fn test_remove_overlaps_keeps_quadratics() {
+        let quadratic = |x: f64| {
+            // A dome from (x, 0) to (x + 100, 0), drawn anticlockwise
+            Shape::Path(Path {
+                nodes: vec![
+                    Node::new_line(x + 100.0, 0.0),
+                    Node::new_offcurve(x + 50.0, 100.0),
+                    Node::new_qcurve(x, 0.0),
+                ],
+                closed: true,
+                format_specific: Default::default(),
+            })
+        };
+        let mut font = font_with_layers(vec![vec![quadratic(0.0), quadratic(50.0)]]);
+        RemoveOverlaps::new().apply(&mut font).unwrap();
+        let layer = &font.glyphs.get("A").unwrap().layers[0];
+        assert_eq!(layer.shapes.len(), 1);
+        let path = layer.shapes[0].as_path().unwrap();
+        assert_eq!(
+            path.nodes
+                .iter()
+                .filter(|n| n.nodetype == crate::NodeType::QCurve)
+                .count(),
+            2
+        );
+        assert!(!path
+            .nodes
+            .iter()
+            .any(|n| n.nodetype == crate::NodeType::Curve));
+    }
+
+    #[test]
+    fn test_skip_incompatible() {
+        // Overlapping in one master, separate in the other
+        let layers = vec![
+            vec![square(0.0, 0.0, 100.0), square(50.0, 50.0, 100.0)],
+            vec![square(0.0, 0.0, 100.0), square(200.0, 200.0, 100.0)],
+        ];
+        let mut font = font_with_layers(layers.clone());
+        RemoveOverlaps::new()
+            .compatibility(OverlapCompatibility::Skip)
+            .apply(&mut font)
+            .unwrap();
+        assert_eq!(font.glyphs.get("A").unwrap().layers[0].shapes.len(), 2);
+
+        let mut font = font_with_layers(layers);
+        RemoveOverlaps::new()
+            .compatibility(OverlapCompatibility::Flag)
+            .apply(&mut font)
+            .unwrap();
+        assert_eq!(font.glyphs.get("A").unwrap().layers[0].shapes.len(), 1);
+    }
+}
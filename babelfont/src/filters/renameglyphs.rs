@@ -3,7 +3,10 @@ use crate::{filters::FontFilter, font::Font, Features};
 use fea_rs_ast::{AsFea, FeatureFile, LayoutVisitor};
 use regex::Regex;
 use smol_str::SmolStr;
-use std::{collections::{BTreeMap, HashMap}, sync::LazyLock};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::LazyLock,
+};
 
 // Glyph names are [\w_\.]+; we fold in @ to avoid matching classes.
 #[allow(clippy::unwrap_used)]
@@ -124,8 +127,12 @@ impl FontFilter for RenameGlyphs {
         let features = font.features.to_fea();
         let glyph_names: Vec<_> = font.glyphs.iter().map(|g| g.name.as_str()).collect();
         let mut feature_file: FeatureFile =
-            FeatureFile::new_from_fea(&features, Some(&glyph_names), font.source.clone())
-                .map_err(|e| crate::BabelfontError::FilterError(e.to_string()))?;
+            FeatureFile::new_from_fea(&features, Some(&glyph_names), font.source.clone()).map_err(
+                |e| {
+                    crate::BabelfontError::from(e)
+                        .with_feature_sources(&font.features, font.source.as_deref())
+                },
+            )?;
         let mut visitor = GlyphRenamerVisitor::new(self.0.clone());
         visitor.visit(&mut feature_file).map_err(|e| {
             crate::BabelfontError::FilterError(format!("Error during glyph renaming: {}", e))
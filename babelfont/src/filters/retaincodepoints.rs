@@ -0,0 +1,228 @@
+use std::collections::{BTreeSet, HashSet};
+
+use icu_properties::{props::Script, script::ScriptWithExtensions, PropertyParser};
+use smol_str::SmolStr;
+
+use crate::{
+    close_layout,
+    filters::{FontFilter, RetainGlyphs},
+    BabelfontError, Font,
+};
+
+/// A filter that keeps only the glyphs needed to support a set of codepoints or scripts
+///
+/// The encoded glyphs for the requested codepoints and scripts are collected,
+/// along with any glyphs they use as components and any glyphs reachable from
+/// them through the font's layout rules; everything else is removed with
+/// [`RetainGlyphs`]. Scripts are given as ISO 15924 codes and match codepoints
+/// by their Unicode script extensions. `.notdef` is always kept.
+///
+/// When any script is given, every encoded glyph whose codepoint has the
+/// `Common` (`Zyyy`) or `Inherited` (`Zinh`) script is kept too, as these are
+/// shared by all scripts: digits, punctuation, spaces, combining marks, but
+/// also symbols, arrows, box drawing and so on. To subset those as well, give
+/// the codepoints wanted explicitly instead of a script.
+#[derive(Debug, Clone, Default)]
+pub struct RetainCodepoints {
+    codepoints: BTreeSet<u32>,
+    scripts: Vec<Script>,
+}
+
+impl RetainCodepoints {
+    /// Create a new filter retaining the given codepoints
+    pub fn new(codepoints: impl IntoIterator<Item = u32>) -> Self {
+        RetainCodepoints {
+            codepoints: codepoints.into_iter().collect(),
+            scripts: vec![],
+        }
+    }
+
+    /// Also retain all codepoints in the scripts with the given ISO 15924 codes
+    ///
+    /// This also retains every `Common` and `Inherited` codepoint in the font.
+    pub fn with_scripts<S: AsRef<str>>(
+        mut self,
+        scripts: impl IntoIterator<Item = S>,
+    ) -> Result<Self, BabelfontError> {
+        for script in scripts {
+            self.scripts.push(parse_script(script.as_ref())?);
+        }
+        Ok(self)
+    }
+
+    /// Also retain the codepoints listed in a file
+    ///
+    /// The file has one hexadecimal codepoint or range (`0041-005A`) per line;
+    /// `#` starts a comment.
+    pub fn with_file(mut self, path: impl AsRef<std::path::Path>) -> Result<Self, BabelfontError> {
+        let contents = std::fs::read_to_string(path)?;
+        for line in contents.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if !line.is_empty() {
+                self.codepoints.extend(parse_range(line)?);
+            }
+        }
+        Ok(self)
+    }
+
+    fn wanted(&self, codepoint: u32) -> bool {
+        if self.codepoints.contains(&codepoint) {
+            return true;
+        }
+        if self.scripts.is_empty() {
+            return false;
+        }
+        let extensions = ScriptWithExtensions::new();
+        [Script::Common, Script::Inherited]
+            .iter()
+            .chain(self.scripts.iter())
+            .any(|script| extensions.has_script32(codepoint, *script))
+    }
+}
+
+fn parse_script(code: &str) -> Result<Script, BabelfontError> {
+    PropertyParser::<Script>::new()
+        .get_strict(code)
+        .ok_or_else(|| BabelfontError::FilterError(format!("Unknown script code: {}", code)))
+}
+
+fn parse_codepoint(s: &str) -> Result<u32, BabelfontError> {
+    let hex = s
+        .trim()
+        .trim_start_matches("U+")
+        .trim_start_matches("u+")
+        .trim_start_matches("0x");
+    u32::from_str_radix(hex, 16)
+        .map_err(|_| BabelfontError::FilterError(format!("Invalid codepoint: {}", s)))
+}
+
+pub(crate) fn parse_range(s: &str) -> Result<std::ops::RangeInclusive<u32>, BabelfontError> {
+    match s.split_once('-') {
+        Some((start, end)) => Ok(parse_codepoint(start)?..=parse_codepoint(end)?),
+        None => {
+            let codepoint = parse_codepoint(s)?;
+            Ok(codepoint..=codepoint)
+        }
+    }
+}
+
+/// Whether a string looks like an ISO 15924 script code (e.g. `Latn`)
+fn is_script_code(s: &str) -> bool {
+    let mut chars = s.chars();
+    s.len() == 4
+        && chars.next().is_some_and(|c| c.is_ascii_uppercase())
+        && chars.all(|c| c.is_ascii_lowercase())
+}
+
+impl FontFilter for RetainCodepoints {
+    fn apply(&self, font: &mut Font) -> Result<(), BabelfontError> {
+        let mut retained: HashSet<SmolStr> = font
+            .glyphs
+            .iter()
+            .filter(|glyph| {
+                glyph.name == ".notdef" || glyph.codepoints.iter().any(|&cp| self.wanted(cp))
+            })
+            .map(|glyph| glyph.name.clone())
+            .collect();
+
+        // Add components (and their components) of retained glyphs
+        let graph = font.component_graph();
+        let components: Vec<SmolStr> = retained
+            .iter()
+            .flat_map(|name| graph.dependencies(name))
+            .collect();
+        retained.extend(components);
+
+        let retained = close_layout(font, retained)?;
+        log::info!(
+            "Retaining {} glyphs for requested codepoints",
+            retained.len()
+        );
+        let glyph_names = font
+            .glyphs
+            .iter()
+            .filter(|glyph| retained.contains(&glyph.name))
+            .map(|glyph| glyph.name.to_string())
+            .collect();
+        RetainGlyphs::new(glyph_names).apply(font)
+    }
+
+    fn from_str(s: &str) -> Result<Self, BabelfontError>
+    where
+        Self: Sized,
+    {
+        let mut filter = RetainCodepoints::default();
+        for item in s.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            if is_script_code(item) {
+                filter.scripts.push(parse_script(item)?);
+            } else if std::path::Path::new(item).is_file() {
+                filter = filter.with_file(item)?;
+            } else {
+                filter.codepoints.extend(parse_range(item)?);
+            }
+        }
+        Ok(filter)
+    }
+
+    #[cfg(feature = "cli")]
+    fn arg() -> clap::Arg
+    where
+        Self: Sized,
+    {
+        clap::Arg::new("retaincodepoints")
+            .long("retain-codepoints")
+            .help("Retain only glyphs needed for the given codepoints, ranges (0041-005A), codepoint files or ISO 15924 script codes (Latn), comma-separated")
+            .value_name("CODEPOINTS")
+            .action(clap::ArgAction::Append)
+    }
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{common::decomposition::DecomposedAffine, Component, Glyph, Layer, Shape};
+
+    fn glyph(name: &str, codepoints: Vec<u32>, components: &[&str]) -> Glyph {
+        let mut layer = Layer::new(500.0);
+        for component in components {
+            layer.shapes.push(Shape::Component(Component {
+                reference: (*component).into(),
+                transform: DecomposedAffine::default(),
+                location: Default::default(),
+                format_specific: Default::default(),
+            }));
+        }
+        Glyph {
+            name: name.into(),
+            codepoints,
+            layers: vec![layer],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let filter = RetainCodepoints::from_str("U+0041-0043, 00E9, Grek").unwrap();
+        assert_eq!(
+            filter.codepoints.iter().copied().collect::<Vec<_>>(),
+            vec![0x41, 0x42, 0x43, 0xE9]
+        );
+        assert!(filter.wanted(0x3B1)); // alpha
+        assert!(filter.wanted(0x20)); // space is common to all scripts
+        assert!(!filter.wanted(0x5D0)); // alef
+    }
+
+    #[test]
+    fn test_retain_codepoints() {
+        let mut font = Font::new();
+        font.glyphs.push(glyph(".notdef", vec![], &[]));
+        font.glyphs.push(glyph("A", vec![0x41], &[]));
+        font.glyphs.push(glyph("acutecomb", vec![0x301], &[]));
+        font.glyphs.push(glyph("Aacute", vec![0xC1], &["A", "acutecomb"]));
+        font.glyphs.push(glyph("alpha", vec![0x3B1], &[]));
+        RetainCodepoints::new([0xC1]).apply(&mut font).unwrap();
+        let names: Vec<_> = font.glyphs.iter().map(|g| g.name.as_str()).collect();
+        assert_eq!(names, vec![".notdef", "A", "acutecomb", "Aacute"]);
+    }
+}
@@ -1,6 +1,6 @@
 use crate::filters::{DecomposeComponentReferences, FontFilter, SubsetLayout};
+use indexmap::IndexSet;
 use smol_str::SmolStr;
-use std::collections::HashSet;
 
 /// A filter that retains only the specified glyphs in a font
 ///
@@ -24,7 +24,7 @@ impl FontFilter for RetainGlyphs {
         SubsetLayout::new(self.0.clone()).apply(font)?;
 
         // Find components referenced by retained glyphs that will be dropped
-        let mut components_to_decompose = HashSet::new();
+        let mut components_to_decompose = IndexSet::new();
         for glyph in font.glyphs.iter() {
             if !self.0.contains(&glyph.name) {
                 continue; // Only look at retained glyphs
@@ -33,20 +33,16 @@ impl FontFilter for RetainGlyphs {
                 if layer.is_background {
                     continue;
                 }
-                for shape in &layer.shapes {
-                    if let crate::Shape::Component(comp) = shape {
-                        // If this component references a glyph being dropped, mark it for decomposition
-                        let reference = comp.reference.as_str();
-                        if !self.0.contains(&comp.reference)
-                            && !components_to_decompose.contains(reference)
-                        {
-                            components_to_decompose.insert(reference);
-                            log::debug!(
-                                "Decomposing component {} used by glyph {}",
-                                comp.reference,
-                                glyph.name
-                            );
-                        }
+                for component in layer.components() {
+                    // If this component references a glyph being dropped, mark it for decomposition
+                    if !self.0.contains(&component.reference)
+                        && components_to_decompose.insert(component.reference.clone())
+                    {
+                        log::debug!(
+                            "Decomposing component {} used by glyph {}",
+                            component.reference,
+                            glyph.name
+                        );
                     }
                 }
             }
@@ -121,3 +117,45 @@ impl FontFilter for RetainGlyphs {
             .action(clap::ArgAction::Append)
     }
 }
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{common::decomposition::DecomposedAffine, Component, Glyph, Layer, Shape};
+
+    fn component(reference: &str) -> Shape {
+        Shape::Component(Component {
+            reference: reference.into(),
+            transform: DecomposedAffine::default(),
+            location: Default::default(),
+            format_specific: Default::default(),
+        })
+    }
+
+    #[test]
+    fn test_background_components_are_not_decomposed() {
+        let mut font = crate::Font::new();
+        let mut foreground = Layer::new(500.0);
+        foreground.background_layer_id = Some("bg".into());
+        let mut background = Layer::new(500.0);
+        background.id = Some("bg".into());
+        background.is_background = true;
+        background.shapes.push(component("B"));
+        font.glyphs.push(Glyph {
+            name: "A".into(),
+            layers: vec![foreground, background],
+            ..Default::default()
+        });
+        font.glyphs.push(Glyph::new("B"));
+
+        RetainGlyphs::new(vec!["A".to_string()])
+            .apply(&mut font)
+            .unwrap();
+        assert!(font.glyphs.get("B").is_none());
+        // The background still refers to the dropped glyph
+        let glyph = font.glyphs.get("A").unwrap();
+        let components: Vec<_> = glyph.layers[1].components().collect();
+        assert_eq!(components[0].reference, "B");
+    }
+}
@@ -0,0 +1,432 @@
+use std::collections::HashMap;
+
+use fontdrasil::coords::DesignCoord;
+use kurbo::Affine;
+
+use crate::{
+    common::decomposition::DecomposedAffine,
+    filters::{AddMaster, FontFilter},
+    BabelfontError, Font, Guide, I18NDictionary, LayerType, MetricType, Shape, Tag,
+};
+
+/// Bit for italic styles in the OS/2 `fsSelection` field
+const FS_SELECTION_ITALIC: u16 = 1 << 0;
+/// Bit for regular styles in the OS/2 `fsSelection` field
+const FS_SELECTION_REGULAR: u16 = 1 << 6;
+
+/// The height around which outlines are slanted
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum SlantOrigin {
+    /// Slant around the baseline
+    #[default]
+    Baseline,
+    /// Slant around half of each master's x-height, so that lowercase letters keep their position
+    HalfXHeight,
+    /// Slant around a fixed height
+    Height(f64),
+}
+
+/// A filter that makes an oblique font by skewing its outlines
+///
+/// Paths, anchors and guides are skewed by the given angle (in degrees, positive
+/// leaning to the right), and component transforms are adjusted to match. Each
+/// master's italic angle and `hhea` caret slope are set, and the font's names
+/// and style linking are updated to describe an italic style.
+///
+/// Alternatively, the slanted outlines can be added to a single-master font as a
+/// new master on a `slnt` axis, using [`AddMaster`]; in that case the names are
+/// left alone.
+#[derive(Debug, Clone)]
+pub struct Slant {
+    angle: f64,
+    origin: SlantOrigin,
+    as_master: bool,
+}
+
+impl Slant {
+    /// Create a new Slant filter with the given angle in degrees
+    pub fn new(angle: f64) -> Self {
+        Slant {
+            angle,
+            origin: SlantOrigin::default(),
+            as_master: false,
+        }
+    }
+
+    /// Set the height around which outlines are slanted
+    pub fn origin(mut self, origin: SlantOrigin) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    /// Add the slanted outlines as a new master on a `slnt` axis instead of slanting the font
+    pub fn as_master(mut self, as_master: bool) -> Self {
+        self.as_master = as_master;
+        self
+    }
+
+    fn origin_for(&self, metrics: Option<&indexmap::IndexMap<MetricType, i32>>) -> f64 {
+        match self.origin {
+            SlantOrigin::Baseline => 0.0,
+            SlantOrigin::HalfXHeight => metrics
+                .and_then(|m| m.get(&MetricType::XHeight))
+                .map(|&x| x as f64 / 2.0)
+                .unwrap_or(0.0),
+            SlantOrigin::Height(y) => y,
+        }
+    }
+
+    /// Skew the outlines and set the metrics of every master
+    fn slant_outlines(&self, font: &mut Font) {
+        let tan = self.angle.to_radians().tan();
+        let skew = |x: f64, y: f64, origin: f64| x + (y - origin) * tan;
+        let default_origin = self.origin_for(font.default_master().map(|m| &m.metrics));
+        let upm = font.upm as f64;
+        let mut origins = HashMap::new();
+        for master in font.masters.iter_mut() {
+            let origin = self.origin_for(Some(&master.metrics));
+            origins.insert(master.id.clone(), origin);
+            for guide in master.guides.iter_mut() {
+                skew_guide(guide, tan, origin);
+            }
+            master
+                .metrics
+                .insert(MetricType::ItalicAngle, -self.angle.round() as i32);
+            master
+                .metrics
+                .insert(MetricType::HheaCaretSlopeRise, upm.round() as i32);
+            master
+                .metrics
+                .insert(MetricType::HheaCaretSlopeRun, (upm * tan).round() as i32);
+        }
+
+        for glyph in font.glyphs.iter_mut() {
+            for layer in glyph.layers.iter_mut() {
+                let origin = match &layer.master {
+                    LayerType::DefaultForMaster(id) | LayerType::AssociatedWithMaster(id) => {
+                        origins.get(id).copied().unwrap_or(default_origin)
+                    }
+                    LayerType::FreeFloating => default_origin,
+                };
+                for shape in layer.shapes.iter_mut() {
+                    match shape {
+                        Shape::Path(path) => {
+                            for node in path.nodes.iter_mut() {
+                                node.x = skew(node.x, node.y, origin);
+                            }
+                        }
+                        // The referenced glyph is slanted already, so the component's
+                        // transform is conjugated by the skew: undo it, apply the
+                        // original transform, and skew the result
+                        Shape::Component(component) => {
+                            let t = &component.transform;
+                            if t.scale == (1.0, 1.0) && t.rotation == 0.0 && t.skew == (0.0, 0.0)
+                            {
+                                component.transform.translation.0 +=
+                                    component.transform.translation.1 * tan;
+                            } else {
+                                let skew = Affine::new([1.0, 0.0, tan, 1.0, -origin * tan, 0.0]);
+                                let transform = skew * t.as_affine() * skew.inverse();
+                                component.transform = DecomposedAffine::from(transform);
+                            }
+                        }
+                    }
+                }
+                for anchor in layer.anchors.iter_mut() {
+                    anchor.x = skew(anchor.x, anchor.y, origin);
+                }
+                for guide in layer.guides.iter_mut() {
+                    skew_guide(guide, tan, origin);
+                }
+            }
+        }
+    }
+
+    /// Describe the font as an italic style
+    fn update_names(&self, font: &mut Font) {
+        let style = font
+            .names
+            .typographic_subfamily
+            .get_default()
+            .or(font.names.preferred_subfamily_name.get_default())
+            .map(|s| italic_style(s))
+            .unwrap_or_else(|| "Italic".to_string());
+        let family = font
+            .names
+            .typographic_family
+            .get_default()
+            .or(font.names.family_name.get_default())
+            .cloned();
+        if !font.names.typographic_subfamily.is_empty() {
+            font.names.typographic_subfamily = I18NDictionary::from(style.as_str());
+        }
+        let style_linked = font
+            .names
+            .preferred_subfamily_name
+            .get_default()
+            .map(|s| italic_style(s))
+            .unwrap_or_else(|| "Italic".to_string());
+        font.names.preferred_subfamily_name = I18NDictionary::from(style_linked);
+        if let Some(family) = family {
+            font.names.full_name = I18NDictionary::from(format!("{} {}", family, style));
+            font.names.postscript_name = I18NDictionary::from(format!(
+                "{}-{}",
+                family.replace(' ', ""),
+                style.replace(' ', "")
+            ));
+        }
+        let fs_selection = font.custom_ot_values.os2_fs_selection.unwrap_or(0);
+        font.custom_ot_values.os2_fs_selection =
+            Some((fs_selection | FS_SELECTION_ITALIC) & !FS_SELECTION_REGULAR);
+    }
+
+    fn add_slant_master(&self, font: &mut Font) -> Result<(), BabelfontError> {
+        if font.masters.len() != 1 {
+            return Err(BabelfontError::FilterError(
+                "Slanted masters can only be added to single-master fonts".into(),
+            ));
+        }
+        let slnt = Tag::new(b"slnt");
+        let mut slanted = font.clone();
+        slanted.source = None;
+        self.slant_outlines(&mut slanted);
+
+        if !font.axes.iter().any(|a| a.tag == slnt) {
+            let mut axis = crate::Axis::new("Slant", slnt);
+            axis.min = Some(crate::UserCoord::new(0.0));
+            axis.default = Some(crate::UserCoord::new(0.0));
+            axis.max = Some(crate::UserCoord::new(0.0));
+            font.axes.push(axis);
+            font.masters[0].location.insert(slnt, DesignCoord::new(0.0));
+        }
+        let upright = font.masters[0].clone();
+        let defaults: Vec<_> = font.axes.iter().map(|a| (a.tag, a.default)).collect();
+        // The slnt axis runs counter-clockwise, so a right lean is negative
+        let mut location = upright.location.clone();
+        location.insert(slnt, DesignCoord::new(-self.angle));
+        AddMaster::new(slanted, Some(location)).apply(font)?;
+
+        // Adding a master moves the default to it, but the upright stays the default
+        for (tag, default) in defaults {
+            if let Some(axis) = font.axes.iter_mut().find(|a| a.tag == tag) {
+                axis.default = default;
+            }
+        }
+        if let Some(master) = font.masters.last_mut() {
+            let name = upright
+                .name
+                .get_default()
+                .map(|s| italic_style(s))
+                .unwrap_or_else(|| "Italic".to_string());
+            master.name = I18NDictionary::from(name);
+        }
+        Ok(())
+    }
+}
+
+/// The style name of the italic counterpart of a style
+///
+/// Styles which are already italic or oblique are left alone.
+fn italic_style(style: &str) -> String {
+    let already_slanted = style.split_whitespace().any(|word| {
+        word.eq_ignore_ascii_case("Italic") || word.eq_ignore_ascii_case("Oblique")
+    });
+    match style.trim() {
+        "" | "Regular" => "Italic".to_string(),
+        style if already_slanted => style.to_string(),
+        style => format!("{} Italic", style),
+    }
+}
+
+fn skew_guide(guide: &mut Guide, tan: f64, origin: f64) {
+    let (x, y) = (guide.pos.x as f64, guide.pos.y as f64);
+    guide.pos.x = (x + (y - origin) * tan) as f32;
+    let angle = (guide.pos.angle as f64).to_radians();
+    let (dx, dy) = (angle.cos() + angle.sin() * tan, angle.sin());
+    guide.pos.angle = dy.atan2(dx).to_degrees() as f32;
+}
+
+impl FontFilter for Slant {
+    fn apply(&self, font: &mut Font) -> Result<(), BabelfontError> {
+        log::info!("Slanting font by {} degrees", self.angle);
+        if self.as_master {
+            return self.add_slant_master(font);
+        }
+        self.slant_outlines(font);
+        self.update_names(font);
+        Ok(())
+    }
+
+    fn from_str(s: &str) -> Result<Self, BabelfontError>
+    where
+        Self: Sized,
+    {
+        let mut parts = s.split(',').map(str::trim);
+        let angle = parts
+            .next()
+            .and_then(|a| a.parse::<f64>().ok())
+            .ok_or_else(|| BabelfontError::FilterError(format!("Invalid slant angle: {}", s)))?;
+        let mut filter = Slant::new(angle);
+        for part in parts {
+            filter = match part {
+                "master" => filter.as_master(true),
+                "origin=xheight" => filter.origin(SlantOrigin::HalfXHeight),
+                other => match other.strip_prefix("origin=").map(str::parse::<f64>) {
+                    Some(Ok(y)) => filter.origin(SlantOrigin::Height(y)),
+                    _ => {
+                        return Err(BabelfontError::FilterError(format!(
+                            "Invalid slant option: {}",
+                            other
+                        )))
+                    }
+                },
+            };
+        }
+        Ok(filter)
+    }
+
+    #[cfg(feature = "cli")]
+    fn arg() -> clap::Arg
+    where
+        Self: Sized,
+    {
+        clap::Arg::new("slant")
+            .long("slant")
+            .help("Slant outlines by an angle in degrees, optionally around 'origin=xheight' or 'origin=Y', or as a new slnt 'master'")
+            .value_name("ANGLE[,OPTIONS]")
+            .action(clap::ArgAction::Append)
+    }
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Anchor, Glyph, Layer, Master, Node, Path};
+
+    fn test_font() -> Font {
+        let mut font = Font::new();
+        font.names.family_name = "Test Sans".into();
+        font.names.preferred_subfamily_name = "Bold".into();
+        let mut master = Master::new("Bold", "m01", Default::default());
+        master.metrics.insert(MetricType::XHeight, 500);
+        font.masters.push(master);
+        let mut layer = Layer::new(500.0);
+        layer.master = LayerType::DefaultForMaster("m01".into());
+        layer.shapes.push(Shape::Path(Path {
+            nodes: vec![Node::new_line(0.0, 0.0), Node::new_line(0.0, 500.0)],
+            closed: false,
+            format_specific: Default::default(),
+        }));
+        layer.anchors.push(Anchor {
+            name: "top".into(),
+            x: 100.0,
+            y: 500.0,
+            ..Default::default()
+        });
+        font.glyphs.push(Glyph {
+            name: "l".into(),
+            layers: vec![layer],
+            ..Default::default()
+        });
+        font
+    }
+
+    #[test]
+    fn test_slant() {
+        let mut font = test_font();
+        Slant::new(45.0)
+            .origin(SlantOrigin::HalfXHeight)
+            .apply(&mut font)
+            .unwrap();
+        let layer = &font.glyphs.get("l").unwrap().layers[0];
+        let path = layer.shapes[0].as_path().unwrap();
+        assert!((path.nodes[0].x - -250.0).abs() < 1e-6);
+        assert!((path.nodes[1].x - 250.0).abs() < 1e-6);
+        assert!((layer.anchors[0].x - 350.0).abs() < 1e-6);
+        assert_eq!(font.masters[0].metrics[&MetricType::ItalicAngle], -45);
+        assert_eq!(
+            font.names.preferred_subfamily_name.get_default().unwrap(),
+            "Bold Italic"
+        );
+        assert_eq!(
+            font.names.postscript_name.get_default().unwrap(),
+            "TestSans-BoldItalic"
+        );
+        assert_eq!(
+            font.custom_ot_values.os2_fs_selection.unwrap() & FS_SELECTION_ITALIC,
+            FS_SELECTION_ITALIC
+        );
+    }
+
+    #[test]
+    fn test_slant_flipped_component() {
+        let mut font = test_font();
+        let mut layer = Layer::new(500.0);
+        layer.master = LayerType::DefaultForMaster("m01".into());
+        let mut component = crate::Component {
+            reference: "l".into(),
+            transform: Default::default(),
+            location: Default::default(),
+            format_specific: Default::default(),
+        };
+        component.transform.scale = (1.0, -1.0);
+        component.transform.translation = (50.0, 500.0);
+        let original = component.transform.as_affine();
+        layer.shapes.push(Shape::Component(component));
+        font.glyphs.push(Glyph {
+            name: "flipped".into(),
+            layers: vec![layer],
+            ..Default::default()
+        });
+        let unslanted: Vec<kurbo::Point> = [(0.0, 0.0), (0.0, 500.0)]
+            .into_iter()
+            .map(|(x, y)| original * kurbo::Point::new(x, y))
+            .collect();
+
+        let tan = 10.0_f64.to_radians().tan();
+        Slant::new(10.0).apply(&mut font).unwrap();
+        let base = font.glyphs.get("l").unwrap().layers[0].shapes[0]
+            .as_path()
+            .unwrap()
+            .clone();
+        let transform = match &font.glyphs.get("flipped").unwrap().layers[0].shapes[0] {
+            Shape::Component(component) => component.transform.as_affine(),
+            _ => unreachable!(),
+        };
+        // The slanted component matches the slanted, decomposed outline
+        for (node, expected) in base.nodes.iter().zip(unslanted) {
+            let actual = transform * kurbo::Point::new(node.x, node.y);
+            assert!((actual.x - (expected.x + expected.y * tan)).abs() < 1e-6);
+            assert!((actual.y - expected.y).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_slant_as_master() {
+        let mut font = test_font();
+        Slant::new(10.0).as_master(true).apply(&mut font).unwrap();
+        assert_eq!(font.masters.len(), 2);
+        let axis = font
+            .axes
+            .iter()
+            .find(|a| a.tag == Tag::new(b"slnt"))
+            .unwrap();
+        assert_eq!(axis.min, Some(crate::UserCoord::new(-10.0)));
+        assert_eq!(axis.default, Some(crate::UserCoord::new(0.0)));
+        let glyph = font.glyphs.get("l").unwrap();
+        assert_eq!(glyph.layers.len(), 2);
+        assert!(glyph.layers[1].shapes[0].as_path().unwrap().nodes[1].x > 0.0);
+        assert!(glyph.compatibility_errors().is_empty());
+    }
+
+    #[test]
+    fn test_italic_style() {
+        assert_eq!(italic_style("Regular"), "Italic");
+        assert_eq!(italic_style(""), "Italic");
+        assert_eq!(italic_style("Bold"), "Bold Italic");
+        assert_eq!(italic_style("Bold Italic"), "Bold Italic");
+        assert_eq!(italic_style("Light Oblique"), "Light Oblique");
+        assert_eq!(italic_style("Italic"), "Italic");
+    }
+}
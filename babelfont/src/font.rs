@@ -2,11 +2,12 @@ use crate::{
     axis::{Axis, CrossAxisMapping, Tag},
     common::{CustomOTValues, FormatSpecific},
     features::Features,
+    filters::{AxisLimit, FontFilter, InstantiateSubspace},
     glyph::GlyphList,
     instance::Instance,
     master::Master,
     names::Names,
-    BabelfontError, Layer, LayerType, MetricType,
+    BabelfontError, Diagnostics, Layer, LayerType, MetricType,
 };
 use fontdrasil::coords::{
     DesignCoord, DesignLocation, DesignSpace, Location, NormalizedLocation, NormalizedSpace,
@@ -15,18 +16,9 @@ use fontdrasil::coords::{
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 use smol_str::SmolStr;
-use std::{
-    collections::{BTreeMap, HashSet},
-    path::PathBuf,
-};
+use std::{collections::BTreeMap, path::PathBuf};
 use typeshare::typeshare;
 
-/// Per-master RTL kerning map: master_id → kern1 → kern2 → value.
-type RtlKerningMap = BTreeMap<String, BTreeMap<String, BTreeMap<String, f32>>>;
-
-#[cfg(feature = "cli")]
-extern crate serde_json_path_to_error as serde_json;
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[cfg_attr(feature = "reactive", derive(reactive_stores::Store))]
 #[typeshare]
@@ -109,6 +101,11 @@ pub struct Font {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     #[typeshare(serialized_as = "Option<String>")]
     pub source: Option<PathBuf>,
+
+    /// Problems noticed while loading the font which did not stop it loading
+    #[serde(skip)]
+    #[typeshare(skip)]
+    pub diagnostics: Diagnostics,
 }
 impl Default for Font {
     fn default() -> Self {
@@ -142,6 +139,7 @@ impl Font {
             features: Default::default(),
             format_specific: Default::default(),
             source: None,
+            diagnostics: Default::default(),
         }
     }
 
@@ -230,138 +228,6 @@ impl Font {
     //     self.axes.iter().map(|ax| ax.tag.clone()).collect()
     // }
 
-    /// Read the Glyphs-style RTL kerning dict from `format_specific`.
-    pub(crate) fn read_rtl_kerning(&self) -> Option<RtlKerningMap> {
-        self.format_specific
-            .get_parse_opt::<RtlKerningMap>(Self::KEY_KERNING_RTL)
-    }
-
-    fn strip_mmk_prefix(raw: &str, expected_side: &str) -> SmolStr {
-        let prefix = format!("@MMK_{}_", expected_side);
-        if let Some(stripped) = raw.strip_prefix(&prefix) {
-            SmolStr::from(format!("@{}", stripped))
-        } else {
-            SmolStr::from(raw)
-        }
-    }
-
-    /// Merge LTR and RTL kerning into one flat set of pairs for a master.
-    pub(crate) fn merged_kerning_for_master(
-        &self,
-        master: &Master,
-    ) -> Vec<((SmolStr, SmolStr), i16)> {
-        let mut result: IndexMap<(SmolStr, SmolStr), i16> = IndexMap::new();
-
-        for ((left, right), value) in &master.kerning {
-            result.insert((left.clone(), right.clone()), *value);
-        }
-
-        if let Some(rtl_by_master) = self.read_rtl_kerning() {
-            if let Some(rtl_dict) = rtl_by_master.get(&master.id.to_string()) {
-                for (kern1, subtable) in rtl_dict {
-                    let left = Self::strip_mmk_prefix(kern1, "R");
-                    for (kern2, value) in subtable {
-                        let right = Self::strip_mmk_prefix(kern2, "L");
-                        result.insert((left.clone(), right), *value as i16);
-                    }
-                }
-            }
-        }
-
-        result.into_iter().collect()
-    }
-
-    /// Identify glyphs that participate in RTL kerning.
-    pub(crate) fn rtl_kerning_glyphs(&self) -> HashSet<SmolStr> {
-        let mut rtl_glyphs: HashSet<SmolStr> = HashSet::new();
-
-        let Some(rtl_kerning) = self.read_rtl_kerning() else {
-            return rtl_glyphs;
-        };
-
-        let mut rtl_left_groups: HashSet<String> = HashSet::new();
-        let mut rtl_right_groups: HashSet<String> = HashSet::new();
-
-        for rtl_dict in rtl_kerning.values() {
-            for (kern1, subtable) in rtl_dict {
-                if let Some(stripped) = kern1.strip_prefix("@MMK_R_") {
-                    rtl_left_groups.insert(stripped.to_string());
-                } else {
-                    rtl_glyphs.insert(SmolStr::from(kern1.as_str()));
-                }
-                for kern2 in subtable.keys() {
-                    if let Some(stripped) = kern2.strip_prefix("@MMK_L_") {
-                        rtl_right_groups.insert(stripped.to_string());
-                    } else {
-                        rtl_glyphs.insert(SmolStr::from(kern2.as_str()));
-                    }
-                }
-            }
-        }
-
-        for glyph in self.glyphs.iter() {
-            if rtl_glyphs.contains(&glyph.name) {
-                continue;
-            }
-            let left_group = glyph.format_specific.get_string("kern_left");
-            let right_group = glyph.format_specific.get_string("kern_right");
-            if (!left_group.is_empty() && rtl_left_groups.contains(left_group.as_str()))
-                || (!right_group.is_empty() && rtl_right_groups.contains(right_group.as_str()))
-            {
-                rtl_glyphs.insert(glyph.name.clone());
-            }
-        }
-
-        rtl_glyphs
-    }
-
-    /// Apply the glyphsLib RTL group-side swap to the kern groups.
-    pub(crate) fn kern_groups_with_rtl_swaps(
-        &self,
-    ) -> (
-        IndexMap<SmolStr, Vec<SmolStr>>,
-        IndexMap<SmolStr, Vec<SmolStr>>,
-    ) {
-        let rtl_glyphs = self.rtl_kerning_glyphs();
-        if rtl_glyphs.is_empty() {
-            return (
-                self.first_kern_groups.clone(),
-                self.second_kern_groups.clone(),
-            );
-        }
-
-        let mut first = self.first_kern_groups.clone();
-        let mut second = self.second_kern_groups.clone();
-        for glyph_name in &rtl_glyphs {
-            for (_group, members) in first.iter_mut() {
-                members.retain(|member| member != glyph_name);
-            }
-            for (_group, members) in second.iter_mut() {
-                members.retain(|member| member != glyph_name);
-            }
-
-            if let Some(glyph) = self.glyphs.iter().find(|glyph| glyph.name == *glyph_name) {
-                let left_group = glyph.format_specific.get_string("kern_left");
-                let right_group = glyph.format_specific.get_string("kern_right");
-
-                if !right_group.is_empty() {
-                    first
-                        .entry(SmolStr::from(right_group.as_str()))
-                        .or_default()
-                        .push(glyph_name.clone());
-                }
-                if !left_group.is_empty() {
-                    second
-                        .entry(SmolStr::from(left_group.as_str()))
-                        .or_default()
-                        .push(glyph_name.clone());
-                }
-            }
-        }
-
-        (first, second)
-    }
-
     /// Save the font to a file
     ///
     /// Which file formats are supported will depend on which features are enabled:
@@ -369,63 +235,11 @@ impl Font {
     ///  - With the `ufo` feature, `.designspace` files and `.ufo` are also supported
     ///  - With the `glyphs` feature, `.glyphs` files are also supported
     ///  - With the `fontir` feature, `.ttf` files are also supported
+    ///
+    /// The file is written by the [crate::convertors::Convertor] registered for its
+    /// extension; further formats can be added with [crate::convertors::register].
     pub fn save<T: Into<std::path::PathBuf>>(&self, path: T) -> Result<(), BabelfontError> {
-        let path = path.into();
-        if path.extension().and_then(|x| x.to_str()) == Some("babelfont") {
-            let file = std::fs::File::create(&path)?;
-            let mut buffer = std::io::BufWriter::new(file);
-            serde_json::to_writer_pretty(&mut buffer, &self)?;
-            return Ok(());
-        }
-
-        #[cfg(feature = "fontir")]
-        {
-            if path.extension().and_then(|x| x.to_str()) == Some("ttf") {
-                use crate::convertors::fontir::CompilationOptions;
-
-                let bytes = crate::convertors::fontir::BabelfontIrSource::compile(
-                    self.clone(),
-                    CompilationOptions::default(),
-                )?;
-                std::fs::write(&path, bytes)?;
-                return Ok(());
-            }
-        }
-
-        #[cfg(feature = "glyphs")]
-        {
-            if path.extension().and_then(|x| x.to_str()) == Some("glyphs") {
-                let glyphs3_font = self.as_glyphslib()?;
-                return glyphs3_font
-                    .save(&path)
-                    .map_err(|x| BabelfontError::PlistParse(x.to_string()));
-            }
-            if path.extension().and_then(|x| x.to_str()) == Some("glyphspackage") {
-                let glyphs3_font = self.as_glyphslib()?;
-                return glyphs3_font
-                    .save(&path)
-                    .map_err(|x| BabelfontError::PlistParse(x.to_string()));
-            }
-        }
-        #[cfg(feature = "ufo")]
-        {
-            if path.extension().and_then(|x| x.to_str()) == Some("ufo") {
-                return crate::convertors::ufo::save_ufo(self, &path);
-            }
-            if path.extension().and_then(|x| x.to_str()) == Some("designspace") {
-                return crate::convertors::designspace::save_designspace(self, &path);
-            }
-        }
-        #[cfg(feature = "fontforge")]
-        {
-            if path.extension().and_then(|x| x.to_str()) == Some("sfd") {
-                return crate::convertors::fontforge::save_sfd(self, &path);
-            }
-        }
-
-        Err(BabelfontError::UnknownFileType {
-            path: path.to_path_buf(),
-        })
+        crate::convertors::registry().save(self, &path.into())
     }
 
     /// Interpolate a glyph at a given location in design space
@@ -499,6 +313,142 @@ impl Font {
             extrapolate,
         )
     }
+    /// Create a static, single-master font by interpolating this font at a location in design space
+    ///
+    /// Glyph layers (paths, components, anchors and widths), master metrics and
+    /// kerning are interpolated; kerning exceptions are resolved against group
+    /// kerning in masters which lack them. So are master guides and numeric custom
+    /// OpenType values, where every master has them. If an instance sits at the
+    /// location, the font is named after it.
+    pub fn instantiate(&self, location: &DesignLocation) -> Result<Font, BabelfontError> {
+        let mut full_location = self.default_location()?;
+        for (tag, coord) in location.iter() {
+            full_location.insert(*tag, *coord);
+        }
+        let limits =
+            self.axes
+                .iter()
+                .map(|axis| {
+                    let coord = full_location.get(axis.tag).ok_or_else(|| {
+                        BabelfontError::IllDefinedAxis {
+                            axis_name: axis.name(),
+                            reason: "No location given for axis".to_string(),
+                        }
+                    })?;
+                    Ok((
+                        axis.tag,
+                        AxisLimit::Pin(axis.designspace_to_userspace(coord)?),
+                    ))
+                })
+                .collect::<Result<Vec<_>, BabelfontError>>()?;
+        let mut font = self.clone();
+        InstantiateSubspace::new(limits).apply(&mut font)?;
+        // Only the master layers (and their backgrounds) make sense in a static font
+        for glyph in font.glyphs.iter_mut() {
+            glyph.layers.retain(|layer| {
+                layer.is_background || matches!(layer.master, LayerType::DefaultForMaster(_))
+            });
+        }
+
+        let instance = self.instances.iter().find(|instance| {
+            let mut instance_location = self
+                .default_location()
+                .unwrap_or_else(|_| DesignLocation::new());
+            for (tag, coord) in instance.location.iter() {
+                instance_location.insert(*tag, *coord);
+            }
+            instance_location == full_location
+        });
+        if let Some(instance) = instance {
+            font.name_after_instance(instance);
+            if let Some(master) = font.masters.first_mut() {
+                master.name = instance.name.clone();
+            }
+        }
+        font.instances.clear();
+        Ok(font)
+    }
+
+    /// Set all of the font's names for a static font made from an instance
+    ///
+    /// The family is the instance's family name, or else the font's, and the
+    /// style is the instance's style name, or else its name. Styles other than
+    /// Regular, Italic, Bold and Bold Italic are moved into the legacy family
+    /// name. Any other custom names set on the instance override those derived.
+    fn name_after_instance(&mut self, instance: &Instance) {
+        let custom = &instance.custom_names;
+        let family = custom
+            .family_name
+            .get_default()
+            .or(self.names.typographic_family.get_default())
+            .or(self.names.family_name.get_default())
+            .cloned()
+            .unwrap_or_else(|| "Untitled".to_string());
+        let style = custom
+            .preferred_subfamily_name
+            .get_default()
+            .or(custom.typographic_subfamily.get_default())
+            .cloned()
+            .or_else(|| {
+                instance.name.get_default().map(|name| {
+                    name.strip_prefix(&family)
+                        .map(|s| s.trim().to_string())
+                        .filter(|s| !s.is_empty())
+                        .unwrap_or_else(|| name.clone())
+                })
+            })
+            .unwrap_or_else(|| "Regular".to_string());
+
+        let is_italic = style
+            .split_whitespace()
+            .any(|word| word.eq_ignore_ascii_case("italic"));
+        let weight: Vec<&str> = style
+            .split_whitespace()
+            .filter(|word| !word.eq_ignore_ascii_case("italic"))
+            .collect();
+        let is_ribbi = weight.is_empty()
+            || (weight.len() == 1
+                && (weight[0].eq_ignore_ascii_case("regular")
+                    || weight[0].eq_ignore_ascii_case("bold")));
+        if is_ribbi {
+            self.names.family_name = family.as_str().into();
+            self.names.preferred_subfamily_name = style.as_str().into();
+            self.names.typographic_family = Default::default();
+            self.names.typographic_subfamily = Default::default();
+        } else {
+            self.names.family_name = format!("{} {}", family, weight.join(" ")).into();
+            self.names.preferred_subfamily_name =
+                if is_italic { "Italic" } else { "Regular" }.into();
+            self.names.typographic_family = family.as_str().into();
+            self.names.typographic_subfamily = style.as_str().into();
+        }
+        let postscript_name = format!("{}-{}", family.replace(' ', ""), style.replace(' ', ""));
+        self.names.full_name = format!("{} {}", family, style).into();
+        self.names.unique_id = format!(
+            "{}.{:03};{};{}",
+            self.version.0,
+            self.version.1,
+            self.custom_ot_values
+                .os2_vendor_id
+                .map(|tag| tag.to_string())
+                .unwrap_or_else(|| "NONE".to_string()),
+            postscript_name
+        )
+        .into();
+        self.names.postscript_name = postscript_name.into();
+        // These describe the variable font
+        self.names.compatible_full_name = Default::default();
+        self.names.wws_family_name = Default::default();
+        self.names.wws_subfamily_name = Default::default();
+        self.names.variations_postscript_name_prefix = Default::default();
+
+        let mut overrides = custom.clone();
+        overrides.family_name = Default::default();
+        overrides.preferred_subfamily_name = Default::default();
+        overrides.typographic_family = Default::default();
+        overrides.typographic_subfamily = Default::default();
+        self.names.update_from(&overrides);
+    }
 }
 
 #[cfg(feature = "glyphs")]
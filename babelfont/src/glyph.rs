@@ -2,25 +2,193 @@ use crate::{
     common::{Direction, FormatSpecific},
     layer::Layer,
     serde_helpers::{default_true, is_true},
-    Axis,
+    Axis, BabelfontError,
 };
 use serde::{Deserialize, Serialize};
 use smol_str::SmolStr;
-use std::ops::{Deref, DerefMut, Index, IndexMut};
+use std::{
+    collections::HashMap,
+    ops::{Deref, DerefMut, Index, IndexMut},
+    sync::OnceLock,
+};
 use typeshare::typeshare;
 
-#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+/// Positions of the glyphs in a [GlyphList], by name
+#[derive(Debug)]
+struct Positions {
+    /// Name → position of the first glyph with that name
+    first: HashMap<SmolStr, usize>,
+    /// Whether some name is shared by more than one glyph
+    duplicates: bool,
+}
+
+impl Positions {
+    fn new(glyphs: &[Glyph]) -> Self {
+        let mut first = HashMap::with_capacity(glyphs.len());
+        let mut duplicates = false;
+        for (ix, glyph) in glyphs.iter().enumerate() {
+            if first.contains_key(&glyph.name) {
+                duplicates = true;
+            } else {
+                first.insert(glyph.name.clone(), ix);
+            }
+        }
+        Positions { first, duplicates }
+    }
+
+    /// Whether the map still describes the glyphs, given that none were added,
+    /// removed or moved since it was built
+    fn still_valid(&self, glyphs: &[Glyph]) -> bool {
+        !self.duplicates
+            && self.first.len() == glyphs.len()
+            && glyphs
+                .iter()
+                .enumerate()
+                .all(|(ix, glyph)| self.first.get(&glyph.name) == Some(&ix))
+    }
+}
+
+/// Glyphs which may have been renamed since the last mutable call on a [GlyphList]
+#[derive(Debug, Default)]
+enum HandedOut {
+    #[default]
+    Nothing,
+    /// The position of a glyph handed out mutably, with its name at the time
+    Glyph(usize, SmolStr),
+    /// Every glyph, through [GlyphList::iter_mut]
+    All,
+}
+
+/// A map from glyph names to their positions in a [GlyphList]
+///
+/// The map is built on the first lookup and read without locking. Only
+/// methods which take the list mutably change it: adding, removing and
+/// renaming glyphs through [GlyphList] keeps it up to date, and any other
+/// change to the underlying `Vec` through [DerefMut] throws it away.
+///
+/// Glyphs handed out mutably could be renamed, so they are remembered until
+/// the next mutable call, which checks them and throws the map away only if
+/// one was renamed. Until then, lookups check hits against the glyph they
+/// point to, and look for misses among the glyphs handed out. So lookups are
+/// never wrong, and a loop which modifies glyphs as it looks others up stays
+/// linear.
+#[derive(Debug, Default)]
+struct NameIndex {
+    positions: OnceLock<Positions>,
+    handed_out: HandedOut,
+}
+
+impl NameIndex {
+    fn lookup(&self, glyphs: &[Glyph], name: &str) -> Option<usize> {
+        let positions = self.positions.get_or_init(|| Positions::new(glyphs));
+        let hit = positions.first.get(name).copied();
+        if let Some(ix) = hit {
+            if glyphs.get(ix).is_some_and(|g| g.name == name) {
+                return Some(ix);
+            }
+        }
+        match &self.handed_out {
+            HandedOut::Nothing => None,
+            // A renamed glyph may have left a duplicate which isn't in the map
+            HandedOut::Glyph(..) if hit.is_some() && positions.duplicates => {
+                glyphs.iter().position(|g| g.name == name)
+            }
+            HandedOut::Glyph(ix, _) => Some(*ix).filter(|&ix| glyphs[ix].name == name),
+            HandedOut::All => glyphs.iter().position(|g| g.name == name),
+        }
+    }
+
+    /// Check the glyphs handed out since the last mutable call, throwing the
+    /// map away if any of them were renamed
+    fn settle(&mut self, glyphs: &[Glyph]) {
+        let valid = match std::mem::take(&mut self.handed_out) {
+            HandedOut::Nothing => true,
+            HandedOut::Glyph(ix, name) => glyphs.get(ix).is_some_and(|g| g.name == name),
+            HandedOut::All => self
+                .positions
+                .get()
+                .is_some_and(|positions| positions.still_valid(glyphs)),
+        };
+        if !valid {
+            self.positions = OnceLock::new();
+        }
+    }
+
+    /// Remember that a glyph is being handed out mutably
+    fn hand_out(&mut self, glyphs: &[Glyph], ix: usize) {
+        self.settle(glyphs);
+        if self.positions.get().is_none() {
+            return;
+        }
+        if let Some(glyph) = glyphs.get(ix) {
+            self.handed_out = HandedOut::Glyph(ix, glyph.name.clone());
+        }
+    }
+
+    /// Remember that every glyph is being handed out mutably
+    fn hand_out_all(&mut self, glyphs: &[Glyph]) {
+        self.settle(glyphs);
+        if self.positions.get().is_some() {
+            self.handed_out = HandedOut::All;
+        }
+    }
+
+    fn invalidate(&mut self) {
+        self.positions = OnceLock::new();
+        self.handed_out = HandedOut::Nothing;
+    }
+}
+
+impl Clone for NameIndex {
+    fn clone(&self) -> Self {
+        // Cheaper to rebuild on demand than to copy a map which may not be needed
+        NameIndex::default()
+    }
+}
+
+impl PartialEq for NameIndex {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
 #[cfg_attr(feature = "reactive", derive(reactive_stores::Store))]
 /// A list of glyphs in the font
-pub struct GlyphList(pub Vec<Glyph>);
+///
+/// The list keeps an index of glyph names, so looking glyphs up by name is
+/// cheap. Use [GlyphList::push], [GlyphList::remove] and [GlyphList::rename]
+/// to keep the index up to date as you go; glyphs can be changed, and even
+/// renamed, through [GlyphList::get_mut] and [GlyphList::iter_mut]. Any other
+/// change made through the underlying `Vec` (inserting, sorting,
+/// retaining...) causes the index to be rebuilt on the next lookup.
+///
+/// The `Vec` is reached through [Deref] and [DerefMut], or
+/// [GlyphList::as_vec] and [GlyphList::as_vec_mut].
+pub struct GlyphList(
+    Vec<Glyph>,
+    #[cfg_attr(feature = "reactive", store(skip))] NameIndex,
+);
 impl GlyphList {
+    /// Find the position of a glyph in the list by name
+    pub fn position(&self, g: &str) -> Option<usize> {
+        self.1.lookup(&self.0, g)
+    }
+
+    /// Whether the list contains a glyph with the given name
+    pub fn contains(&self, g: &str) -> bool {
+        self.position(g).is_some()
+    }
+
     /// Get a glyph by name
     pub fn get(&self, g: &str) -> Option<&Glyph> {
-        self.0.iter().find(|&glyph| glyph.name == g)
+        self.position(g).and_then(|ix| self.0.get(ix))
     }
     /// Get a glyph by name, mutably
     pub fn get_mut(&mut self, g: &str) -> Option<&mut Glyph> {
-        self.0.iter_mut().find(|glyph| glyph.name == g)
+        let ix = self.position(g)?;
+        self.1.hand_out(&self.0, ix);
+        self.0.get_mut(ix)
     }
 
     /// Get a glyph by index
@@ -29,12 +197,124 @@ impl GlyphList {
     }
     /// Get a glyph by index, mutably
     pub fn get_by_index_mut(&mut self, id: usize) -> Option<&mut Glyph> {
+        self.1.hand_out(&self.0, id);
         self.0.get_mut(id)
     }
     /// Get an iterator over the glyphs
     pub fn iter(&self) -> std::slice::Iter<'_, Glyph> {
         self.0.iter()
     }
+    /// Get an iterator over the glyphs, mutably
+    ///
+    /// Unlike changes made through [DerefMut], this keeps the index unless
+    /// a glyph is renamed.
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, Glyph> {
+        self.1.hand_out_all(&self.0);
+        self.0.iter_mut()
+    }
+
+    /// The underlying list of glyphs
+    pub fn as_vec(&self) -> &Vec<Glyph> {
+        &self.0
+    }
+    /// The underlying list of glyphs, mutably
+    ///
+    /// The index is rebuilt on the next lookup.
+    pub fn as_vec_mut(&mut self) -> &mut Vec<Glyph> {
+        self.1.invalidate();
+        &mut self.0
+    }
+
+    /// Add a glyph to the end of the list
+    pub fn push(&mut self, glyph: Glyph) {
+        self.1.settle(&self.0);
+        let ix = self.0.len();
+        if let Some(positions) = self.1.positions.get_mut() {
+            if positions.first.contains_key(&glyph.name) {
+                positions.duplicates = true;
+            } else {
+                positions.first.insert(glyph.name.clone(), ix);
+            }
+        }
+        self.0.push(glyph);
+    }
+
+    /// Remove a glyph from the list by name, returning it
+    pub fn remove(&mut self, g: &str) -> Option<Glyph> {
+        let ix = self.position(g)?;
+        self.1.invalidate();
+        Some(self.0.remove(ix))
+    }
+
+    /// Rename a glyph
+    ///
+    /// Only the glyph itself is renamed; use the
+    /// [RenameGlyphs](crate::filters::RenameGlyphs) filter to also update
+    /// components, kerning and features which refer to it.
+    pub fn rename(&mut self, old: &str, new: &str) -> Result<(), BabelfontError> {
+        let ix = self
+            .position(old)
+            .ok_or_else(|| BabelfontError::GlyphNotFound {
+                glyph: old.to_string(),
+            })?;
+        if self.contains(new) {
+            return Err(BabelfontError::General(format!(
+                "Cannot rename {} to {}: a glyph called {} already exists",
+                old, new, new
+            )));
+        }
+        self.1.settle(&self.0);
+        self.0[ix].name = new.into();
+        if let Some(positions) = self.1.positions.get_mut() {
+            if positions.duplicates {
+                // Another glyph may share the old name, and is missing from the map
+                self.1.positions = OnceLock::new();
+            } else {
+                positions.first.remove(old);
+                positions.first.insert(new.into(), ix);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl From<Vec<Glyph>> for GlyphList {
+    fn from(glyphs: Vec<Glyph>) -> Self {
+        GlyphList(glyphs, NameIndex::default())
+    }
+}
+
+impl From<GlyphList> for Vec<Glyph> {
+    fn from(glyphs: GlyphList) -> Self {
+        glyphs.0
+    }
+}
+
+impl FromIterator<Glyph> for GlyphList {
+    fn from_iter<T: IntoIterator<Item = Glyph>>(iter: T) -> Self {
+        GlyphList::from(iter.into_iter().collect::<Vec<_>>())
+    }
+}
+
+impl IntoIterator for GlyphList {
+    type Item = Glyph;
+    type IntoIter = std::vec::IntoIter<Glyph>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl Serialize for GlyphList {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for GlyphList {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::<Glyph>::deserialize(deserializer).map(GlyphList::from)
+    }
 }
 
 impl Deref for GlyphList {
@@ -45,6 +325,7 @@ impl Deref for GlyphList {
 }
 impl DerefMut for GlyphList {
     fn deref_mut(&mut self) -> &mut Self::Target {
+        self.1.invalidate();
         &mut self.0
     }
 }
@@ -58,6 +339,7 @@ impl Index<usize> for GlyphList {
 
 impl IndexMut<usize> for GlyphList {
     fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        self.1.hand_out(&self.0, index);
         &mut self.0[index]
     }
 }
@@ -171,7 +453,7 @@ pub(crate) mod glyphs {
     use crate::{
         convertors::glyphs3::{copy_user_data, UserData, KEY_USER_DATA},
         layer::glyphs::{layer_from_glyphs, layer_to_glyphs},
-        BabelfontError,
+        BabelfontError, Diagnostic, Diagnostics,
     };
 
     use super::*;
@@ -182,7 +464,11 @@ pub(crate) mod glyphs {
     use glyphslib::glyphs3::Glyph as G3Glyph;
     use indexmap::IndexMap;
 
-    pub(crate) fn from_glyphs(val: &G3Glyph, axes_order: &[Tag]) -> Result<Glyph, BabelfontError> {
+    pub(crate) fn from_glyphs(
+        val: &G3Glyph,
+        axes_order: &[Tag],
+        diagnostics: &mut Diagnostics,
+    ) -> Result<Glyph, BabelfontError> {
         let mut format_specific = FormatSpecific::default();
         format_specific.insert_json_non_null("case", &val.case);
         format_specific.insert_json_non_null("color", &val.color);
@@ -230,9 +516,16 @@ pub(crate) mod glyphs {
         // Now pre-chew them for easy layer generation
         let sc_axes = glyph_specific_axes(&component_axes);
         for layer in &val.layers {
-            let mut bf_layer = layer_from_glyphs(layer, axes_order, &sc_axes)?;
+            let mut bf_layer =
+                layer_from_glyphs(layer, axes_order, &sc_axes, &val.name, diagnostics)?;
             if let Some(bg_layer) = &layer.background {
-                let mut background = layer_from_glyphs(bg_layer.deref(), axes_order, &sc_axes)?;
+                let mut background = layer_from_glyphs(
+                    bg_layer.deref(),
+                    axes_order,
+                    &sc_axes,
+                    &val.name,
+                    diagnostics,
+                )?;
                 background.is_background = true;
                 if background.id.is_none() || background.id == Some("".to_string()) {
                     background.id =
@@ -256,6 +549,22 @@ pub(crate) mod glyphs {
             })
             .collect::<Vec<_>>();
         fixup_axis_definitions(&mut component_axes, &sc_locations);
+        let direction = val.direction.as_ref().and_then(|d| {
+            if d.is_empty() {
+                None
+            } else {
+                Some(Direction::from_str(d).unwrap_or_else(|_| {
+                    diagnostics.push(
+                        Diagnostic::warning(
+                            "glyphs.invalid-direction",
+                            format!("Unknown writing direction '{}', assuming LTR", d),
+                        )
+                        .glyph(val.name.as_str()),
+                    );
+                    Direction::LeftToRight
+                }))
+            }
+        });
 
         Ok(Glyph {
             name: SmolStr::from(&val.name),
@@ -264,13 +573,7 @@ pub(crate) mod glyphs {
             codepoints: val.unicode.clone(),
             layers,
             exported: val.export,
-            direction: val.direction.as_ref().and_then(|d| {
-                if d.is_empty() {
-                    None
-                } else {
-                    Some(Direction::from_str(d).unwrap_or(Direction::LeftToRight))
-                }
-            }),
+            direction,
             component_axes,
             format_specific,
         })
@@ -423,3 +726,93 @@ pub(crate) mod glyphs {
         }
     }
 }
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(glyphs: &GlyphList) -> Vec<&str> {
+        glyphs.iter().map(|g| g.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_glyph_list_index() {
+        let mut glyphs: GlyphList = ["A", "B", "C"].into_iter().map(Glyph::new).collect();
+        assert_eq!(glyphs.position("B"), Some(1));
+        glyphs.push(Glyph::new("D"));
+        assert_eq!(glyphs.position("D"), Some(3));
+
+        glyphs.rename("B", "beta").unwrap();
+        assert!(glyphs.get("B").is_none());
+        assert_eq!(glyphs.position("beta"), Some(1));
+        assert!(glyphs.rename("beta", "A").is_err());
+
+        // Changes made behind the index's back are still picked up
+        glyphs.get_mut("C").unwrap().name = "gamma".into();
+        assert_eq!(glyphs.position("gamma"), Some(2));
+        glyphs.reverse();
+        assert_eq!(names(&glyphs), vec!["D", "gamma", "beta", "A"]);
+        assert_eq!(glyphs.position("A"), Some(3));
+
+        assert_eq!(glyphs.remove("gamma").unwrap().name, "gamma");
+        assert_eq!(glyphs.position("beta"), Some(1));
+        assert!(!glyphs.contains("gamma"));
+
+        let json = serde_json::to_string(&glyphs).unwrap();
+        assert!(json.starts_with("[{\"name\":\"D\""));
+        let glyphs: GlyphList = serde_json::from_str(&json).unwrap();
+        assert_eq!(glyphs.position("A"), Some(2));
+    }
+
+    #[test]
+    fn test_glyph_list_misses_after_get_mut() {
+        let mut glyphs: GlyphList = ["A", "B", "C"].into_iter().map(Glyph::new).collect();
+        assert!(glyphs.contains("A"));
+        glyphs.get_mut("A").unwrap().exported = false;
+        // A miss only checks the glyph which was handed out
+        assert!(!glyphs.contains("Z"));
+        assert!(matches!(glyphs.1.handed_out, HandedOut::Glyph(..)));
+        glyphs.get_mut("B").unwrap().name = "beta".into();
+        assert_eq!(glyphs.position("beta"), Some(1));
+        assert!(!glyphs.contains("B"));
+        // Checking the handed out glyphs keeps the map if they weren't renamed
+        glyphs.get_mut("C").unwrap().exported = false;
+        assert!(glyphs.1.positions.get().is_none());
+        glyphs.get_mut("C").unwrap().exported = true;
+        assert!(glyphs.1.positions.get().is_some());
+    }
+
+    #[test]
+    fn test_glyph_list_iter_mut() {
+        let mut glyphs: GlyphList = ["A", "B", "C"].into_iter().map(Glyph::new).collect();
+        assert!(glyphs.contains("A"));
+        for glyph in glyphs.iter_mut() {
+            glyph.exported = false;
+        }
+        glyphs.push(Glyph::new("D"));
+        // Nothing was renamed, so the map was kept
+        assert!(glyphs.1.positions.get().is_some());
+        assert_eq!(glyphs.position("D"), Some(3));
+
+        for glyph in glyphs.iter_mut() {
+            if glyph.name == "B" {
+                glyph.name = "beta".into();
+            }
+        }
+        assert_eq!(glyphs.position("beta"), Some(1));
+        assert!(!glyphs.contains("B"));
+        glyphs.push(Glyph::new("E"));
+        assert_eq!(glyphs.position("beta"), Some(1));
+        assert_eq!(glyphs.position("E"), Some(4));
+    }
+
+    #[cfg(feature = "reactive")]
+    #[test]
+    fn test_glyph_list_store() {
+        // The name index is skipped by the store, so the list can still go in one
+        let glyphs: GlyphList = ["A", "B"].into_iter().map(Glyph::new).collect();
+        let _store = reactive_stores::Store::new(glyphs);
+        let _store = reactive_stores::Store::new(crate::Font::new());
+    }
+}
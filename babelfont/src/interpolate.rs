@@ -225,7 +225,7 @@ impl Shape {
 }
 
 impl Path {
-    fn signature(&self) -> Vec<u8> {
+    pub(crate) fn signature(&self) -> Vec<u8> {
         let mut sig = vec![];
         for node in &self.nodes {
             sig.push(match node.nodetype {
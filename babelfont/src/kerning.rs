@@ -0,0 +1,662 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::Display,
+};
+
+use fontdrasil::{
+    coords::{DesignLocation, NormalizedLocation},
+    variations::VariationModel,
+};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use smol_str::SmolStr;
+
+use crate::{BabelfontError, Font, Master};
+
+/// Per-master RTL kerning map: master_id → kern1 → kern2 → value.
+type RtlKerningMap = BTreeMap<String, BTreeMap<String, BTreeMap<String, f32>>>;
+
+/// Which side of a kerning pair a kern group applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KernSide {
+    /// The first glyph of a pair (the left glyph in LTR text)
+    First,
+    /// The second glyph of a pair (the right glyph in LTR text)
+    Second,
+}
+
+impl Display for KernSide {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KernSide::First => write!(f, "first"),
+            KernSide::Second => write!(f, "second"),
+        }
+    }
+}
+
+/// Look up a pair, trying the glyphs' kern groups where the exact pair is not
+/// kerned
+///
+/// Exceptions take precedence over group kerning: glyph–glyph is tried first,
+/// then glyph–group, group–glyph and finally group–group.
+pub(crate) fn resolve<'a>(
+    pairs: &'a IndexMap<(SmolStr, SmolStr), i16>,
+    left: &str,
+    left_groups: &[SmolStr],
+    right: &str,
+    right_groups: &[SmolStr],
+) -> Option<(&'a (SmolStr, SmolStr), i16)> {
+    let sides = |name: &str, groups: &[SmolStr]| {
+        let mut sides = vec![SmolStr::from(name)];
+        if !name.starts_with('@') {
+            sides.extend(
+                groups
+                    .iter()
+                    .map(|group| SmolStr::from(format!("@{}", group))),
+            );
+        }
+        sides
+    };
+    let lefts = sides(left, left_groups);
+    let rights = sides(right, right_groups);
+    lefts
+        .iter()
+        .flat_map(|l| rights.iter().map(move |r| (l.clone(), r.clone())))
+        .find_map(|pair| {
+            pairs
+                .get_key_value(&pair)
+                .map(|(pair, value)| (pair, *value))
+        })
+}
+
+/// The kerning of one master, with LTR and RTL pairs merged and glyphs
+/// resolved to their kern groups
+///
+/// Build this with [Font::master_kerning] when making many queries against
+/// the same master.
+#[derive(Debug, Clone, Default)]
+pub struct MasterKerning {
+    pairs: IndexMap<(SmolStr, SmolStr), i16>,
+    first_groups: HashMap<SmolStr, Vec<SmolStr>>,
+    second_groups: HashMap<SmolStr, Vec<SmolStr>>,
+}
+
+impl MasterKerning {
+    /// The kern groups a glyph belongs to on one side of a pair
+    ///
+    /// Group names are not prefixed with `@`.
+    pub fn groups_of(&self, glyph: &str, side: KernSide) -> &[SmolStr] {
+        let groups = match side {
+            KernSide::First => &self.first_groups,
+            KernSide::Second => &self.second_groups,
+        };
+        groups.get(glyph).map_or(&[], |groups| groups.as_slice())
+    }
+
+    /// The kerned pair which supplies the kerning between two glyphs, and its value
+    ///
+    /// Each side may be a glyph name or a `@group`.
+    pub fn pair_for(&self, left: &str, right: &str) -> Option<(&(SmolStr, SmolStr), i16)> {
+        resolve(
+            &self.pairs,
+            left,
+            self.groups_of(left, KernSide::First),
+            right,
+            self.groups_of(right, KernSide::Second),
+        )
+    }
+
+    /// The kerning between two glyphs (or `@group`s); unkerned pairs are zero
+    pub fn value(&self, left: &str, right: &str) -> i16 {
+        self.pair_for(left, right).map_or(0, |(_, value)| value)
+    }
+
+    /// All kerned pairs
+    pub fn pairs(&self) -> impl Iterator<Item = (&(SmolStr, SmolStr), i16)> {
+        self.pairs.iter().map(|(pair, value)| (pair, *value))
+    }
+}
+
+impl Font {
+    /// The kerning of a master, ready to be queried
+    ///
+    /// RTL kerning is merged in and its kern groups swapped as described in
+    /// [Font::kern_groups_with_rtl_swaps].
+    pub fn master_kerning(&self, master: &Master) -> MasterKerning {
+        let (first, second) = self.kern_groups_with_rtl_swaps();
+        let by_glyph = |groups: IndexMap<SmolStr, Vec<SmolStr>>| {
+            let mut by_glyph: HashMap<SmolStr, Vec<SmolStr>> = HashMap::new();
+            for (group, members) in groups {
+                for member in members {
+                    by_glyph.entry(member).or_default().push(group.clone());
+                }
+            }
+            by_glyph
+        };
+        MasterKerning {
+            pairs: self.merged_kerning_for_master(master).into_iter().collect(),
+            first_groups: by_glyph(first),
+            second_groups: by_glyph(second),
+        }
+    }
+
+    /// The kerning between two glyphs (or `@group`s) in a master
+    ///
+    /// Group kerning and RTL kerning are taken into account; unkerned pairs
+    /// are zero.
+    pub fn kerning(&self, master: &Master, left: &str, right: &str) -> i16 {
+        self.master_kerning(master).value(left, right)
+    }
+
+    /// The kerning between two glyphs (or `@group`s) at a location in the designspace
+    ///
+    /// The resolved kerning of each (non-sparse) master is interpolated, so a
+    /// master which only kerns the pair through its groups still contributes.
+    pub fn kerning_at(
+        &self,
+        location: &DesignLocation,
+        left: &str,
+        right: &str,
+    ) -> Result<f64, BabelfontError> {
+        let axes = self.fontdrasil_axes()?;
+        let mut values: HashMap<NormalizedLocation, Vec<f64>> = HashMap::new();
+        for master in self.masters.iter().filter(|m| !m.is_sparse(self)) {
+            values.insert(
+                master.location.to_normalized(&axes)?,
+                vec![self.kerning(master, left, right) as f64],
+            );
+        }
+        if values.is_empty() {
+            return Ok(0.0);
+        }
+        let model = VariationModel::new(values.keys().cloned().collect(), axes.axis_order());
+        let deltas = model.deltas(&values)?;
+        Ok(model
+            .interpolate_from_deltas(&location.to_normalized(&axes)?, &deltas)
+            .first()
+            .copied()
+            .unwrap_or(0.0))
+    }
+
+    /// The kern groups for one side of a pair
+    pub fn kern_groups(&self, side: KernSide) -> &IndexMap<SmolStr, Vec<SmolStr>> {
+        match side {
+            KernSide::First => &self.first_kern_groups,
+            KernSide::Second => &self.second_kern_groups,
+        }
+    }
+
+    fn kern_groups_mut(&mut self, side: KernSide) -> &mut IndexMap<SmolStr, Vec<SmolStr>> {
+        match side {
+            KernSide::First => &mut self.first_kern_groups,
+            KernSide::Second => &mut self.second_kern_groups,
+        }
+    }
+
+    /// The kern group a glyph belongs to on one side of a pair, if any
+    ///
+    /// This looks at the kern groups as stored, before any RTL swapping.
+    pub fn kern_group_of(&self, glyph: &str, side: KernSide) -> Option<&SmolStr> {
+        self.kern_groups(side)
+            .iter()
+            .find(|(_, members)| members.iter().any(|m| m == glyph))
+            .map(|(group, _)| group)
+    }
+
+    /// Set the kerning for a pair in a master
+    ///
+    /// Each side may be a glyph name or a `@group`; groups must exist on the
+    /// appropriate side.
+    pub fn set_kerning(
+        &mut self,
+        master_id: &str,
+        left: &str,
+        right: &str,
+        value: i16,
+    ) -> Result<(), BabelfontError> {
+        for (name, side) in [(left, KernSide::First), (right, KernSide::Second)] {
+            if let Some(group) = name.strip_prefix('@') {
+                if !self.kern_groups(side).contains_key(group) {
+                    return Err(BabelfontError::KernGroupNotFound {
+                        group: group.to_string(),
+                        side,
+                    });
+                }
+            }
+        }
+        let master = self
+            .masters
+            .iter_mut()
+            .find(|m| m.id == master_id)
+            .ok_or_else(|| BabelfontError::MasterNotFound(master_id.to_string()))?;
+        master.kerning.insert((left.into(), right.into()), value);
+        Ok(())
+    }
+
+    /// Put a glyph into a kern group, creating the group if needed
+    ///
+    /// A glyph belongs to at most one group on each side, so it is taken out
+    /// of any other group on that side.
+    pub fn add_to_kern_group(&mut self, glyph: &str, group: &str, side: KernSide) {
+        let groups = self.kern_groups_mut(side);
+        for (name, members) in groups.iter_mut() {
+            if name != group {
+                members.retain(|m| m != glyph);
+            }
+        }
+        let members = groups.entry(group.into()).or_default();
+        if !members.iter().any(|m| m == glyph) {
+            members.push(glyph.into());
+        }
+    }
+
+    /// Rename a kern group, updating the kerning pairs of every master which use it
+    ///
+    /// Glyphs-style RTL kerning is updated too. It is an error to rename a
+    /// group to the name of another group on the same side.
+    pub fn rename_kern_group(
+        &mut self,
+        side: KernSide,
+        old: &str,
+        new: &str,
+    ) -> Result<(), BabelfontError> {
+        let groups = self.kern_groups_mut(side);
+        let index = groups
+            .get_index_of(old)
+            .ok_or_else(|| BabelfontError::KernGroupNotFound {
+                group: old.to_string(),
+                side,
+            })?;
+        if old == new {
+            return Ok(());
+        }
+        if groups.contains_key(new) {
+            return Err(BabelfontError::KernGroupExists {
+                group: new.to_string(),
+                side,
+            });
+        }
+        let Some((_, members)) = groups.shift_remove_index(index) else {
+            return Ok(());
+        };
+        groups.shift_insert(index, new.into(), members);
+        let (old, new) = (format!("@{}", old), SmolStr::from(format!("@{}", new)));
+        let rename = |(left, right): (SmolStr, SmolStr)| match side {
+            KernSide::First if left == old => (new.clone(), right),
+            KernSide::Second if right == old => (left, new.clone()),
+            _ => (left, right),
+        };
+        for master in self.masters.iter_mut() {
+            master.kerning = std::mem::take(&mut master.kerning)
+                .into_iter()
+                .map(|(pair, value)| (rename(pair), value))
+                .collect();
+        }
+        self.update_rtl_kerning(|pair| Some(rename(pair)));
+        Ok(())
+    }
+
+    /// Remove a kern group, and the kerning pairs of every master which use it
+    ///
+    /// Glyphs-style RTL pairs using the group are removed too. Returns the
+    /// members of the group, if it existed.
+    pub fn remove_kern_group(&mut self, side: KernSide, group: &str) -> Option<Vec<SmolStr>> {
+        let members = self.kern_groups_mut(side).shift_remove(group)?;
+        let group = format!("@{}", group);
+        let keep = |(left, right): &(SmolStr, SmolStr)| match side {
+            KernSide::First => *left != group,
+            KernSide::Second => *right != group,
+        };
+        for master in self.masters.iter_mut() {
+            master.kerning.retain(|pair, _| keep(pair));
+        }
+        self.update_rtl_kerning(|pair| keep(&pair).then_some(pair));
+        Some(members)
+    }
+
+    /// Take a glyph out of all kern groups and remove its kerning exceptions
+    ///
+    /// This includes its exceptions in Glyphs-style RTL kerning.
+    pub fn remove_glyph_from_kerning(&mut self, glyph: &str) {
+        for members in self
+            .first_kern_groups
+            .values_mut()
+            .chain(self.second_kern_groups.values_mut())
+        {
+            members.retain(|m| m != glyph);
+        }
+        let keep = |(left, right): &(SmolStr, SmolStr)| left != glyph && right != glyph;
+        for master in self.masters.iter_mut() {
+            master.kerning.retain(|pair, _| keep(pair));
+        }
+        self.update_rtl_kerning(|pair| keep(&pair).then_some(pair));
+    }
+
+    /// Rewrite or drop the pairs of every master's Glyphs-style RTL kerning
+    ///
+    /// Pairs are given to `update` with plain `@group` names, as returned by
+    /// [Font::merged_kerning_for_master].
+    fn update_rtl_kerning(
+        &mut self,
+        update: impl Fn((SmolStr, SmolStr)) -> Option<(SmolStr, SmolStr)>,
+    ) {
+        let Some(rtl_kerning) = self.read_rtl_kerning() else {
+            return;
+        };
+        for (master_id, rtl_dict) in rtl_kerning {
+            let mut pairs = vec![];
+            for (kern1, subtable) in &rtl_dict {
+                let left = Self::strip_mmk_prefix(kern1, "R");
+                for (kern2, value) in subtable {
+                    let right = Self::strip_mmk_prefix(kern2, "L");
+                    if let Some(pair) = update((left.clone(), right)) {
+                        pairs.push((pair, *value as i16));
+                    }
+                }
+            }
+            self.set_rtl_kerning(&master_id, pairs);
+        }
+    }
+
+    /// Read the Glyphs-style RTL kerning dict from `format_specific`.
+    pub(crate) fn read_rtl_kerning(&self) -> Option<RtlKerningMap> {
+        self.format_specific
+            .get_parse_opt::<RtlKerningMap>(Self::KEY_KERNING_RTL)
+    }
+
+    /// Set a master's Glyphs-style RTL kerning in `format_specific`
+    ///
+    /// Pairs use plain `@group` names, as returned by
+    /// [Font::merged_kerning_for_master]; they are stored with the
+    /// `@MMK_R_`/`@MMK_L_` prefixes Glyphs uses.
+    pub(crate) fn set_rtl_kerning(
+        &mut self,
+        master_id: &str,
+        pairs: impl IntoIterator<Item = ((SmolStr, SmolStr), i16)>,
+    ) {
+        let mut rtl_kerning = self.read_rtl_kerning().unwrap_or_default();
+        let mut rtl_dict: BTreeMap<String, BTreeMap<String, f32>> = BTreeMap::new();
+        for ((left, right), value) in pairs {
+            let add_prefix = |name: &str, side: &str| match name.strip_prefix('@') {
+                Some(group) => format!("@MMK_{}_{}", side, group),
+                None => name.to_string(),
+            };
+            rtl_dict
+                .entry(add_prefix(&left, "R"))
+                .or_default()
+                .insert(add_prefix(&right, "L"), value as f32);
+        }
+        if rtl_dict.is_empty() {
+            rtl_kerning.remove(master_id);
+        } else {
+            rtl_kerning.insert(master_id.to_string(), rtl_dict);
+        }
+        if rtl_kerning.is_empty() {
+            self.format_specific.remove(Self::KEY_KERNING_RTL);
+        } else {
+            self.format_specific
+                .insert_json(Self::KEY_KERNING_RTL, &rtl_kerning);
+        }
+    }
+
+    fn strip_mmk_prefix(raw: &str, expected_side: &str) -> SmolStr {
+        let prefix = format!("@MMK_{}_", expected_side);
+        if let Some(stripped) = raw.strip_prefix(&prefix) {
+            SmolStr::from(format!("@{}", stripped))
+        } else {
+            SmolStr::from(raw)
+        }
+    }
+
+    /// Merge LTR and RTL kerning into one flat set of pairs for a master.
+    ///
+    /// Glyphs stores right-to-left kerning separately from the master's
+    /// kerning, with `@MMK_R_`/`@MMK_L_` group prefixes; these pairs are
+    /// rewritten to use plain `@group` names.
+    pub fn merged_kerning_for_master(&self, master: &Master) -> Vec<((SmolStr, SmolStr), i16)> {
+        let mut result: IndexMap<(SmolStr, SmolStr), i16> = IndexMap::new();
+
+        for ((left, right), value) in &master.kerning {
+            result.insert((left.clone(), right.clone()), *value);
+        }
+
+        if let Some(rtl_by_master) = self.read_rtl_kerning() {
+            if let Some(rtl_dict) = rtl_by_master.get(&master.id.to_string()) {
+                for (kern1, subtable) in rtl_dict {
+                    let left = Self::strip_mmk_prefix(kern1, "R");
+                    for (kern2, value) in subtable {
+                        let right = Self::strip_mmk_prefix(kern2, "L");
+                        result.insert((left.clone(), right), *value as i16);
+                    }
+                }
+            }
+        }
+
+        result.into_iter().collect()
+    }
+
+    /// Identify glyphs that participate in RTL kerning.
+    pub(crate) fn rtl_kerning_glyphs(&self) -> HashSet<SmolStr> {
+        let mut rtl_glyphs: HashSet<SmolStr> = HashSet::new();
+
+        let Some(rtl_kerning) = self.read_rtl_kerning() else {
+            return rtl_glyphs;
+        };
+
+        let mut rtl_left_groups: HashSet<String> = HashSet::new();
+        let mut rtl_right_groups: HashSet<String> = HashSet::new();
+
+        for rtl_dict in rtl_kerning.values() {
+            for (kern1, subtable) in rtl_dict {
+                if let Some(stripped) = kern1.strip_prefix("@MMK_R_") {
+                    rtl_left_groups.insert(stripped.to_string());
+                } else {
+                    rtl_glyphs.insert(SmolStr::from(kern1.as_str()));
+                }
+                for kern2 in subtable.keys() {
+                    if let Some(stripped) = kern2.strip_prefix("@MMK_L_") {
+                        rtl_right_groups.insert(stripped.to_string());
+                    } else {
+                        rtl_glyphs.insert(SmolStr::from(kern2.as_str()));
+                    }
+                }
+            }
+        }
+
+        for glyph in self.glyphs.iter() {
+            if rtl_glyphs.contains(&glyph.name) {
+                continue;
+            }
+            let left_group = glyph.format_specific.get_string("kern_left");
+            let right_group = glyph.format_specific.get_string("kern_right");
+            if (!left_group.is_empty() && rtl_left_groups.contains(left_group.as_str()))
+                || (!right_group.is_empty() && rtl_right_groups.contains(right_group.as_str()))
+            {
+                rtl_glyphs.insert(glyph.name.clone());
+            }
+        }
+
+        rtl_glyphs
+    }
+
+    /// Apply the glyphsLib RTL group-side swap to the kern groups.
+    ///
+    /// Returns the first and second kern groups. Glyphs which take part in
+    /// RTL kerning are moved into the groups named by their `kern_right` and
+    /// `kern_left` properties, so that they match the pairs returned by
+    /// [Font::merged_kerning_for_master].
+    pub fn kern_groups_with_rtl_swaps(
+        &self,
+    ) -> (
+        IndexMap<SmolStr, Vec<SmolStr>>,
+        IndexMap<SmolStr, Vec<SmolStr>>,
+    ) {
+        let rtl_glyphs = self.rtl_kerning_glyphs();
+        if rtl_glyphs.is_empty() {
+            return (
+                self.first_kern_groups.clone(),
+                self.second_kern_groups.clone(),
+            );
+        }
+
+        let mut first = self.first_kern_groups.clone();
+        let mut second = self.second_kern_groups.clone();
+        for glyph_name in &rtl_glyphs {
+            for (_group, members) in first.iter_mut() {
+                members.retain(|member| member != glyph_name);
+            }
+            for (_group, members) in second.iter_mut() {
+                members.retain(|member| member != glyph_name);
+            }
+
+            if let Some(glyph) = self.glyphs.get(glyph_name) {
+                let left_group = glyph.format_specific.get_string("kern_left");
+                let right_group = glyph.format_specific.get_string("kern_right");
+
+                if !right_group.is_empty() {
+                    first
+                        .entry(SmolStr::from(right_group.as_str()))
+                        .or_default()
+                        .push(glyph_name.clone());
+                }
+                if !left_group.is_empty() {
+                    second
+                        .entry(SmolStr::from(left_group.as_str()))
+                        .or_default()
+                        .push(glyph_name.clone());
+                }
+            }
+        }
+
+        (first, second)
+    }
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Glyph;
+
+    fn kerned_font() -> Font {
+        let mut font = Font::new();
+        let mut master = Master::new("Regular", "m01", DesignLocation::default());
+        master.kerning.insert(("@T".into(), "@o".into()), -80);
+        master.kerning.insert(("T".into(), "oacute".into()), -40);
+        master
+            .kerning
+            .insert(("@T".into(), "odieresis".into()), -60);
+        font.masters.push(master);
+        font.first_kern_groups
+            .insert("T".into(), vec!["T".into(), "Tcaron".into()]);
+        font.second_kern_groups.insert(
+            "o".into(),
+            vec!["o".into(), "oacute".into(), "odieresis".into()],
+        );
+        font
+    }
+
+    #[test]
+    fn test_kerning_precedence() {
+        let font = kerned_font();
+        let master = &font.masters[0];
+        assert_eq!(font.kerning(master, "T", "o"), -80);
+        assert_eq!(font.kerning(master, "T", "oacute"), -40);
+        assert_eq!(font.kerning(master, "Tcaron", "oacute"), -80);
+        assert_eq!(font.kerning(master, "Tcaron", "odieresis"), -60);
+        assert_eq!(font.kerning(master, "o", "T"), 0);
+        let kerning = font.master_kerning(master);
+        assert_eq!(kerning.groups_of("Tcaron", KernSide::First), ["T"]);
+        assert_eq!(
+            kerning.pair_for("T", "odieresis").unwrap().0,
+            &("@T".into(), "odieresis".into())
+        );
+    }
+
+    #[test]
+    fn test_rtl_kerning() {
+        let mut font = Font::new();
+        font.masters
+            .push(Master::new("Regular", "m01", DesignLocation::default()));
+        for (name, group) in [("alef-ar", "alef"), ("beh-ar", "beh")] {
+            let mut glyph = Glyph::new(name);
+            glyph
+                .format_specific
+                .insert("kern_left".into(), serde_json::json!(group));
+            glyph
+                .format_specific
+                .insert("kern_right".into(), serde_json::json!(group));
+            font.glyphs.push(glyph);
+        }
+        font.format_specific.insert(
+            Font::KEY_KERNING_RTL.into(),
+            serde_json::json!({"m01": {"@MMK_R_alef": {"@MMK_L_beh": -20}}}),
+        );
+        assert_eq!(font.kerning(&font.masters[0], "alef-ar", "beh-ar"), -20);
+    }
+
+    #[test]
+    fn test_kerning_mutation() {
+        let mut font = kerned_font();
+        assert!(font.set_kerning("m01", "@V", "o", -10).is_err());
+        font.add_to_kern_group("Tcaron", "Tcaron", KernSide::First);
+        assert_eq!(font.first_kern_groups["T"], vec![SmolStr::from("T")]);
+        assert_eq!(
+            font.kern_group_of("Tcaron", KernSide::First),
+            Some(&"Tcaron".into())
+        );
+        font.second_kern_groups.insert("n".into(), vec!["n".into()]);
+        assert!(matches!(
+            font.rename_kern_group(KernSide::Second, "o", "n"),
+            Err(BabelfontError::KernGroupExists { .. })
+        ));
+        font.rename_kern_group(KernSide::Second, "o", "round")
+            .unwrap();
+        assert_eq!(font.kerning(&font.masters[0], "T", "o"), -80);
+        assert!(font.masters[0]
+            .kerning
+            .contains_key(&("@T".into(), "@round".into())));
+        font.remove_kern_group(KernSide::First, "T").unwrap();
+        assert_eq!(font.masters[0].kerning.len(), 1);
+        font.remove_glyph_from_kerning("oacute");
+        assert!(font.masters[0].kerning.is_empty());
+    }
+
+    #[test]
+    fn test_rtl_kerning_mutation() {
+        let mut font = Font::new();
+        font.masters
+            .push(Master::new("Regular", "m01", DesignLocation::default()));
+        font.first_kern_groups
+            .insert("alef".into(), vec!["alef-ar".into()]);
+        font.second_kern_groups
+            .insert("beh".into(), vec!["beh-ar".into()]);
+        font.format_specific.insert(
+            Font::KEY_KERNING_RTL.into(),
+            serde_json::json!({"m01": {
+                "@MMK_R_alef": {"@MMK_L_beh": -20},
+                "alef-ar": {"reh-ar": -10}
+            }}),
+        );
+        let rtl_pairs = |font: &Font| {
+            font.merged_kerning_for_master(&font.masters[0])
+                .into_iter()
+                .map(|((left, right), _)| format!("{} {}", left, right))
+                .collect::<Vec<_>>()
+        };
+        font.rename_kern_group(KernSide::First, "alef", "alefs")
+            .unwrap();
+        assert_eq!(rtl_pairs(&font), ["@alefs @beh", "alef-ar reh-ar"]);
+        assert_eq!(
+            font.format_specific.get(Font::KEY_KERNING_RTL).unwrap()["m01"]["@MMK_R_alefs"]
+                ["@MMK_L_beh"],
+            -20.0
+        );
+        font.remove_glyph_from_kerning("reh-ar");
+        assert_eq!(rtl_pairs(&font), ["@alefs @beh"]);
+        font.remove_kern_group(KernSide::Second, "beh").unwrap();
+        assert!(rtl_pairs(&font).is_empty());
+        assert!(font.format_specific.get(Font::KEY_KERNING_RTL).is_none());
+    }
+}
@@ -337,6 +337,7 @@ pub(crate) mod glyphs {
     use crate::convertors::glyphs3::{
         UserData, KEY_ANNOTATIONS, KEY_LAYER_HINTS, KEY_LAYER_IMAGE, KEY_USER_DATA,
     };
+    use crate::{Diagnostic, Diagnostics};
 
     use super::*;
 
@@ -344,7 +345,14 @@ pub(crate) mod glyphs {
         val: &glyphslib::glyphs3::Layer,
         axes_order: &[Tag],
         glyph_specific_axes: &[(String, DesignCoord, DesignCoord)], // name, bottom, top
+        glyph_name: &str,
+        diagnostics: &mut Diagnostics,
     ) -> Result<Layer, BabelfontError> {
+        let diagnostic = |code: &str, message: String| {
+            Diagnostic::warning(code, message)
+                .glyph(glyph_name)
+                .layer(val.layer_id.as_str())
+        };
         let format_specific = {
             let mut fs = FormatSpecific::default();
             fs.insert_if_ne_json("visible", &val.visible, &true);
@@ -375,7 +383,14 @@ pub(crate) mod glyphs {
                     .iter()
                     .zip(coords.iter())
                     .filter_map(|(axis_tag, v)| {
-                        v.as_f64().map(|f| (*axis_tag, DesignCoord::new(f)))
+                        let coord = v.as_f64().map(|f| (*axis_tag, DesignCoord::new(f)));
+                        if coord.is_none() {
+                            diagnostics.push(diagnostic(
+                                "glyphs.invalid-layer-coordinate",
+                                format!("Ignoring non-numeric coordinate for axis {}", axis_tag),
+                            ));
+                        }
+                        coord
                     })
                     .collect::<DesignLocation>()
             });
@@ -387,7 +402,16 @@ pub(crate) mod glyphs {
                 let coord = match index {
                     1 => *bottom,
                     2 => *top,
-                    _ => continue,
+                    _ => {
+                        diagnostics.push(diagnostic(
+                            "glyphs.invalid-part-selection",
+                            format!(
+                                "Ignoring part selection {} for smart component axis {}",
+                                index, axis
+                            ),
+                        ));
+                        continue;
+                    }
                 };
                 smart_location.insert(axis.clone(), coord);
             } else {
@@ -13,8 +13,11 @@ pub fn close_layout(
     let features = font.features.to_fea();
     let font_glyphs: Vec<&str> = font.glyphs.iter().map(|g| g.name.as_str()).collect();
     let mut feature_file: FeatureFile =
-        FeatureFile::new_from_fea(&features, Some(&font_glyphs), font.source.clone())
-            .map_err(|e| crate::BabelfontError::FilterError(e.to_string()))?;
+        FeatureFile::new_from_fea(&features, Some(&font_glyphs), font.source.clone()).map_err(
+            |e| {
+                BabelfontError::from(e).with_feature_sources(&font.features, font.source.as_deref())
+            },
+        )?;
     // Rather annoyingly we may need to perform multiple rounds of this. Consider:
     // lookup A { sub b by c; } lookup A; feature foo { sub a by b; } foo; feature bar { sub b' lookup A; } bar;
     // The first round we would add b to the closure, but not c, because we skipped over lookup A as it didn't
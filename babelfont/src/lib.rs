@@ -138,9 +138,15 @@ extern crate serde_json_path_to_error as serde_json;
 
 mod anchor;
 mod axis;
+/// Checks for problems in font sources
+pub mod checks;
 mod common;
+mod componentgraph;
 /// Convertors for various font file formats
 pub mod convertors;
+mod diagnostics;
+/// Structural comparison of fonts
+pub mod diff;
 mod error;
 mod features;
 /// Filters for font processing
@@ -151,9 +157,12 @@ mod guide;
 mod i18ndictionary;
 mod instance;
 mod interpolate;
+mod kerning;
 mod layer;
 mod layout;
 mod master;
+/// Three-way merging of fonts
+pub mod merge;
 mod metrics;
 mod names;
 mod serde_helpers;
@@ -163,13 +172,16 @@ pub use crate::{
     anchor::Anchor,
     axis::Axis,
     common::{constants, CustomOTValues, Direction, FormatSpecific, Node, NodeType, Position},
-    error::BabelfontError,
-    features::Features,
+    componentgraph::ComponentGraph,
+    diagnostics::{Diagnostic, Diagnostics},
+    error::{BabelfontError, FeatureError},
+    features::{FeatureCodeOrigin, FeatureSourceMap, Features},
     font::Font,
     glyph::{Glyph, GlyphCategory, GlyphList},
     guide::Guide,
     i18ndictionary::I18NDictionary,
     instance::Instance,
+    kerning::{KernSide, MasterKerning},
     layer::{Layer, LayerType},
     layout::closure::close_layout,
     master::Master,
@@ -197,41 +209,26 @@ pub use write_fonts::read::tables::name::NameId;
 
 /// Load a Babelfont Font from a file
 ///
-/// Which file formats are supported depends on which features are enabled:
+/// The file is loaded by the [convertors::Convertor] registered for its
+/// extension. Which file formats are supported out of the box depends on
+/// which features are enabled:
 /// - "ufo": UFO and DesignSpace files
 /// - "glyphs": Glyphs files
 /// - "fontlab": FontLab VFJ files
+///
+/// Further formats can be added with [convertors::register].
+///
+/// Problems which did not stop the font loading, such as source data which
+/// could not be represented, are collected in the font's `diagnostics`.
 pub fn load(filename: impl Into<PathBuf>) -> Result<Font, BabelfontError> {
-    let pb = filename.into();
-    let pb_clone = pb.clone();
+    convertors::registry().load(&filename.into())
+}
 
-    let mut font: Font = match pb.extension() {
-        Some(ext) if ext == "babelfont" => {
-            let buffered = std::io::BufReader::new(std::fs::File::open(&pb)?);
-            Ok(serde_json::from_reader(buffered)?)
-        }
-        #[cfg(feature = "ufo")]
-        Some(ext) if ext == "designspace" => crate::convertors::designspace::load(pb),
-        #[cfg(feature = "fontra")]
-        Some(ext) if ext == "fontra" => crate::convertors::fontra::load(pb),
-        #[cfg(feature = "fontlab")]
-        Some(ext) if ext == "vfj" => crate::convertors::fontlab::load(pb),
-        #[cfg(feature = "ufo")]
-        Some(ext) if ext == "ufo" => crate::convertors::ufo::load(pb),
-        #[cfg(feature = "fontforge")]
-        Some(ext) if ext == "sfd" || ext == "sfdir" => crate::convertors::fontforge::load(pb),
-        #[cfg(feature = "vfb")]
-        Some(ext) if ext == "vfb" => crate::convertors::vfb::load(pb),
-        #[cfg(feature = "robocjk")]
-        Some(ext) if ext == "rcjk" => crate::convertors::robocjk::load(pb),
-        #[cfg(feature = "glyphs")]
-        Some(ext) if ext == "glyphs" || ext == "glyphspackage" => {
-            crate::convertors::glyphs3::load(pb)
-        }
-        #[cfg(feature = "ttf")]
-        Some(ext) if ext == "ttf" => crate::convertors::ttf::load(pb),
-        _ => Err(BabelfontError::UnknownFileType { path: pb }),
-    }?;
-    font.source = Some(pb_clone);
-    Ok(font)
+/// Load a Babelfont Font from an in-memory buffer
+///
+/// The format is detected from the first bytes of the buffer, falling back to
+/// the extension of `path` if given. Only formats which are stored in a single
+/// file can be loaded this way.
+pub fn load_bytes(bytes: &[u8], path: Option<&std::path::Path>) -> Result<Font, BabelfontError> {
+    convertors::registry().load_bytes(bytes, path)
 }
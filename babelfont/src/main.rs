@@ -1,39 +1,15 @@
-use std::{collections::HashMap, path::PathBuf};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
 
-use babelfont::filters::FontFilter;
+use babelfont::{
+    checks::{CompatibilityFix, CompatibilityReport},
+    filters::FontFilter,
+    BabelfontError, Font,
+};
 use clap::Command;
 
-static SUPPORTED_EXTENSIONS: std::sync::LazyLock<Vec<&'static str>> =
-    std::sync::LazyLock::new(|| {
-        vec![
-            "babelfont",
-            #[cfg(feature = "ufo")]
-            "ufo",
-            #[cfg(feature = "ufo")]
-            "designspace",
-            #[cfg(feature = "fontra")]
-            "fontra",
-            #[cfg(feature = "glyphs")]
-            "glyphs",
-            #[cfg(feature = "glyphs")]
-            "glyphspackage",
-            #[cfg(feature = "fontir")]
-            "ttf",
-            #[cfg(feature = "fontlab")]
-            "vfj",
-            #[cfg(feature = "vfb")]
-            "vfb",
-            #[cfg(feature = "robocjk")]
-            "rcjk",
-            #[cfg(feature = "fontforge")]
-            "sfd",
-            #[cfg(feature = "fontforge")]
-            "sfdir",
-            #[cfg(feature = "ttf")]
-            "ttf",
-        ]
-    });
-
 fn main() {
     let command = Command::new("babelfont")
         .version(env!("CARGO_PKG_VERSION"))
@@ -56,7 +32,145 @@ fn main() {
                 .short('v')
                 .long("verbosity")
                 .help("Set the level of verbosity")
-                .action(clap::ArgAction::Count),
+                .action(clap::ArgAction::Count)
+                .global(true),
+        )
+        .args_conflicts_with_subcommands(true)
+        .subcommand(
+            Command::new("diff")
+                .about("Show the structural differences between two fonts")
+                .arg(
+                    clap::Arg::new("old")
+                        .help("Path to the old font file")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    clap::Arg::new("new")
+                        .help("Path to the new font file")
+                        .required(true)
+                        .index(2),
+                )
+                .arg(
+                    clap::Arg::new("json")
+                        .long("json")
+                        .help("Output the changes as JSON")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("check")
+                .about("Check a font source for problems")
+                .arg(
+                    clap::Arg::new("font_path")
+                        .help("Path to the font file")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    clap::Arg::new("only")
+                        .long("only")
+                        .help(format!("Only run the given checks ({})", check_names()))
+                        .value_name("CHECK")
+                        .value_delimiter(',')
+                        .action(clap::ArgAction::Append),
+                )
+                .arg(
+                    clap::Arg::new("skip")
+                        .long("skip")
+                        .help("Do not run the given checks")
+                        .value_name("CHECK")
+                        .value_delimiter(',')
+                        .action(clap::ArgAction::Append),
+                )
+                .arg(
+                    clap::Arg::new("json")
+                        .long("json")
+                        .help("Output the report as JSON")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("compatibility")
+                .about("Report interpolation compatibility problems, and optionally fix them")
+                .arg(
+                    clap::Arg::new("font_path")
+                        .help("Path to the font file")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    clap::Arg::new("glyphs")
+                        .long("glyphs")
+                        .help("Only report on (and fix) the given glyphs")
+                        .value_name("GLYPH")
+                        .value_delimiter(',')
+                        .action(clap::ArgAction::Append),
+                )
+                .arg(
+                    clap::Arg::new("fix")
+                        .long("fix")
+                        .help("Apply the given fixes where they are suggested (fixstartpoints, makecompatible, dropincompatiblepaths)")
+                        .value_name("FIX")
+                        .value_delimiter(',')
+                        .action(clap::ArgAction::Append)
+                        .requires("output"),
+                )
+                .arg(
+                    clap::Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .help("Path to write the fixed font to")
+                        .value_name("FILE"),
+                )
+                .arg(
+                    clap::Arg::new("json")
+                        .long("json")
+                        .help("Output the report as JSON")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("merge")
+                .about("Three-way merge two versions of a font with their common ancestor")
+                .long_about(
+                    "Three-way merge two versions of a font with their common ancestor. \
+                     Conflicts are listed and our side is kept; the exit status is 1 if there \
+                     were any, and 2 if a font could not be loaded or saved. To use as a git \
+                     merge driver, set `driver = babelfont merge %O %A %B --path %P` and write \
+                     the result over %A.",
+                )
+                .arg(
+                    clap::Arg::new("base")
+                        .help("Path to the common ancestor font file")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    clap::Arg::new("ours")
+                        .help("Path to our font file")
+                        .required(true)
+                        .index(2),
+                )
+                .arg(
+                    clap::Arg::new("theirs")
+                        .help("Path to their font file")
+                        .required(true)
+                        .index(3),
+                )
+                .arg(
+                    clap::Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .help("Path to write the merged font to (default: overwrite our file)")
+                        .value_name("FILE"),
+                )
+                .arg(
+                    clap::Arg::new("path")
+                        .long("path")
+                        .help("Original path of the file, used to find its format when the inputs are temporary files")
+                        .value_name("PATH"),
+                ),
         );
 
     let command = command.next_help_heading("TTF Compilation Options");
@@ -98,14 +212,30 @@ fn main() {
             _ => log::LevelFilter::Trace,
         })
         .init();
+    if let Some(("diff", diff_args)) = args.subcommand() {
+        diff(diff_args);
+        return;
+    }
+    if let Some(("check", check_args)) = args.subcommand() {
+        check(check_args);
+        return;
+    }
+    if let Some(("compatibility", compatibility_args)) = args.subcommand() {
+        compatibility(compatibility_args);
+        return;
+    }
+    if let Some(("merge", merge_args)) = args.subcommand() {
+        merge(merge_args);
+        return;
+    }
     let input_name = PathBuf::from(args.get_one::<String>("font_path").unwrap());
     let output_name = PathBuf::from(args.get_one::<String>("output").unwrap());
-    let input_extension = input_name.extension().unwrap().to_str().unwrap();
-    if !SUPPORTED_EXTENSIONS.contains(&input_extension) {
+    let registry = babelfont::convertors::registry();
+    if registry.loader_for_path(&input_name).is_none() {
         log::error!(
             "Input extension {:?} is not in the list of supported extensions: {}",
-            input_name.extension().unwrap(),
-            SUPPORTED_EXTENSIONS.join(", ")
+            input_name.extension().unwrap_or_default(),
+            registry.loadable_extensions().join(", ")
         );
         std::process::exit(1);
     }
@@ -113,11 +243,11 @@ fn main() {
         .extension()
         .and_then(|x| x.to_str())
         .unwrap_or("");
-    if !SUPPORTED_EXTENSIONS.contains(&output_extension) {
+    if registry.saver_for_path(&output_name).is_none() {
         log::error!(
             "Output extension {:?} is not in the list of supported extensions: {}",
             output_extension,
-            SUPPORTED_EXTENSIONS.join(", ")
+            registry.saveable_extensions().join(", ")
         );
         std::process::exit(1);
     }
@@ -217,3 +347,215 @@ fn main() {
         input.save(output_name).expect("Failed to save font");
     }
 }
+
+fn diff(args: &clap::ArgMatches) {
+    let old = babelfont::load(args.get_one::<String>("old").unwrap()).expect("Failed to load font");
+    let new = babelfont::load(args.get_one::<String>("new").unwrap()).expect("Failed to load font");
+    let changes = babelfont::diff::diff_fonts(&old, &new);
+    if args.get_flag("json") {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&changes).expect("Failed to serialize changes")
+        );
+    } else {
+        for change in changes.iter() {
+            println!("{}", change);
+        }
+    }
+}
+
+fn merge(args: &clap::ArgMatches) {
+    let path_arg = |name: &str| match args.get_one::<String>(name) {
+        Some(path) => PathBuf::from(path),
+        None => fail(format!("No {} font given", name)),
+    };
+    let ours = path_arg("ours");
+    // Git passes merge drivers temporary files without the original extension
+    let format = args
+        .get_one::<String>("path")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| ours.clone());
+    let load = |name: &str| {
+        let path = path_arg(name);
+        load_as(&path, name, &format).unwrap_or_else(|e| {
+            fail(format!(
+                "Failed to load {} font {}: {}",
+                name,
+                path.display(),
+                e
+            ))
+        })
+    };
+    let merged = babelfont::merge::merge3(&load("base"), &load("ours"), &load("theirs"));
+    for conflict in merged.conflicts.iter() {
+        eprintln!("{}", conflict);
+    }
+    let output = args
+        .get_one::<String>("output")
+        .map(PathBuf::from)
+        .unwrap_or(ours);
+    if let Err(e) = save_as(&merged.font, &output, &format) {
+        fail(format!(
+            "Failed to save merged font {}: {}",
+            output.display(),
+            e
+        ));
+    }
+    if !merged.is_clean() {
+        std::process::exit(1);
+    }
+}
+
+/// Report a merge error and exit; status 1 is kept for conflicts
+fn fail(message: String) -> ! {
+    log::error!("{}", message);
+    std::process::exit(2);
+}
+
+/// A temporary file name with the extension of `format`
+fn temp_path(label: &str, format: &Path) -> PathBuf {
+    std::env::temp_dir()
+        .join(format!("babelfont-{}-{}", std::process::id(), label))
+        .with_extension(format.extension().unwrap_or_default())
+}
+
+/// Load a font in the format given by the extension of `format`
+fn load_as(path: &Path, label: &str, format: &Path) -> Result<Font, BabelfontError> {
+    if path.extension() == format.extension() {
+        return babelfont::load(path);
+    }
+    let temp = temp_path(label, format);
+    let font = copy_font(path, &temp)
+        .map_err(BabelfontError::from)
+        .and_then(|_| babelfont::load(&temp));
+    remove_font(&temp);
+    font
+}
+
+/// Save a font in the format given by the extension of `format`
+fn save_as(font: &Font, path: &Path, format: &Path) -> Result<(), BabelfontError> {
+    if path.extension() == format.extension() {
+        return font.save(path);
+    }
+    let temp = temp_path("merged", format);
+    let result = font.save(&temp).and_then(|_| {
+        remove_font(path);
+        copy_font(&temp, path).map_err(BabelfontError::from)
+    });
+    remove_font(&temp);
+    result
+}
+
+/// Copy a font file, or a directory-based font such as a UFO, recursively
+fn copy_font(from: &Path, to: &Path) -> std::io::Result<()> {
+    if !from.is_dir() {
+        return std::fs::copy(from, to).map(|_| ());
+    }
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        copy_font(&entry.path(), &to.join(entry.file_name()))?;
+    }
+    Ok(())
+}
+
+/// Remove a font file or directory, if it exists
+fn remove_font(path: &Path) {
+    let _ = if path.is_dir() {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_file(path)
+    };
+}
+
+fn check_names() -> String {
+    babelfont::checks::all_checks()
+        .iter()
+        .map(|check| check.name())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn check(args: &clap::ArgMatches) {
+    let font =
+        babelfont::load(args.get_one::<String>("font_path").unwrap()).expect("Failed to load font");
+    let only: Vec<&String> = args
+        .get_many::<String>("only")
+        .unwrap_or_default()
+        .collect();
+    let skip: Vec<&String> = args
+        .get_many::<String>("skip")
+        .unwrap_or_default()
+        .collect();
+    for name in only.iter().chain(skip.iter()) {
+        if !babelfont::checks::all_checks()
+            .iter()
+            .any(|check| check.name() == name.as_str())
+        {
+            log::error!("Unknown check {}; known checks are {}", name, check_names());
+            std::process::exit(2);
+        }
+    }
+    let checks: Vec<_> = babelfont::checks::all_checks()
+        .into_iter()
+        .filter(|check| only.is_empty() || only.iter().any(|n| n.as_str() == check.name()))
+        .filter(|check| !skip.iter().any(|n| n.as_str() == check.name()))
+        .collect();
+    let report = babelfont::checks::run_checks(&font, &checks);
+    if args.get_flag("json") {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report).expect("Failed to serialize report")
+        );
+    } else {
+        for finding in report.findings.iter() {
+            println!("{}", finding);
+        }
+    }
+    if report.has_errors() {
+        std::process::exit(1);
+    }
+}
+
+fn compatibility(args: &clap::ArgMatches) {
+    let mut font =
+        babelfont::load(args.get_one::<String>("font_path").unwrap()).expect("Failed to load font");
+    let glyphs: Vec<&String> = args
+        .get_many::<String>("glyphs")
+        .unwrap_or_default()
+        .collect();
+    let fixes: Vec<CompatibilityFix> = args
+        .get_many::<String>("fix")
+        .unwrap_or_default()
+        .map(|name| {
+            name.parse().unwrap_or_else(|e| {
+                log::error!("{}", e);
+                std::process::exit(2);
+            })
+        })
+        .collect();
+    let mut report = CompatibilityReport::new(&font);
+    if !glyphs.is_empty() {
+        report
+            .issues
+            .retain(|issue| glyphs.iter().any(|g| **g == issue.glyph));
+    }
+    if args.get_flag("json") {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report).expect("Failed to serialize report")
+        );
+    } else {
+        for issue in report.issues.iter() {
+            println!("{}", issue);
+        }
+    }
+    if let Some(output) = args.get_one::<String>("output") {
+        report
+            .apply_fixes(&mut font, &fixes)
+            .expect("Failed to apply fixes");
+        font.save(output).expect("Failed to save font");
+    } else if report.issues.iter().any(|issue| issue.blocking) {
+        std::process::exit(1);
+    }
+}
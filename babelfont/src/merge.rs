@@ -0,0 +1,550 @@
+use std::{collections::HashMap, fmt::Display};
+
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use smol_str::SmolStr;
+
+use crate::{
+    diff::{at, to_value},
+    Font, Glyph, LayerType, Master, Names,
+};
+
+/// Something which was changed in different ways on both sides of a merge
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Conflict {
+    /// Where the conflict is, from the font downwards (e.g. `["glyphs", "A", "layers", "m01"]`)
+    pub path: Vec<String>,
+    /// The value in the common ancestor, if it existed there
+    pub base: Option<Value>,
+    /// Our value, if it exists on our side
+    pub ours: Option<Value>,
+    /// Their value, if it exists on their side
+    pub theirs: Option<Value>,
+}
+
+impl Display for Conflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let what = match (&self.base, &self.ours, &self.theirs) {
+            (None, _, _) => "added differently on both sides",
+            (Some(_), None, _) => "deleted in ours but changed in theirs",
+            (Some(_), _, None) => "changed in ours but deleted in theirs",
+            _ => "changed differently on both sides",
+        };
+        write!(f, "! {}: {}", self.path.join("/"), what)
+    }
+}
+
+/// The result of a three-way merge
+#[derive(Debug, Clone)]
+pub struct Merged {
+    /// The merged font; where there are conflicts, our side is kept
+    pub font: Font,
+    /// Changes which could not be merged
+    pub conflicts: Vec<Conflict>,
+}
+
+impl Merged {
+    /// Whether the merge was free of conflicts
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+/// Merge the changes made to `base` in `ours` and in `theirs`
+///
+/// Glyphs, layers, masters, kerning pairs, axes, instances and names are
+/// matched by name or ID and merged individually, so that (for example) two
+/// designers editing different layers of the same glyph, or different kerning
+/// pairs, do not conflict. A change made on only one side is taken; the same
+/// change made on both sides is taken once; different changes to the same
+/// item are reported as a [`Conflict`] and our side is kept. Other font-level
+/// data, such as the feature code, is merged as a whole.
+pub fn merge3(base: &Font, ours: &Font, theirs: &Font) -> Merged {
+    let mut merger = Merger::default();
+    let root: Vec<String> = vec![];
+    let mut font = ours.clone();
+    font.upm = merger.pick(&at(&root, "upm"), &base.upm, &ours.upm, &theirs.upm);
+    font.version = merger.pick(
+        &at(&root, "version"),
+        &base.version,
+        &ours.version,
+        &theirs.version,
+    );
+    font.axes = merger.keyed(
+        &at(&root, "axes"),
+        &base.axes,
+        &ours.axes,
+        &theirs.axes,
+        |axis| axis.tag.to_string(),
+        Merger::conflicted,
+    );
+    font.cross_axis_mappings = merger.pick(
+        &at(&root, "cross_axis_mappings"),
+        &base.cross_axis_mappings,
+        &ours.cross_axis_mappings,
+        &theirs.cross_axis_mappings,
+    );
+    font.instances = merger.keyed(
+        &at(&root, "instances"),
+        &base.instances,
+        &ours.instances,
+        &theirs.instances,
+        |instance| instance.id.clone(),
+        Merger::conflicted,
+    );
+    font.masters = merger.keyed(
+        &at(&root, "masters"),
+        &base.masters,
+        &ours.masters,
+        &theirs.masters,
+        |master| master.id.clone(),
+        Merger::master,
+    );
+    font.glyphs = merger
+        .keyed(
+            &at(&root, "glyphs"),
+            base.glyphs.as_slice(),
+            ours.glyphs.as_slice(),
+            theirs.glyphs.as_slice(),
+            |glyph| glyph.name.to_string(),
+            Merger::glyph,
+        )
+        .into();
+    font.note = merger.pick(&at(&root, "note"), &base.note, &ours.note, &theirs.note);
+    font.names = merger.names(&at(&root, "names"), &base.names, &ours.names, &theirs.names);
+    font.custom_ot_values = merger.pick(
+        &at(&root, "custom_ot_values"),
+        &base.custom_ot_values,
+        &ours.custom_ot_values,
+        &theirs.custom_ot_values,
+    );
+    // Tuple keys don't serialize to JSON objects, so compare these as lists of entries
+    let entries = |font: &Font| -> Vec<((u32, u32), SmolStr)> {
+        font.variation_sequences
+            .iter()
+            .map(|(key, name)| (*key, name.clone()))
+            .collect()
+    };
+    font.variation_sequences = merger
+        .pick(
+            &at(&root, "variation_sequences"),
+            &entries(base),
+            &entries(ours),
+            &entries(theirs),
+        )
+        .into_iter()
+        .collect();
+    font.features = merger.pick(
+        &at(&root, "features"),
+        &base.features,
+        &ours.features,
+        &theirs.features,
+    );
+    font.first_kern_groups = merger.pick(
+        &at(&root, "first_kern_groups"),
+        &base.first_kern_groups,
+        &ours.first_kern_groups,
+        &theirs.first_kern_groups,
+    );
+    font.second_kern_groups = merger.pick(
+        &at(&root, "second_kern_groups"),
+        &base.second_kern_groups,
+        &ours.second_kern_groups,
+        &theirs.second_kern_groups,
+    );
+    font.format_specific = merger.pick(
+        &at(&root, "format_specific"),
+        &base.format_specific,
+        &ours.format_specific,
+        &theirs.format_specific,
+    );
+    Merged {
+        font,
+        conflicts: merger.conflicts,
+    }
+}
+
+/// Which side of a merge to take
+enum Choice {
+    Ours,
+    Theirs,
+    Conflict,
+}
+
+fn same<T: Serialize>(a: Option<&T>, b: Option<&T>) -> bool {
+    match (a, b) {
+        (None, None) => true,
+        (Some(a), Some(b)) => to_value(a) == to_value(b),
+        _ => false,
+    }
+}
+
+fn choose<T: Serialize>(base: Option<&T>, ours: Option<&T>, theirs: Option<&T>) -> Choice {
+    if same(ours, theirs) || same(base, theirs) {
+        Choice::Ours
+    } else if same(base, ours) {
+        Choice::Theirs
+    } else {
+        Choice::Conflict
+    }
+}
+
+#[derive(Default)]
+struct Merger {
+    conflicts: Vec<Conflict>,
+}
+
+impl Merger {
+    fn conflict<T: Serialize>(
+        &mut self,
+        path: &[String],
+        base: Option<&T>,
+        ours: Option<&T>,
+        theirs: Option<&T>,
+    ) {
+        self.conflicts.push(Conflict {
+            path: path.to_vec(),
+            base: base.map(to_value),
+            ours: ours.map(to_value),
+            theirs: theirs.map(to_value),
+        });
+    }
+
+    /// Merge an item which cannot be merged any more finely
+    fn pick<T: Serialize + Clone>(&mut self, path: &[String], base: &T, ours: &T, theirs: &T) -> T {
+        match choose(Some(base), Some(ours), Some(theirs)) {
+            Choice::Ours => ours.clone(),
+            Choice::Theirs => theirs.clone(),
+            Choice::Conflict => self.conflicted(path, base, ours, theirs),
+        }
+    }
+
+    /// Record a conflict on an item changed on both sides, and keep ours
+    fn conflicted<T: Serialize + Clone>(
+        &mut self,
+        path: &[String],
+        base: &T,
+        ours: &T,
+        theirs: &T,
+    ) -> T {
+        self.conflict(path, Some(base), Some(ours), Some(theirs));
+        ours.clone()
+    }
+
+    /// Merge lists of items matched by key
+    ///
+    /// `merge` is called for items which exist on all sides and were changed on both.
+    /// Items sharing a key are told apart by their order, as `key#2` and so on.
+    fn keyed<T: Serialize + Clone>(
+        &mut self,
+        path: &[String],
+        base: &[T],
+        ours: &[T],
+        theirs: &[T],
+        key: impl Fn(&T) -> String,
+        mut merge: impl FnMut(&mut Self, &[String], &T, &T, &T) -> T,
+    ) -> Vec<T> {
+        let by_key = |items: &'_ [T]| {
+            let mut counts: HashMap<String, usize> = HashMap::new();
+            items
+                .iter()
+                .map(|item| {
+                    let key = key(item);
+                    let count = counts.entry(key.clone()).or_default();
+                    *count += 1;
+                    if *count > 1 {
+                        (format!("{}#{}", key, count), item)
+                    } else {
+                        (key, item)
+                    }
+                })
+                .collect::<IndexMap<String, &T>>()
+        };
+        let base = by_key(base);
+        let ours = by_key(ours);
+        let theirs = by_key(theirs);
+        // Keep our order, with items only they have at the end
+        let keys = ours
+            .keys()
+            .chain(theirs.keys().filter(|k| !ours.contains_key(*k)));
+        let mut merged = vec![];
+        for name in keys {
+            let (b, o, t) = (
+                base.get(name).copied(),
+                ours.get(name).copied(),
+                theirs.get(name).copied(),
+            );
+            let item = match choose(b, o, t) {
+                Choice::Ours => o.cloned(),
+                Choice::Theirs => t.cloned(),
+                Choice::Conflict => match (b, o, t) {
+                    (Some(b), Some(o), Some(t)) => Some(merge(self, &at(path, name), b, o, t)),
+                    _ => {
+                        self.conflict(&at(path, name), b, o, t);
+                        o.cloned()
+                    }
+                },
+            };
+            merged.extend(item);
+        }
+        merged
+    }
+
+    fn glyph(&mut self, path: &[String], base: &Glyph, ours: &Glyph, theirs: &Glyph) -> Glyph {
+        let without_layers = |glyph: &Glyph| Glyph {
+            layers: vec![],
+            ..glyph.clone()
+        };
+        let mut glyph = self.pick(
+            path,
+            &without_layers(base),
+            &without_layers(ours),
+            &without_layers(theirs),
+        );
+        glyph.layers = self.keyed(
+            &at(path, "layers"),
+            &base.layers,
+            &ours.layers,
+            &theirs.layers,
+            |layer| {
+                layer.id.clone().unwrap_or_else(|| {
+                    let master = match &layer.master {
+                        LayerType::DefaultForMaster(m) | LayerType::AssociatedWithMaster(m) => {
+                            m.as_str()
+                        }
+                        LayerType::FreeFloating => "",
+                    };
+                    format!("{}/{}", master, layer.debug_name())
+                })
+            },
+            Merger::conflicted,
+        );
+        glyph
+    }
+
+    fn master(&mut self, path: &[String], base: &Master, ours: &Master, theirs: &Master) -> Master {
+        let without_kerning = |master: &Master| Master {
+            kerning: IndexMap::new(),
+            ..master.clone()
+        };
+        let mut master = self.pick(
+            path,
+            &without_kerning(base),
+            &without_kerning(ours),
+            &without_kerning(theirs),
+        );
+        master.kerning = self.kerning(
+            &at(path, "kerning"),
+            &base.kerning,
+            &ours.kerning,
+            &theirs.kerning,
+        );
+        master
+    }
+
+    fn kerning(
+        &mut self,
+        path: &[String],
+        base: &IndexMap<(SmolStr, SmolStr), i16>,
+        ours: &IndexMap<(SmolStr, SmolStr), i16>,
+        theirs: &IndexMap<(SmolStr, SmolStr), i16>,
+    ) -> IndexMap<(SmolStr, SmolStr), i16> {
+        let pairs = ours
+            .keys()
+            .chain(theirs.keys().filter(|k| !ours.contains_key(*k)));
+        let mut merged = IndexMap::new();
+        for pair in pairs {
+            let (b, o, t) = (base.get(pair), ours.get(pair), theirs.get(pair));
+            let value = match choose(b, o, t) {
+                Choice::Ours => o,
+                Choice::Theirs => t,
+                Choice::Conflict => {
+                    self.conflict(&at(path, format!("{} {}", pair.0, pair.1)), b, o, t);
+                    o
+                }
+            };
+            if let Some(value) = value {
+                merged.insert(pair.clone(), *value);
+            }
+        }
+        merged
+    }
+
+    fn names(&mut self, path: &[String], base: &Names, ours: &Names, theirs: &Names) -> Names {
+        let mut names = ours.clone();
+        macro_rules! merge {
+            ($($field:ident),*) => {
+                $(
+                    names.$field = self.pick(
+                        &at(path, stringify!($field)),
+                        &base.$field,
+                        &ours.$field,
+                        &theirs.$field,
+                    );
+                )*
+            };
+        }
+        merge!(
+            copyright,
+            family_name,
+            preferred_subfamily_name,
+            unique_id,
+            full_name,
+            version,
+            postscript_name,
+            trademark,
+            manufacturer,
+            designer,
+            description,
+            manufacturer_url,
+            designer_url,
+            license,
+            license_url,
+            typographic_family,
+            typographic_subfamily,
+            compatible_full_name,
+            sample_text,
+            postscript_cid_name,
+            wws_family_name,
+            wws_subfamily_name,
+            variations_postscript_name_prefix
+        );
+        names
+    }
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{I18NDictionary, Layer};
+
+    fn font() -> Font {
+        let mut font = Font::new();
+        font.masters
+            .push(Master::new("Regular", "m01", Default::default()));
+        font.masters[0]
+            .kerning
+            .insert(("A".into(), "V".into()), -50);
+        for name in ["A", "V"] {
+            let mut layer = Layer::new(500.0);
+            layer.id = Some("m01".into());
+            layer.master = LayerType::DefaultForMaster("m01".into());
+            font.glyphs.push(Glyph {
+                name: name.into(),
+                layers: vec![layer],
+                ..Default::default()
+            });
+        }
+        font
+    }
+
+    #[test]
+    fn test_clean_merge() {
+        let base = font();
+        let mut ours = font();
+        let mut theirs = font();
+        ours.glyphs.get_mut("A").unwrap().layers[0].width = 600.0;
+        ours.names.designer = I18NDictionary::from("Ours");
+        theirs.glyphs.get_mut("V").unwrap().layers[0].width = 700.0;
+        theirs.glyphs.push(Glyph::new("B"));
+        theirs.masters[0]
+            .kerning
+            .insert(("A".into(), "V".into()), -60);
+        theirs.names.license = I18NDictionary::from("OFL");
+
+        let merged = merge3(&base, &ours, &theirs);
+        assert!(merged.is_clean());
+        let font = merged.font;
+        assert_eq!(font.glyphs.get("A").unwrap().layers[0].width, 600.0);
+        assert_eq!(font.glyphs.get("V").unwrap().layers[0].width, 700.0);
+        assert!(font.glyphs.get("B").is_some());
+        assert_eq!(font.masters[0].kerning[&("A".into(), "V".into())], -60);
+        assert_eq!(font.names.designer, I18NDictionary::from("Ours"));
+        assert_eq!(font.names.license, I18NDictionary::from("OFL"));
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let base = font();
+        let mut ours = font();
+        let mut theirs = font();
+        ours.glyphs.get_mut("A").unwrap().layers[0].width = 600.0;
+        theirs.glyphs.get_mut("A").unwrap().layers[0].width = 650.0;
+        ours.masters[0]
+            .kerning
+            .shift_remove(&("A".into(), "V".into()));
+        theirs.masters[0]
+            .kerning
+            .insert(("A".into(), "V".into()), -60);
+
+        let merged = merge3(&base, &ours, &theirs);
+        let paths: Vec<String> = merged.conflicts.iter().map(|c| c.path.join("/")).collect();
+        assert_eq!(
+            paths,
+            vec!["masters/m01/kerning/A V", "glyphs/A/layers/m01"]
+        );
+        assert_eq!(
+            merged.conflicts[0].to_string(),
+            "! masters/m01/kerning/A V: deleted in ours but changed in theirs"
+        );
+        // Our side is kept
+        assert_eq!(merged.font.glyphs.get("A").unwrap().layers[0].width, 600.0);
+    }
+
+    #[test]
+    fn test_variation_sequence_conflict() {
+        let base = font();
+        let mut ours = font();
+        let mut theirs = font();
+        ours.variation_sequences
+            .insert((0x41, 0xFE00), "A.ss01".into());
+        theirs
+            .variation_sequences
+            .insert((0x41, 0xFE00), "A.alt".into());
+
+        let merged = merge3(&base, &ours, &theirs);
+        assert_eq!(merged.conflicts.len(), 1);
+        let conflict = &merged.conflicts[0];
+        assert_eq!(conflict.path, vec!["variation_sequences"]);
+        assert_eq!(conflict.base, Some(serde_json::json!([])));
+        assert_eq!(
+            conflict.ours,
+            Some(serde_json::json!([[[0x41, 0xFE00], "A.ss01"]]))
+        );
+        assert_eq!(
+            conflict.theirs,
+            Some(serde_json::json!([[[0x41, 0xFE00], "A.alt"]]))
+        );
+        assert_eq!(merged.font.variation_sequences[&(0x41, 0xFE00)], "A.ss01");
+    }
+
+    #[test]
+    fn test_same_named_layers() {
+        let with_brackets = || {
+            let mut font = font();
+            let glyph = font.glyphs.get_mut("A").unwrap();
+            for id in ["bracket1", "bracket2"] {
+                let mut layer = Layer::new(500.0);
+                layer.id = Some(id.into());
+                layer.name = Some("Bold [120]".into());
+                layer.master = LayerType::AssociatedWithMaster("m01".into());
+                glyph.layers.push(layer);
+            }
+            font
+        };
+        let base = with_brackets();
+        let mut ours = with_brackets();
+        let mut theirs = with_brackets();
+        ours.glyphs.get_mut("A").unwrap().layers[1].width = 600.0;
+        theirs.glyphs.get_mut("A").unwrap().layers[2].width = 700.0;
+
+        let merged = merge3(&base, &ours, &theirs);
+        assert!(merged.is_clean());
+        let layers = &merged.font.glyphs.get("A").unwrap().layers;
+        assert_eq!(layers.len(), 3);
+        assert_eq!(layers[1].width, 600.0);
+        assert_eq!(layers[2].width, 700.0);
+    }
+}
@@ -100,6 +100,44 @@ impl Names {
         Self::default()
     }
 
+    /// Replace names with those which are set in `other`
+    pub fn update_from(&mut self, other: &Names) {
+        macro_rules! update {
+            ($($field:ident),*) => {
+                $(
+                    if !other.$field.is_empty() {
+                        self.$field = other.$field.clone();
+                    }
+                )*
+            };
+        }
+        update!(
+            copyright,
+            family_name,
+            preferred_subfamily_name,
+            unique_id,
+            full_name,
+            version,
+            postscript_name,
+            trademark,
+            manufacturer,
+            designer,
+            description,
+            manufacturer_url,
+            designer_url,
+            license,
+            license_url,
+            typographic_family,
+            typographic_subfamily,
+            compatible_full_name,
+            sample_text,
+            postscript_cid_name,
+            wws_family_name,
+            wws_subfamily_name,
+            variations_postscript_name_prefix
+        );
+    }
+
     /// Get a name by its OpenType Name ID
     pub fn get(&self, stringid: NameId) -> Option<&I18NDictionary> {
         match stringid {
@@ -179,6 +179,33 @@ impl Path {
         self.nodes.rotate_left(len - trailing);
     }
 
+    /// Reverse the direction of the path
+    ///
+    /// Node types are shuffled so that each on-curve node keeps describing the
+    /// segment which leads up to it, and implied on-curve points in quadratic
+    /// splines stay implied.
+    pub fn reverse(&mut self) {
+        let len = self.nodes.len();
+        let oncurves: Vec<usize> = (0..len)
+            .filter(|&ix| self.nodes[ix].nodetype != NodeType::OffCurve)
+            .collect();
+        let mut new_types = vec![];
+        for (position, &ix) in oncurves.iter().enumerate() {
+            let next = match oncurves.get(position + 1) {
+                Some(&next) => Some(next),
+                None if self.closed => oncurves.first().copied(),
+                None => None,
+            };
+            let nodetype = next.map_or(NodeType::Move, |next| self.nodes[next].nodetype);
+            new_types.push((ix, nodetype));
+        }
+        for (ix, nodetype) in new_types {
+            self.nodes[ix].nodetype = nodetype;
+        }
+        self.nodes.reverse();
+        self.rotate_to_preferred_representation();
+    }
+
     pub(crate) fn set_smooth(&mut self) {
         let mut to_set_smooth = vec![];
         for (prev_ix, node_ix, next_ix) in (0..self.nodes.len()).circular_tuple_windows() {
@@ -794,6 +821,56 @@ mod tests {
         assert_eq!(deserialized, path);
     }
 
+    fn path_from(nodes: &str, closed: bool) -> Path {
+        serde_json::from_value(serde_json::json!({ "nodes": nodes, "closed": closed })).unwrap()
+    }
+
+    fn nodes_of(path: &Path) -> String {
+        serde_json::to_value(path).unwrap()["nodes"]
+            .as_str()
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn test_reverse_roundtrip() {
+        let cases = [
+            // Closed cubic, with off-curve points wrapping around the start
+            (
+                "394 173 o 467 246 o 467 337 cs 467 427 o 394 500 o 304 500 cs 213 500 o 140 427 o 140 337 cs 140 246 o 213 173 o 304 173 cs",
+                true,
+                "467 246 o 394 173 o 304 173 cs 213 173 o 140 246 o 140 337 cs 140 427 o 213 500 o 304 500 cs 394 500 o 467 427 o 467 337 cs",
+            ),
+            // Closed, mixing lines and curves
+            (
+                "0 0 l 100 0 l 150 50 o 150 100 o 100 150 cs 0 150 l",
+                true,
+                "0 150 l 100 150 ls 150 100 o 150 50 o 100 0 c 0 0 l",
+            ),
+            // Quadratic with an implied on-curve point
+            (
+                "0 0 l 50 0 o 100 50 o 100 100 q",
+                true,
+                "100 100 l 100 50 o 50 0 o 0 0 q",
+            ),
+            // Open path: the last point becomes the move
+            (
+                "0 0 m 50 0 o 100 50 o 100 100 c 100 200 l",
+                false,
+                "100 200 m 100 100 l 100 50 o 50 0 o 0 0 c",
+            ),
+        ];
+        for (nodes, closed, expected) in cases {
+            let original = path_from(nodes, closed);
+            let mut path = original.clone();
+            path.reverse();
+            assert_eq!(nodes_of(&path), expected);
+            assert_eq!(path.closed, closed);
+            path.reverse();
+            assert_eq!(path, original);
+        }
+    }
+
     #[test]
     fn test_to_kurbo() {
         let path: Path = serde_json::from_str(r#"
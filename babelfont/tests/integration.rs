@@ -189,3 +189,219 @@ fn test_ufo_export_unifies_glyphs3_rtl_kerning() {
         Some(-80.0)
     );
 }
+
+#[test]
+fn test_ttf_roundtrip_resolves_kerning_per_master() {
+    let path = "resources/RadioCanadaDisplay.babelfont";
+    let font = load(path).expect("Failed to load babelfont");
+
+    use babelfont::convertors::fontir::CompilationOptions;
+    let bytes = babelfont::convertors::fontir::BabelfontIrSource::compile(
+        font,
+        CompilationOptions::default(),
+    )
+    .expect("Failed to compile to TTF");
+    let dir = tempfile::tempdir().unwrap();
+    let ttf_path = dir.path().join("RadioCanadaDisplay.ttf");
+    std::fs::write(&ttf_path, bytes).unwrap();
+
+    let loaded = babelfont::convertors::ttf::load(&ttf_path).expect("Failed to load TTF");
+    assert!(loaded.masters.len() > 1);
+    // Kerning comes back as data on each master, not as feature code
+    for master in loaded.masters.iter() {
+        assert!(!master.kerning.is_empty());
+    }
+    assert!(!loaded
+        .features
+        .features
+        .iter()
+        .any(|(name, _)| name == "kern"));
+    // Everything uncompiled from the binary can be regenerated
+    assert!(loaded
+        .features
+        .features
+        .iter()
+        .all(|(_, code)| code.automatic));
+    assert!(loaded.features.prefixes.values().all(|code| code.automatic));
+    // Kerning values differ between the extremes of the weight axis
+    let first = &loaded.masters[0].kerning;
+    let last = &loaded.masters[loaded.masters.len() - 1].kerning;
+    assert!(first
+        .iter()
+        .any(|(pair, value)| last.get(pair) != Some(value)));
+}
+
+/// Compile a font to a TTF and load it back, naming anchors after the source's
+fn ttf_roundtrip(font: babelfont::Font) -> babelfont::Font {
+    use babelfont::convertors::{fontir, ttf};
+    let options = sr_aef::UncompileOptions {
+        anchor_name_hints: ttf::anchor_name_hints(&font),
+        ..Default::default()
+    };
+    let bytes =
+        fontir::BabelfontIrSource::compile(font, fontir::CompilationOptions::default()).unwrap();
+    ttf::load_bytes_with_options(&bytes, options).expect("Failed to load TTF")
+}
+
+#[test]
+fn test_ttf_roundtrip_resolves_anchors_per_master() {
+    let font = load("resources/RadioCanadaDisplay.babelfont").unwrap();
+    let top_x = |font: &babelfont::Font| {
+        let mut xs = font
+            .masters
+            .iter()
+            .map(|master| {
+                let layer = font.master_layer_for("A", master).unwrap();
+                let top = layer.anchors.iter().find(|a| a.name == "top").unwrap();
+                top.x.round() as i32
+            })
+            .collect::<Vec<_>>();
+        xs.sort();
+        xs
+    };
+    let expected = top_x(&font);
+    assert_eq!(expected, vec![331, 337]);
+    assert_eq!(top_x(&ttf_roundtrip(font)), expected);
+}
+
+#[test]
+fn test_ttf_roundtrip_features_recompile() {
+    // Lookups converted to kerning must leave no dangling references behind
+    let loaded = ttf_roundtrip(load("resources/RadioCanadaDisplay.babelfont").unwrap());
+    babelfont::convertors::fontir::BabelfontIrSource::compile(
+        loaded,
+        babelfont::convertors::fontir::CompilationOptions::default(),
+    )
+    .expect("Failed to recompile uncompiled features");
+}
+
+#[test]
+fn test_ttf_roundtrip_keeps_zero_kerning_exceptions() {
+    let mut font = load("resources/RadioCanadaDisplay.babelfont").unwrap();
+    // Find a group pair kerned in every master, and cancel it for two of its members
+    let (left, right) = font.masters[0]
+        .kerning
+        .keys()
+        .filter_map(|(l, r)| Some((l.strip_prefix('@')?, r.strip_prefix('@')?)))
+        .find(|(l, r)| {
+            let pair: (babelfont::SmolStr, babelfont::SmolStr) =
+                (format!("@{}", l).into(), format!("@{}", r).into());
+            font.masters
+                .iter()
+                .all(|m| m.kerning.get(&pair).is_some_and(|v| *v != 0))
+        })
+        .map(|(l, r)| {
+            (
+                font.first_kern_groups[l][0].clone(),
+                font.second_kern_groups[r][0].clone(),
+            )
+        })
+        .unwrap();
+    for master in font.masters.iter_mut() {
+        master.kerning.insert((left.clone(), right.clone()), 0);
+    }
+
+    let loaded = ttf_roundtrip(font);
+    for master in loaded.masters.iter() {
+        assert_eq!(loaded.kerning(master, &left, &right), 0);
+    }
+}
+
+#[test]
+fn test_instantiate_static_font() {
+    let font = load("resources/IbarraRealNova.designspace").expect("Failed to load designspace");
+    let location = babelfont::DesignLocation::from(vec![
+        (Tag::new(b"wght"), babelfont::DesignCoord::new(600.0)),
+        (Tag::new(b"ital"), babelfont::DesignCoord::new(0.0)),
+    ]);
+    let instance = font.instantiate(&location).expect("Failed to instantiate");
+    assert!(instance.axes.is_empty());
+    assert_eq!(instance.masters.len(), 1);
+    let master = &instance.masters[0];
+    for glyph in instance.glyphs.iter() {
+        assert!(instance.master_layer_for(&glyph.name, master).is_some());
+    }
+    // Advance widths fall between those of the regular and bold
+    let width = |font: &babelfont::Font, master: &babelfont::Master| {
+        font.master_layer_for("n", master).unwrap().width
+    };
+    let regular = width(&font, &font.masters[0]);
+    let bold = width(&font, &font.masters[1]);
+    let middle = width(&instance, master);
+    assert!(middle > regular.min(bold) && middle < regular.max(bold));
+    // Metrics and kerning are two-thirds of the way from regular to bold
+    assert_eq!(master.metrics[&babelfont::MetricType::XHeight], 437);
+    assert_eq!(font.kerning(&font.masters[0], "B", "J"), -30);
+    assert_eq!(font.kerning(&font.masters[1], "B", "J"), -45);
+    assert_eq!(instance.kerning(master, "B", "J"), -40);
+    // Named after the SemiBold instance at this location
+    let name = |name: &babelfont::I18NDictionary| name.get_default().cloned();
+    assert_eq!(
+        name(&instance.names.family_name).as_deref(),
+        Some("Ibarra Real Nova SemiBold")
+    );
+    assert_eq!(
+        name(&instance.names.preferred_subfamily_name).as_deref(),
+        Some("Regular")
+    );
+    assert_eq!(
+        name(&instance.names.typographic_family).as_deref(),
+        Some("Ibarra Real Nova")
+    );
+    assert_eq!(
+        name(&instance.names.typographic_subfamily).as_deref(),
+        Some("SemiBold")
+    );
+    assert_eq!(
+        name(&instance.names.full_name).as_deref(),
+        Some("Ibarra Real Nova SemiBold")
+    );
+    assert_eq!(
+        name(&instance.names.postscript_name).as_deref(),
+        Some("IbarraRealNova-SemiBold")
+    );
+}
+
+#[test]
+fn test_variable_ligature_carets_compile() {
+    use babelfont::convertors::fontir::{BabelfontIrSource, CompilationOptions};
+    use write_fonts::read::{tables::gdef::CaretValue, FontRef, TableProvider};
+
+    let mut font = load("resources/RadioCanadaDisplay.babelfont").unwrap();
+    let master_ids: Vec<String> = font.masters.iter().map(|m| m.id.clone()).collect();
+    let glyph = font.glyphs.get_mut("f_i").unwrap();
+    for layer in glyph.layers.iter_mut() {
+        let Some(index) = master_ids
+            .iter()
+            .position(|id| layer.master == babelfont::LayerType::DefaultForMaster(id.clone()))
+        else {
+            continue;
+        };
+        let caret = layer
+            .anchors
+            .iter_mut()
+            .find(|a| a.name == "caret_1")
+            .unwrap();
+        caret.x = 250.0 + 30.0 * index as f64;
+    }
+
+    let bytes = BabelfontIrSource::compile(font, CompilationOptions::default())
+        .expect("Failed to compile to TTF");
+    let font_ref = FontRef::new(&bytes).expect("Failed to read font bytes");
+    let carets = font_ref
+        .gdef()
+        .expect("No GDEF table")
+        .lig_caret_list()
+        .expect("No ligature caret list")
+        .unwrap();
+    assert!(carets.lig_glyph_count() > 0);
+    // Carets which vary are coordinates with a variation index
+    let variable = carets.lig_glyphs().iter().any(|lig_glyph| {
+        lig_glyph
+            .unwrap()
+            .caret_values()
+            .iter()
+            .any(|caret| matches!(caret, Ok(CaretValue::Format3(_))))
+    });
+    assert!(variable);
+}
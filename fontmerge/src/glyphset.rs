@@ -1,5 +1,5 @@
 use crate::args::ExistingGlyphHandling;
-use babelfont::{Font, SmolStr};
+use babelfont::{ComponentGraph, Font, SmolStr};
 use indexmap::{IndexMap, IndexSet};
 
 pub struct GlyphsetFilter {
@@ -153,27 +153,14 @@ impl GlyphsetFilter {
     }
 
     pub(crate) fn close_components(&mut self, font_2: &Font) {
+        let graph = font_2.component_graph();
         for glyph in self.incoming_glyphset.clone().iter() {
-            self._close_components(glyph, font_2);
+            self._close_components(glyph, &graph);
         }
     }
 
-    fn _close_components(&mut self, glyph_name: &str, font_2: &Font) {
-        let Some(glyph) = font_2.glyphs.get(glyph_name) else {
-            return;
-        };
-        let component_set = glyph
-            .layers
-            .iter()
-            .flat_map(|layer| layer.shapes.iter())
-            .filter_map(|shape| match shape {
-                babelfont::Shape::Component(comp) => Some(comp.reference.clone()),
-                _ => None,
-            })
-            .collect::<IndexSet<SmolStr>>();
-        if component_set.is_empty() {
-            return;
-        }
+    fn _close_components(&mut self, glyph_name: &str, graph: &ComponentGraph) {
+        let component_set: Vec<SmolStr> = graph.components_of(glyph_name).cloned().collect();
         for component_name in component_set.iter() {
             if self.incoming_glyphset.contains(component_name) {
                 continue;
@@ -187,7 +174,7 @@ impl GlyphsetFilter {
                     );
 
                     self.incoming_glyphset.insert(component_name.clone());
-                    self._close_components(component_name, font_2);
+                    self._close_components(component_name, graph);
                 } else {
                     log::warn!(
                         "Component glyph '{}' used in glyph '{}' is already present in font 1, not replacing it",
@@ -203,7 +190,7 @@ impl GlyphsetFilter {
                 );
                 self.incoming_glyphset.insert(component_name.clone());
                 // Recursively check components of this component
-                self._close_components(component_name, font_2);
+                self._close_components(component_name, graph);
             }
         }
     }
@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use crate::UncompileContext;
+use crate::{SimpleUserLocation, UncompileContext};
 use fea_rs_ast::{
     Anchor as FeaAnchor, CursivePosStatement, GlyphClass, GlyphContainer, LookupBlock,
     MarkBasePosStatement, MarkClass, MarkLigPosStatement, MarkMarkPosStatement, Metric,
@@ -150,8 +150,24 @@ impl<'a> UncompileContext<'a> {
                 )
             })
             .transpose()?;
-        let x_advance = value_record.x_advance().map(Metric::from);
-        let y_advance = value_record.y_advance().map(Metric::from);
+        let x_advance = value_record
+            .x_advance()
+            .map(|vr| {
+                self.resolve_pos_with_variations(
+                    vr,
+                    value_record.x_advance_device(parent_offset_data),
+                )
+            })
+            .transpose()?;
+        let y_advance = value_record
+            .y_advance()
+            .map(|vr| {
+                self.resolve_pos_with_variations(
+                    vr,
+                    value_record.y_advance_device(parent_offset_data),
+                )
+            })
+            .transpose()?;
         Ok(FeaValueRecord::new(
             x_placement,
             y_placement,
@@ -669,6 +685,15 @@ impl<'a> UncompileContext<'a> {
                 xs.push(x_percentage);
                 ys.push(y_percentage);
             }
+            // If we were told what these anchors are called, believe it.
+            if let Some(name) = self.hinted_anchor_name(base_glyphs_anchors)
+                && self.anchors.get(&name).is_none()
+            {
+                self.anchors
+                    .insert(name.clone(), base_glyphs_anchors.clone());
+                new_names.push(name);
+                continue;
+            }
             // Now guess: if they're all majority in top, topright, topleft, bottom, bottomright, bottomleft, center, etc
             // in order if not already registered in the "anchors" field.
             if let Some(name) = majority_in_quadrant(&xs, &ys)
@@ -687,6 +712,46 @@ impl<'a> UncompileContext<'a> {
         }
         new_names
     }
+
+    /// Find the hinted anchor name which matches the majority of these glyph anchors
+    fn hinted_anchor_name(&self, glyphs_anchors: &IndexMap<SmolStr, FeaAnchor>) -> Option<SmolStr> {
+        let mut votes: IndexMap<&SmolStr, usize> = IndexMap::new();
+        for (glyph, anchor) in glyphs_anchors {
+            let position = self.default_anchor_location(anchor);
+            for (name, hinted_glyphs) in self.options.anchor_name_hints.iter() {
+                if hinted_glyphs.get(glyph) == Some(&position) {
+                    *votes.entry(name).or_default() += 1;
+                }
+            }
+        }
+        let (name, count) = votes.into_iter().max_by_key(|(_, count)| *count)?;
+        if count * 2 > glyphs_anchors.len() {
+            Some(name.clone())
+        } else {
+            None
+        }
+    }
+
+    /// The position of an anchor at the default location
+    fn default_anchor_location(&self, anchor: &FeaAnchor) -> (i16, i16) {
+        let default_location: SimpleUserLocation = self
+            .axes
+            .iter()
+            .flat_map(|axes| axes.iter())
+            .map(|axis| (axis.tag.to_string().into(), axis.default.to_f64() as i16))
+            .collect();
+        let at_default = |metric: &Metric| match metric {
+            Metric::Scalar(s) => *s,
+            Metric::Variable(items) => items
+                .iter()
+                .find(|(loc, _)| loc == &default_location)
+                .or(items.first())
+                .map(|(_, value)| *value)
+                .unwrap_or_default(),
+            Metric::GlyphsAppNumber(_) => 0,
+        };
+        (at_default(&anchor.x), at_default(&anchor.y))
+    }
 }
 
 fn anchor_location(anchor: &FeaAnchor) -> (f32, f32) {
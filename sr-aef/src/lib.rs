@@ -6,9 +6,9 @@ use std::collections::{HashMap, HashSet};
 /// The return value of uncompile() will be a [fea_rs_ast::FeatureFile]; you will probably want to call `.as_fea()` on it.
 pub use fea_rs_ast;
 use fea_rs_ast::{
-    Anchor, GlyphClass, GlyphClassDefStatement, GlyphClassDefinition, GlyphContainer, GlyphName,
-    LanguageSystemStatement, LookupBlock, LookupFlagStatement, LookupReferenceStatement, MarkClass,
-    MarkClassDefinition, Pos, Statement, SubOrPos, Subst, ToplevelItem,
+    Anchor, AsFea, GlyphClass, GlyphClassDefStatement, GlyphClassDefinition, GlyphContainer,
+    GlyphName, LanguageSystemStatement, LookupBlock, LookupFlagStatement, LookupReferenceStatement,
+    MarkClass, MarkClassDefinition, Pos, Statement, SubOrPos, Subst, ToplevelItem,
 };
 use indexmap::{IndexMap, IndexSet};
 /// A handle to the version of Skrifa that sr-eaf is using. Pass a skrifa::FontRef to uncompile()
@@ -38,7 +38,47 @@ mod variations;
 
 pub(crate) type SimpleUserLocation = IndexMap<SmolStr, i16>; // as used by fea-rs-ast metrics
 
-const PROMOTE_TO_NAMED_CLASS_THRESHOLD: usize = 5;
+/// How lookups in the uncompiled output are named.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LookupNaming {
+    /// Name lookups after their type, numbered in order of appearance: `gsub_single_1`, `gpos_pair_2`, ...
+    #[default]
+    ByType,
+    /// Name lookups after their index in the lookup list: `sub_lookup_0`, `pos_lookup_12`, ...
+    ///
+    /// Names are stable as long as the lookup list order doesn't change.
+    ByIndex,
+}
+
+/// Options controlling how a font is uncompiled.
+#[derive(Debug, Clone)]
+pub struct UncompileOptions {
+    /// Coverage tables with at least this many glyphs are promoted to named classes.
+    ///
+    /// Set to `usize::MAX` to never promote.
+    pub promote_to_named_class_threshold: usize,
+    /// How to name lookups.
+    pub lookup_naming: LookupNaming,
+    /// Known anchors, used to name mark classes before falling back to guessing.
+    ///
+    /// This maps an anchor name (without leading underscore) to the glyphs which carry that
+    /// anchor, and its position at the default location. Typically this comes from a source
+    /// font for the binary being uncompiled.
+    pub anchor_name_hints: IndexMap<SmolStr, IndexMap<SmolStr, (i16, i16)>>,
+    /// Whether to merge lookups which have identical contents.
+    pub deduplicate_lookups: bool,
+}
+
+impl Default for UncompileOptions {
+    fn default() -> Self {
+        Self {
+            promote_to_named_class_threshold: 5,
+            lookup_naming: LookupNaming::default(),
+            anchor_name_hints: IndexMap::new(),
+            deduplicate_lookups: false,
+        }
+    }
+}
 
 /// The context object that holds all the information we need when uncompiling a font.
 #[cfg_attr(feature = "cli", derive(serde::Serialize))]
@@ -95,10 +135,12 @@ pub struct UncompileContext<'a> {
     #[cfg_attr(feature = "cli", serde(skip))]
     axes: Option<fontdrasil::types::Axes>,
     num_glyphs: u16,
+    #[cfg_attr(feature = "cli", serde(skip))]
+    options: UncompileOptions,
 }
 
 impl<'a> UncompileContext<'a> {
-    fn new(font: &'a skrifa::FontRef) -> Result<Self, ReadError> {
+    fn new(font: &'a skrifa::FontRef, options: UncompileOptions) -> Result<Self, ReadError> {
         let glyph_names = GlyphNames::new(font);
         let default = LocationRef::default();
         let glyph_metrics = GlyphMetrics::new(font, Size::unscaled(), default);
@@ -144,10 +186,14 @@ impl<'a> UncompileContext<'a> {
                 .unwrap_or_default(),
             axes: variations::fontdrasil_axes(font)?,
             num_glyphs: glyph_names.num_glyphs() as u16,
+            options,
         };
         slf.gather_language_systems()?;
         slf.uncompile_gsub_lookups()?;
         slf.uncompile_gpos_lookups()?;
+        if slf.options.deduplicate_lookups {
+            slf.deduplicate_lookups();
+        }
         slf.uncompile_feature_table()?;
         Ok(slf)
     }
@@ -244,7 +290,7 @@ impl<'a> UncompileContext<'a> {
         }
         let glyphclass = GlyphClass::new(glyphs.clone(), 0..0);
 
-        if glyphs.len() >= PROMOTE_TO_NAMED_CLASS_THRESHOLD {
+        if glyphs.len() >= self.options.promote_to_named_class_threshold {
             // Have we seen this exact set of glyphs before (in order)? If so, reuse the same class. Otherwise, make a new one.
             if let Some(_class_name) = self
                 .named_classes
@@ -302,7 +348,10 @@ impl<'a> UncompileContext<'a> {
         index: u16,
         phase: T,
     ) -> LookupBlock {
-        let name = self.gensym(prefix);
+        let name = match self.options.lookup_naming {
+            LookupNaming::ByType => self.gensym(prefix),
+            LookupNaming::ByIndex => format!("{}_lookup_{}", phase, index).into(),
+        };
         self.lookup_map
             .insert((phase.to_string(), index), name.clone());
         LookupBlock::new(name.clone(), vec![], false, 0..0)
@@ -315,6 +364,64 @@ impl<'a> UncompileContext<'a> {
             .unwrap_or_else(|| format!("{}_lookup_{}", phase, lookup_list_index).into())
     }
 
+    /// Merge lookups with identical contents, keeping the first and pointing all references at it.
+    ///
+    /// Repeats until nothing changes, so that contextual lookups which only differed in
+    /// which of a set of duplicates they referenced are merged too.
+    fn deduplicate_lookups(&mut self) {
+        loop {
+            let phases: HashMap<SmolStr, String> = self
+                .lookup_map
+                .iter()
+                .map(|((phase, _), name)| (name.clone(), phase.clone()))
+                .collect();
+            let mut seen: HashMap<(String, String), SmolStr> = HashMap::new();
+            let mut renames: HashMap<SmolStr, SmolStr> = HashMap::new();
+            for (name, lookup) in self.lookups.iter() {
+                if lookup.statements.is_empty() {
+                    continue;
+                }
+                let body = lookup
+                    .statements
+                    .iter()
+                    .map(|s| s.as_fea(""))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                let key = (phases.get(name).cloned().unwrap_or_default(), body);
+                if let Some(canonical) = seen.get(&key) {
+                    renames.insert(name.clone(), canonical.clone());
+                } else {
+                    seen.insert(key, name.clone());
+                }
+            }
+            if renames.is_empty() {
+                return;
+            }
+            self.lookups.retain(|name, _| !renames.contains_key(name));
+            for name in self.lookup_map.values_mut() {
+                if let Some(canonical) = renames.get(name) {
+                    *name = canonical.clone();
+                }
+            }
+            let rename_all = |lookups: &mut Vec<Vec<SmolStr>>| {
+                for name in lookups.iter_mut().flatten() {
+                    if let Some(canonical) = renames.get(name) {
+                        *name = canonical.clone();
+                    }
+                }
+            };
+            for lookup in self.lookups.values_mut() {
+                for statement in lookup.statements.iter_mut() {
+                    match statement {
+                        Statement::ChainedContextSubst(s) => rename_all(&mut s.lookups),
+                        Statement::ChainedContextPos(s) => rename_all(&mut s.lookups),
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+
     fn uncompile_gdef(&mut self) -> Result<Vec<ToplevelItem>, ReadError> {
         let mut items = vec![];
         let mut base_glyphs = vec![];
@@ -448,7 +555,16 @@ pub fn uncompile(
     font: &skrifa::FontRef,
     do_gdef: bool,
 ) -> Result<fea_rs_ast::FeatureFile, ReadError> {
-    let mut context = UncompileContext::new(font)?;
+    uncompile_with_options(font, do_gdef, UncompileOptions::default())
+}
+
+/// Uncompile a TTF font into a fea file, with options. See uncompile() for details.
+pub fn uncompile_with_options(
+    font: &skrifa::FontRef,
+    do_gdef: bool,
+    options: UncompileOptions,
+) -> Result<fea_rs_ast::FeatureFile, ReadError> {
+    let mut context = UncompileContext::new(font, options)?;
 
     let mut ff = fea_rs_ast::FeatureFile::new(vec![]);
     ff.statements.extend(context.dump_language_systems());
@@ -506,7 +622,15 @@ pub fn uncompile_bytes(
 /// put them where you want them. Useful for font editors and other tools that want the
 /// data but don't want to go all the way to a fea file.
 pub fn uncompile_context<'a>(font: &'a skrifa::FontRef) -> Result<UncompileContext<'a>, ReadError> {
-    UncompileContext::new(font)
+    UncompileContext::new(font, UncompileOptions::default())
+}
+
+/// Uncompile a TTF font to a context object, with options. See uncompile_context() for details.
+pub fn uncompile_context_with_options<'a>(
+    font: &'a skrifa::FontRef,
+    options: UncompileOptions,
+) -> Result<UncompileContext<'a>, ReadError> {
+    UncompileContext::new(font, options)
 }
 
 #[cfg(test)]
@@ -524,4 +648,113 @@ mod tests {
             "GlyphClassDef [A], [], [grave acute dotbelowcomb], [];\nmarkClass grave <anchor 200 150> @bottomleft;\nmarkClass acute <anchor 350 0> @bottomleft;\nmarkClass dotbelowcomb <anchor 200 -200> @mark_class_1_1;\nlookup gsub_single_1 {\n    sub a by b;\n} gsub_single_1;\nlookup gsub_multiple_1 {\n    sub a by b c;\n} gsub_multiple_1;\nlookup gsub_alternate_1 {\n    sub a from [b c d e f];\n} gsub_alternate_1;\nlookup gsub_ligature_1 {\n    sub b c by a;\n} gsub_ligature_1;\nlookup gsub_contextual_1 {\n    sub [one a]' lookup gsub_single_1 b' [two c]' lookup gsub_multiple_1;\n} gsub_contextual_1;\nlookup gsub_chain_contextual_1 {\n    sub one two three a' lookup gsub_single_1 b' c' lookup gsub_multiple_1 x y z;\n} gsub_chain_contextual_1;\nlookup gpos_mark_to_base_1 {\n    pos base A\n        <anchor 150 100> mark @bottomleft\n        <anchor -200 -200> mark @mark_class_1_1;\n} gpos_mark_to_base_1;\n"
         );
     }
+
+    #[test]
+    fn test_lookup_naming_by_index() {
+        let data = std::fs::read("resources/test.ttf").unwrap();
+        let fontref = skrifa::FontRef::new(&data).unwrap();
+        let options = UncompileOptions {
+            lookup_naming: LookupNaming::ByIndex,
+            ..Default::default()
+        };
+        let context = uncompile_context_with_options(&fontref, options).unwrap();
+        assert!(!context.lookup_map.is_empty());
+        for ((phase, index), name) in context.lookup_map.iter() {
+            assert_eq!(name.as_str(), format!("{}_lookup_{}", phase, index));
+        }
+    }
+
+    #[test]
+    fn test_anchor_name_hints() {
+        let data = std::fs::read("resources/test.ttf").unwrap();
+        let fontref = skrifa::FontRef::new(&data).unwrap();
+        let mut options = UncompileOptions::default();
+        options.anchor_name_hints.insert(
+            "top".into(),
+            IndexMap::from([(SmolStr::from("A"), (150, 100))]),
+        );
+        let context = uncompile_context_with_options(&fontref, options).unwrap();
+        assert!(context.mark_classes.contains_key("top"));
+        assert!(!context.mark_classes.contains_key("bottomleft"));
+    }
+
+    #[test]
+    fn test_promote_to_named_class_threshold() {
+        let data = std::fs::read("resources/test.ttf").unwrap();
+        let fontref = skrifa::FontRef::new(&data).unwrap();
+        let options = UncompileOptions {
+            promote_to_named_class_threshold: 2,
+            ..Default::default()
+        };
+        let context = uncompile_context_with_options(&fontref, options).unwrap();
+        let promoted: Vec<String> = context
+            .named_classes
+            .values()
+            .map(|class| class.as_fea(""))
+            .collect();
+        assert!(promoted.contains(&"[one a]".to_string()));
+        assert!(promoted.contains(&"[two c]".to_string()));
+
+        let options = UncompileOptions {
+            promote_to_named_class_threshold: usize::MAX,
+            ..Default::default()
+        };
+        let context = uncompile_context_with_options(&fontref, options).unwrap();
+        assert!(context.named_classes.is_empty());
+    }
+
+    #[test]
+    fn test_deduplicate_lookups() {
+        let data = std::fs::read("resources/test.ttf").unwrap();
+        let fontref = skrifa::FontRef::new(&data).unwrap();
+        let mut context =
+            uncompile_context_with_options(&fontref, UncompileOptions::default()).unwrap();
+        let phase_of = |context: &UncompileContext, name: &str| {
+            context
+                .lookup_map
+                .keys()
+                .find(|key| context.lookup_map[*key].as_str() == name)
+                .map(|(phase, _)| phase.clone())
+                .unwrap()
+        };
+        let phase = phase_of(&context, "gsub_single_1");
+        assert_eq!(phase, phase_of(&context, "gsub_contextual_1"));
+        // A copy of a single substitution, and a copy of the contextual lookup which
+        // refers to the copy; only the second pass can see the latter is a duplicate.
+        let mut single = context.lookups["gsub_single_1"].clone();
+        single.name = "copy_single".into();
+        let mut contextual = context.lookups["gsub_contextual_1"].clone();
+        contextual.name = "copy_contextual".into();
+        for statement in contextual.statements.iter_mut() {
+            if let Statement::ChainedContextSubst(s) = statement {
+                for name in s.lookups.iter_mut().flatten() {
+                    if name.as_str() == "gsub_single_1" {
+                        *name = "copy_single".into();
+                    }
+                }
+            }
+        }
+        assert_ne!(
+            contextual.as_fea(""),
+            context.lookups["gsub_contextual_1"].as_fea("")
+        );
+        for (index, lookup) in [(1000, single), (1001, contextual)] {
+            context
+                .lookup_map
+                .insert((phase.clone(), index), lookup.name.clone());
+            context.lookups.insert(lookup.name.clone(), lookup);
+        }
+
+        context.deduplicate_lookups();
+        assert!(!context.lookups.contains_key("copy_single"));
+        assert!(!context.lookups.contains_key("copy_contextual"));
+        assert_eq!(
+            context.lookup_map[&(phase.clone(), 1000)].as_str(),
+            "gsub_single_1"
+        );
+        assert_eq!(
+            context.lookup_map[&(phase, 1001)].as_str(),
+            "gsub_contextual_1"
+        );
+    }
 }